@@ -22,9 +22,13 @@ function playSound() {
 }
 
 class ChainsState {
-  constructor(sources, chains) {
+  constructor(sources, chains, urls) {
     this.sources = sources;
     this.chains = chains;
+    this.urls = {};
+    for (const url of (urls || [])) {
+      this.urls[this.enableSoundForIdx(url.source, url.chain)] = url.url;
+    }
     this.states = [];
     this.bestHeight = Array(chains.length).fill(0);
     // max time between the source updates that backend can guarantee
@@ -77,6 +81,14 @@ class ChainsState {
     }
   }
 
+  getBlockUrl(source, chain, chainState) {
+    const url = this.urls[this.enableSoundForIdx(source, chain)];
+    if (url === undefined) {
+      return undefined;
+    }
+    return url.replace('{height}', chainState.height).replace('{hash}', chainState.hash);
+  }
+
   getIdxByIds(source, chain) {
     return this.getIdx(
       this.sources.findIndex((element) => element.id === source),
@@ -84,10 +96,25 @@ class ChainsState {
     );
   }
 
+  hasSource(source) {
+    return this.sources.some((element) => element.id === source);
+  }
+
   getIdx(sourceIdx, chainIdx) {
     return sourceIdx * this.chains.length + chainIdx;
   }
 
+  // sources of mirrors stay registered once the mirror stops relaying them,
+  // so don't show the ones without any state left
+  sourceHasAnyState(sourceIdx) {
+    for (var chainIdx = 0; chainIdx < this.chains.length; chainIdx++) {
+      if (this.states[this.getIdx(sourceIdx, chainIdx)]) {
+        return true;
+      }
+    }
+    return false;
+  }
+
   getChainIdx(chain) {
     return this.chains.findIndex((element) => element.id === chain);
   }
@@ -139,6 +166,9 @@ class ChainsState {
       }
 
       for (var sourceIdx = 0; sourceIdx < this.sources.length; sourceIdx++){
+        if (!this.sourceHasAnyState(sourceIdx)) {
+          continue;
+        }
         const th = document.createElement('th');
         const div = document.createElement('div');
         div.setAttribute('scope', 'col');
@@ -180,6 +210,9 @@ class ChainsState {
       }
 
       for (var sourceIdx = 0; sourceIdx < this.sources.length; sourceIdx++){
+        if (!this.sourceHasAnyState(sourceIdx)) {
+          continue;
+        }
         const source = this.sources[sourceIdx];
         const stateIdx = this.getIdx(sourceIdx, chainIdx);
         const chainState = this.states[stateIdx];
@@ -204,13 +237,39 @@ class ChainsState {
           const secsAgo = chainState.firstSeenTs
           div.appendChild(span);
           this.addStopOnClickPropagationToElement(span);
-          span.appendChild(document.createTextNode(`height: ${chainState.height}`));
+          span.appendChild(document.createTextNode(`${chain.heightKind === 'slot' ? 'slot' : 'height'}: ${chainState.height}`));
+          if (chainState.secondaryHeight !== undefined) {
+            span.appendChild(document.createElement('br'));
+            const secondaryLabel = { slot: 'slot', burn_block: 'burn block height' }[chain.secondaryHeightKind] || 'height';
+            span.appendChild(document.createTextNode(`${secondaryLabel}: ${chainState.secondaryHeight}`));
+          }
           span.appendChild(document.createElement('br'));
           span.appendChild(document.createTextNode(`hash:`));
           span.innerHTML += '&nbsp;';
           span.appendChild(document.createTextNode(`${chainState.hash}`));
           span.appendChild(document.createElement('br'));
           span.appendChild(document.createTextNode(`first seen: ${new Date(1000 * chainState.firstSeenTs).toISOString()} (${stalenessSecs}s ago)`));
+          if (chainState.safeHeight !== undefined) {
+            span.appendChild(document.createElement('br'));
+            span.appendChild(document.createTextNode(`safe: ${chainState.safeHeight}`));
+          }
+          if (chainState.finalizedHeight !== undefined) {
+            span.appendChild(document.createElement('br'));
+            span.appendChild(document.createTextNode(`finalized: ${chainState.finalizedHeight}`));
+          }
+          if (chainState.blockTime) {
+            span.appendChild(document.createElement('br'));
+            span.appendChild(document.createTextNode(`block time: ${new Date(1000 * chainState.blockTime).toISOString()} (${Math.round(nowTs - chainState.blockTime)}s ago)`));
+          }
+          const blockUrl = this.getBlockUrl(source.id, chain.id, chainState);
+          if (blockUrl !== undefined) {
+            span.appendChild(document.createElement('br'));
+            const a = document.createElement('a');
+            a.href = blockUrl;
+            a.target = '_blank';
+            a.appendChild(document.createTextNode('open in explorer'));
+            span.appendChild(a);
+          }
           span.classList.add('tooltiptext');
 
           if (diff >= -1) {
@@ -259,6 +318,11 @@ class App {
 
     var url = new URL('/ws', window.location.href);
     url.protocol = url.protocol.replace('http', 'ws');
+    // pass on the token the page was opened with, if any
+    const token = new URLSearchParams(window.location.search).get('token');
+    if (token) {
+      url.searchParams.set('token', token);
+    }
     const socket = new WebSocket(url);
 
     const app = this;
@@ -274,9 +338,15 @@ class App {
       app.reconnectCount = 0;
 
       if (msg.type === 'init') {
-        app.chains = new ChainsState(msg.sources, msg.chains);
+        app.chains = new ChainsState(msg.sources, msg.chains, msg.urls);
         app.redraw();
       } else if (msg.type === 'update') {
+        if (!app.chains.hasSource(msg.source)) {
+          // registered after our `init` (e.g. relayed by a mirror), get a
+          // new one listing it
+          socket.close();
+          return;
+        }
         app.chains.update(msg.source, msg.chain, msg);
         app.redraw();
       }
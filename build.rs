@@ -0,0 +1,70 @@
+//! Build info exposed at `/version`
+use std::{
+    env, fs,
+    path::Path,
+    process::Command,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+fn main() {
+    // builds without a git checkout (e.g. Nix) can pass the commit in
+    println!("cargo:rerun-if-env-changed=GIT_COMMIT");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    watch_git_head();
+
+    let git_commit = env::var("GIT_COMMIT").ok().or_else(|| {
+        let output = Command::new("git")
+            .args(["rev-parse", "HEAD"])
+            .output()
+            .ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().to_owned())
+    });
+    println!(
+        "cargo:rustc-env=CHAIN_MONITOR_GIT_COMMIT={}",
+        git_commit.unwrap_or_default()
+    );
+
+    // honor reproducible builds
+    let build_ts = env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or_default()
+        });
+    println!("cargo:rustc-env=CHAIN_MONITOR_BUILD_TS={build_ts}");
+
+    let mut features: Vec<_> = env::vars()
+        .filter_map(|(name, _)| {
+            name.strip_prefix("CARGO_FEATURE_")
+                .map(|feature| feature.to_lowercase().replace('_', "-"))
+        })
+        .collect();
+    features.sort();
+    println!(
+        "cargo:rustc-env=CHAIN_MONITOR_FEATURES={}",
+        features.join(",")
+    );
+}
+
+/// Rerun on new commits, not just on checking out another branch
+fn watch_git_head() {
+    let git_dir = Path::new(".git");
+    let mut watched = vec![git_dir.join("HEAD"), git_dir.join("packed-refs")];
+    // commits update the ref HEAD points to, e.g. `refs/heads/master`
+    if let Some(head_ref) = fs::read_to_string(git_dir.join("HEAD"))
+        .ok()
+        .and_then(|head| head.strip_prefix("ref: ").map(|r| r.trim().to_owned()))
+    {
+        watched.push(git_dir.join(head_ref));
+    }
+    // a missing file would rerun the build script on every build
+    for path in watched.iter().filter(|path| path.exists()) {
+        println!("cargo:rerun-if-changed={}", path.display());
+    }
+}
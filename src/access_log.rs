@@ -0,0 +1,56 @@
+//! Request ids and access logs of the HTTP (and websocket) requests
+use std::time::Instant;
+
+use axum::{
+    http::{HeaderValue, Request},
+    middleware::Next,
+    response::IntoResponse,
+};
+use rand::{thread_rng, Rng};
+use tracing::Instrument;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Id of the request being handled, available as an extension
+#[derive(Clone, Debug)]
+pub struct RequestId(pub String);
+
+impl RequestId {
+    /// Take the id assigned by a proxy in front of us, or make up a new one
+    fn of<B>(req: &Request<B>) -> Self {
+        req.headers()
+            .get(REQUEST_ID_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .filter(|id| !id.is_empty() && id.len() <= 64)
+            .map(|id| Self(id.to_owned()))
+            .unwrap_or_else(|| Self(format!("{:016x}", thread_rng().gen::<u64>())))
+    }
+}
+
+/// Run every request in a span with its id, returned in the `x-request-id`
+/// header, and log a record of it once handled
+pub async fn log_requests<B>(mut req: Request<B>, next: Next<B>) -> impl IntoResponse {
+    let start = Instant::now();
+    let request_id = RequestId::of(&req);
+    let path = req.uri().path().to_owned();
+    let method = req.method().clone();
+
+    let span = tracing::info_span!("request", request_id = %request_id.0);
+    req.extensions_mut().insert(request_id.clone());
+    let mut response = next.run(req).instrument(span.clone()).await;
+
+    span.in_scope(|| {
+        tracing::info!(
+            method = %method,
+            path = %path,
+            status = response.status().as_u16(),
+            duration_ms = start.elapsed().as_millis() as u64,
+            "handled request"
+        )
+    });
+    if let Ok(value) = HeaderValue::from_str(&request_id.0) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    response
+}
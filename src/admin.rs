@@ -0,0 +1,186 @@
+//! Endpoints changing the behavior of a running instance, behind `--admin-token`
+use std::{str::FromStr, sync::Arc};
+
+use axum::{
+    extract::{Extension, Path, Query},
+    http::{header, Request, StatusCode},
+    middleware::{self, Next},
+    response::IntoResponse,
+    routing::{delete, post},
+    Json, Router,
+};
+
+use serde::Deserialize;
+
+use crate::{
+    silence::{self, Silence},
+    source::{ChainId, SourceId},
+    webhook::{NewWebhook, Webhook},
+    AppState,
+};
+
+/// Routes to nest under `/admin`, accepting only requests bearing `token`
+pub fn router(token: &str) -> Router {
+    require_token(
+        Router::new()
+            .route("/chains/:ticker/pause", post(pause_chain_handler))
+            .route("/chains/:ticker/resume", post(resume_chain_handler))
+            .route("/refresh", post(refresh_handler))
+            .route("/silence", post(silence_handler))
+            .route("/silence/:id", delete(unsilence_handler)),
+        token,
+    )
+}
+
+/// Routes to nest under `/api/v1`, accepting only requests bearing `token`
+pub fn api_router(token: &str) -> Router {
+    require_token(
+        Router::new()
+            .route(
+                "/webhooks",
+                post(add_webhook_handler).get(list_webhooks_handler),
+            )
+            .route("/webhooks/:id", delete(remove_webhook_handler)),
+        token,
+    )
+}
+
+pub fn require_token(router: Router, token: &str) -> Router {
+    let expected = format!("Bearer {token}");
+
+    router.route_layer(middleware::from_fn(
+        move |req: Request<_>, next: Next<_>| {
+            let authorized = req
+                .headers()
+                .get(header::AUTHORIZATION)
+                .map(|value| value.as_bytes())
+                == Some(expected.as_bytes());
+            async move {
+                if authorized {
+                    Ok(next.run(req).await)
+                } else {
+                    Err(StatusCode::UNAUTHORIZED)
+                }
+            }
+        },
+    ))
+}
+
+fn chain_from_ticker(ticker: &str) -> Result<ChainId, (StatusCode, String)> {
+    ChainId::from_ticker(ticker)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("unknown ticker: {ticker}")))
+}
+
+/// Stop polling and alerting on a chain, e.g. during a known halt
+async fn pause_chain_handler(
+    Path(ticker): Path<String>,
+    Extension(state): Extension<Arc<AppState>>,
+) -> impl IntoResponse {
+    let chain = chain_from_ticker(&ticker)?;
+    state.set_chain_paused(chain, true).await;
+    Ok::<_, (StatusCode, String)>(Json(state.get_paused_chains().await))
+}
+
+async fn resume_chain_handler(
+    Path(ticker): Path<String>,
+    Extension(state): Extension<Arc<AppState>>,
+) -> impl IntoResponse {
+    let chain = chain_from_ticker(&ticker)?;
+    state.set_chain_paused(chain, false).await;
+    Ok::<_, (StatusCode, String)>(Json(state.get_paused_chains().await))
+}
+
+#[derive(Deserialize)]
+struct RefreshParams {
+    chain: String,
+    source: Option<String>,
+}
+
+/// Poll a chain right away, bypassing rate limiting
+async fn refresh_handler(
+    Query(params): Query<RefreshParams>,
+    Extension(state): Extension<Arc<AppState>>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let chain = chain_from_ticker(&params.chain)?;
+    let source = params
+        .source
+        .map(|source| {
+            SourceId::from_str(&source)
+                .map_err(|_| (StatusCode::BAD_REQUEST, format!("unknown source: {source}")))
+        })
+        .transpose()?;
+
+    if state.request_refresh(chain, source).await {
+        Ok(StatusCode::ACCEPTED)
+    } else {
+        Err((
+            StatusCode::NOT_FOUND,
+            format!("chain not monitored: {}", params.chain),
+        ))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NewSilence {
+    /// Ticker of a chain, or short name of a source
+    target: String,
+    duration_secs: u64,
+    reason: String,
+}
+
+/// Don't notify of alerts on a chain, or the chains of a source, for a while
+async fn silence_handler(
+    Json(new): Json<NewSilence>,
+    Extension(state): Extension<Arc<AppState>>,
+) -> Result<(StatusCode, Json<Silence>), (StatusCode, String)> {
+    let target =
+        silence::parse_target(&new.target).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+    let silence = state
+        .add_silence(target, new.duration_secs, new.reason)
+        .await;
+    Ok((StatusCode::CREATED, Json(silence)))
+}
+
+async fn unsilence_handler(
+    Path(id): Path<u64>,
+    Extension(state): Extension<Arc<AppState>>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if state.remove_silence(id).await {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, format!("no silence {id}")))
+    }
+}
+
+fn internal_error(e: anyhow::Error) -> (StatusCode, String) {
+    (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+}
+
+async fn list_webhooks_handler(Extension(state): Extension<Arc<AppState>>) -> Json<Vec<Webhook>> {
+    Json(state.webhooks.list().await)
+}
+
+/// Register a webhook, notified of alerts firing and resolving
+async fn add_webhook_handler(
+    Json(new): Json<NewWebhook>,
+    Extension(state): Extension<Arc<AppState>>,
+) -> Result<(StatusCode, Json<Webhook>), (StatusCode, String)> {
+    state
+        .webhooks
+        .add(new)
+        .await
+        .map(|webhook| (StatusCode::CREATED, Json(webhook)))
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))
+}
+
+async fn remove_webhook_handler(
+    Path(id): Path<u64>,
+    Extension(state): Extension<Arc<AppState>>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    if state.webhooks.remove(id).await.map_err(internal_error)? {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err((StatusCode::NOT_FOUND, format!("no webhook {id}")))
+    }
+}
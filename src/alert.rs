@@ -0,0 +1,203 @@
+//! Built-in alert rules, evaluated against the recorded chain states
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use crate::source::ChainId;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum AlertKind {
+    /// Chain produced noticeably fewer blocks than expected from its block time
+    BehindSchedule,
+}
+
+/// A condition detected by one of the rules
+#[derive(Clone, Debug)]
+pub struct Firing {
+    pub chain: ChainId,
+    pub kind: AlertKind,
+    pub message: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Alert {
+    chain: ChainId,
+    ticker: &'static str,
+    kind: AlertKind,
+    message: String,
+    since_ts: u64,
+    /// How many times did the condition clear and come back before the alert
+    /// got resolved, without being notified of again
+    refirings: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    escalated_ts: Option<u64>,
+    /// Since when is the condition gone, if it is
+    #[serde(skip_serializing_if = "Option::is_none")]
+    clear_since_ts: Option<u64>,
+}
+
+/// Change of an alert
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AlertEventKind {
+    Fired,
+    /// Still firing after `--alert-escalate-after-secs`, for notifiers
+    /// subscribed to escalations only
+    Escalated,
+    Resolved,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AlertEvent {
+    pub event: AlertEventKind,
+    #[serde(flatten)]
+    pub alert: Alert,
+}
+
+impl Alert {
+    pub fn chain(&self) -> ChainId {
+        self.chain
+    }
+
+    pub fn kind(&self) -> AlertKind {
+        self.kind
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn since_ts(&self) -> u64 {
+        self.since_ts
+    }
+}
+
+/// Settings of the alert lifecycle
+#[derive(Default)]
+pub struct AlertsOpts {
+    /// For how long does a condition have to be gone for its alert to resolve;
+    /// an alert firing again before that is just the same one
+    pub resolve_after_secs: u64,
+    /// Escalate alerts that didn't resolve after this long
+    pub escalate_after_secs: Option<u64>,
+}
+
+/// Currently active alerts
+#[derive(Default)]
+pub struct Alerts {
+    opts: AlertsOpts,
+    active: HashMap<(ChainId, AlertKind), Alert>,
+}
+
+impl Alerts {
+    pub fn new(opts: AlertsOpts) -> Self {
+        Self {
+            opts,
+            active: HashMap::new(),
+        }
+    }
+
+    /// Update all alerts of `kind` with the currently `firing` ones
+    ///
+    /// Returns the alerts that fired, escalated or got resolved.
+    pub fn update(&mut self, kind: AlertKind, firing: Vec<Firing>, now: u64) -> Vec<AlertEvent> {
+        let mut events = vec![];
+        let mut still_firing = vec![];
+
+        for firing in firing {
+            debug_assert_eq!(firing.kind, kind);
+            let key = (firing.chain, firing.kind);
+            still_firing.push(key);
+            match self.active.get_mut(&key) {
+                Some(alert) => {
+                    alert.message = firing.message;
+                    if alert.clear_since_ts.take().is_some() {
+                        alert.refirings += 1;
+                        debug!(
+                            "Alert {:?} fired again for {} before resolving",
+                            kind,
+                            firing.chain.short_name()
+                        );
+                    }
+                }
+                None => {
+                    warn!(
+                        "Alert {:?} fired for {}: {}",
+                        kind,
+                        firing.chain.short_name(),
+                        firing.message
+                    );
+                    let alert = Alert {
+                        chain: firing.chain,
+                        ticker: firing.chain.ticker(),
+                        kind: firing.kind,
+                        message: firing.message,
+                        since_ts: now,
+                        refirings: 0,
+                        escalated_ts: None,
+                        clear_since_ts: None,
+                    };
+                    events.push(AlertEvent {
+                        event: AlertEventKind::Fired,
+                        alert: alert.clone(),
+                    });
+                    self.active.insert(key, alert);
+                }
+            }
+        }
+
+        let opts = &self.opts;
+        self.active.retain(|key, alert| {
+            if key.1 != kind {
+                return true;
+            }
+
+            if still_firing.contains(key) {
+                let escalate = matches!(
+                    opts.escalate_after_secs,
+                    Some(escalate_after_secs) if alert.escalated_ts.is_none()
+                        && alert.since_ts + escalate_after_secs <= now
+                );
+                if escalate {
+                    warn!("Alert {:?} escalated for {}", kind, key.0.short_name());
+                    alert.escalated_ts = Some(now);
+                    events.push(AlertEvent {
+                        event: AlertEventKind::Escalated,
+                        alert: alert.clone(),
+                    });
+                }
+                return true;
+            }
+
+            let clear_since_ts = *alert.clear_since_ts.get_or_insert(now);
+            if clear_since_ts + opts.resolve_after_secs <= now {
+                info!("Alert {:?} resolved for {}", kind, key.0.short_name());
+                events.push(AlertEvent {
+                    event: AlertEventKind::Resolved,
+                    alert: alert.clone(),
+                });
+                false
+            } else {
+                true
+            }
+        });
+        events
+    }
+
+    /// Alerts whose condition holds, leaving out the ones whose condition is
+    /// gone, even if they aren't resolved yet
+    pub fn active(&self) -> Vec<Alert> {
+        let mut active: Vec<_> = self
+            .active
+            .values()
+            .filter(|alert| alert.clear_since_ts.is_none())
+            .cloned()
+            .collect();
+        active.sort_by_key(|alert| (alert.chain, alert.since_ts));
+        active
+    }
+}
@@ -0,0 +1,224 @@
+//! The web UI, embedded in the binary
+//!
+//! Every asset is also served under a name with its content hash (e.g.
+//! `script.0123456789abcdef.js`), which is what `index.html` refers to, so
+//! browsers can cache them for good and only fetch them again after an upgrade.
+use std::{borrow::Cow, io, path::PathBuf, sync::Arc};
+
+use axum::{
+    http::{header, HeaderMap, StatusCode},
+    response::{Headers, IntoResponse, Response},
+    routing::{get, get_service},
+    Router,
+};
+use tokio::fs;
+use tower_http::services::ServeDir;
+use tracing::warn;
+
+/// FNV-1a, simple enough to be evaluated at compile time
+const fn content_hash(bytes: &[u8]) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+        i += 1;
+    }
+    hash
+}
+
+const CACHE_REVALIDATE: &str = "no-cache";
+const CACHE_IMMUTABLE: &str = "public, max-age=31536000, immutable";
+
+struct Asset {
+    /// File name, as served at the root
+    name: &'static str,
+    content_type: &'static str,
+    body: &'static [u8],
+    hash: u64,
+}
+
+macro_rules! asset {
+    ($name:literal, $content_type:literal) => {{
+        const BODY: &[u8] = include_bytes!(concat!("../assets/", $name));
+        Asset {
+            name: $name,
+            content_type: $content_type,
+            body: BODY,
+            hash: content_hash(BODY),
+        }
+    }};
+}
+
+/// Served with `index.html`, which is rendered separately, see [`AssetsDir::index_html`]
+const ASSETS: &[Asset] = &[
+    asset!("favicon.ico", "image/x-icon"),
+    asset!("style.css", "text/css"),
+    asset!("script.js", "application/javascript"),
+    asset!("sound1.mp3", "audio/mpeg"),
+];
+
+const INDEX_HTML: &str = include_str!("../assets/index.html");
+
+impl Asset {
+    /// `name` with the content hash inserted before the extension
+    fn hashed_name(&self) -> String {
+        match self.name.rsplit_once('.') {
+            Some((stem, ext)) => format!("{stem}.{:016x}.{ext}", self.hash),
+            None => format!("{}.{:016x}", self.name, self.hash),
+        }
+    }
+}
+
+/// Respond with `body`, or just `304 Not Modified` if the client has it already
+fn serve(
+    request_headers: &HeaderMap,
+    content_type: &'static str,
+    etag: String,
+    body: impl IntoResponse,
+    cache_control: &'static str,
+) -> Response {
+    let not_modified = request_headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim().trim_start_matches("W/"))
+        .any(|tag| tag == "*" || tag == etag);
+
+    if not_modified {
+        (
+            StatusCode::NOT_MODIFIED,
+            Headers([
+                (header::ETAG, etag),
+                (header::CACHE_CONTROL, cache_control.to_owned()),
+            ]),
+            (),
+        )
+            .into_response()
+    } else {
+        (
+            Headers([
+                (header::CONTENT_TYPE, content_type.to_owned()),
+                (header::ETAG, etag),
+                (header::CACHE_CONTROL, cache_control.to_owned()),
+            ]),
+            body,
+        )
+            .into_response()
+    }
+}
+
+/// Assets overriding the embedded ones, see [`router`]
+#[derive(Clone)]
+struct AssetsDir(Option<Arc<PathBuf>>);
+
+impl AssetsDir {
+    /// Content of `name` in the directory, if there's one
+    async fn read(&self, name: &str) -> Option<Vec<u8>> {
+        let dir = self.0.as_ref()?;
+        match fs::read(dir.join(name)).await {
+            Ok(content) => Some(content),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => {
+                warn!("Couldn't read {name} from {}: {e}", dir.display());
+                None
+            }
+        }
+    }
+
+    /// Overriden `asset`, or the embedded one
+    async fn asset(&self, asset: &Asset) -> Cow<'static, [u8]> {
+        self.read(asset.name)
+            .await
+            .map_or(Cow::Borrowed(asset.body), Cow::Owned)
+    }
+
+    /// `index.html`, referring to the hashed names of the assets not overridden
+    async fn index_html(&self) -> Vec<u8> {
+        let mut html = self.read("index.html").await.map_or_else(
+            || INDEX_HTML.to_owned(),
+            |html| String::from_utf8_lossy(&html).into_owned(),
+        );
+        for asset in ASSETS {
+            if self.read(asset.name).await.is_none() {
+                html = html.replace(
+                    &format!("./{}", asset.name),
+                    &format!("./{}", asset.hashed_name()),
+                );
+            }
+        }
+        html.into_bytes()
+    }
+}
+
+fn etag(content: &[u8]) -> String {
+    format!("\"{:016x}\"", content_hash(content))
+}
+
+/// Routes of the web UI
+///
+/// Files in `dir` take precedence over the embedded ones, and are read on every
+/// request, so they can be edited while running. Files in it that aren't
+/// embedded are served too.
+pub fn router(dir: Option<PathBuf>) -> Router {
+    let dir = AssetsDir(dir.map(Arc::new));
+
+    let index_dir = dir.clone();
+    let mut router = Router::new().route(
+        "/",
+        get(move |headers: HeaderMap| {
+            let dir = index_dir.clone();
+            async move {
+                let index = dir.index_html().await;
+                serve(&headers, "text/html", etag(&index), index, CACHE_REVALIDATE)
+            }
+        }),
+    );
+
+    for asset in ASSETS {
+        let asset_dir = dir.clone();
+        router = router
+            .route(
+                &format!("/{}", asset.name),
+                get(move |headers: HeaderMap| {
+                    let dir = asset_dir.clone();
+                    async move {
+                        let body = dir.asset(asset).await;
+                        serve(
+                            &headers,
+                            asset.content_type,
+                            etag(&body),
+                            body,
+                            CACHE_REVALIDATE,
+                        )
+                    }
+                }),
+            )
+            // only ever the embedded one, the override isn't referred to by this name
+            .route(
+                &format!("/{}", asset.hashed_name()),
+                get(move |headers: HeaderMap| async move {
+                    serve(
+                        &headers,
+                        asset.content_type,
+                        etag(asset.body),
+                        asset.body,
+                        CACHE_IMMUTABLE,
+                    )
+                }),
+            );
+    }
+
+    match dir.0 {
+        Some(dir) => router.fallback(get_service(ServeDir::new(dir.as_ref())).handle_error(
+            |error: io::Error| async move {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Unhandled internal error: {}", error),
+                )
+            },
+        )),
+        None => router,
+    }
+}
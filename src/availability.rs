@@ -0,0 +1,86 @@
+//! Rolling success rates of sources
+use std::collections::VecDeque;
+
+use serde::{ser::SerializeMap, Serialize, Serializer};
+
+/// Granularity of the recorded outcomes
+const BUCKET_SECS: u64 = 300;
+
+/// Windows to report success rates over, with their names
+pub const WINDOWS: &[(&str, u64)] = &[("1h", 3600), ("24h", 86400), ("7d", 7 * 86400)];
+
+#[derive(Clone, Copy, Debug)]
+struct Bucket {
+    start_ts: u64,
+    successes: u64,
+    failures: u64,
+}
+
+/// Successful and failed updates of a source over the longest of [`WINDOWS`]
+///
+/// Serializes as the success rate in each window, `null` if there were no
+/// updates in it.
+#[derive(Clone, Debug, Default)]
+pub struct Outcomes {
+    buckets: VecDeque<Bucket>,
+}
+
+impl Outcomes {
+    pub fn record(&mut self, success: bool, now: u64) {
+        let start_ts = now - now % BUCKET_SECS;
+        let bucket = match self.buckets.back_mut() {
+            Some(bucket) if bucket.start_ts == start_ts => bucket,
+            _ => {
+                self.buckets.push_back(Bucket {
+                    start_ts,
+                    successes: 0,
+                    failures: 0,
+                });
+                self.buckets.back_mut().expect("just pushed")
+            }
+        };
+        if success {
+            bucket.successes += 1;
+        } else {
+            bucket.failures += 1;
+        }
+
+        let max_window_secs = WINDOWS.iter().map(|(_, secs)| *secs).max().unwrap_or(0);
+        while let Some(bucket) = self.buckets.front() {
+            if now < bucket.start_ts + max_window_secs {
+                break;
+            }
+            self.buckets.pop_front();
+        }
+    }
+
+    /// Ratio of successful updates in the last `window_secs`
+    pub fn success_rate(&self, window_secs: u64, now: u64) -> Option<f64> {
+        let (successes, failures) = self
+            .buckets
+            .iter()
+            .rev()
+            .take_while(|bucket| now < bucket.start_ts + window_secs)
+            .fold((0, 0), |(successes, failures), bucket| {
+                (successes + bucket.successes, failures + bucket.failures)
+            });
+
+        let total = successes + failures;
+        if total == 0 {
+            None
+        } else {
+            Some(successes as f64 / total as f64)
+        }
+    }
+}
+
+impl Serialize for Outcomes {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let now = crate::get_now_ts();
+        let mut map = serializer.serialize_map(Some(WINDOWS.len()))?;
+        for (name, secs) in WINDOWS {
+            map.serialize_entry(name, &self.success_rate(*secs, now))?;
+        }
+        map.end()
+    }
+}
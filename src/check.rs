@@ -0,0 +1,76 @@
+//! `/check`, for Nagios, Zabbix and other monitoring systems running plain HTTP checks
+use std::fmt;
+
+use axum::http::StatusCode;
+use serde::Deserialize;
+
+/// Status of a check, as understood by Nagios plugins
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Critical,
+    Unknown,
+}
+
+impl CheckStatus {
+    /// Exit code of a Nagios plugin reporting this status
+    pub fn exit_code(self) -> u8 {
+        match self {
+            CheckStatus::Ok => 0,
+            CheckStatus::Warning => 1,
+            CheckStatus::Critical => 2,
+            CheckStatus::Unknown => 3,
+        }
+    }
+
+    /// So checks only looking at the status code fail on critical problems
+    pub fn http_status(self) -> StatusCode {
+        match self {
+            CheckStatus::Ok | CheckStatus::Warning => StatusCode::OK,
+            CheckStatus::Critical | CheckStatus::Unknown => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CheckStatus::Ok => "OK",
+            CheckStatus::Warning => "WARNING",
+            CheckStatus::Critical => "CRITICAL",
+            CheckStatus::Unknown => "UNKNOWN",
+        })
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CheckParams {
+    /// Ticker of the chain to check
+    pub chain: String,
+    /// Critical above this many blocks behind the height expected from the block time
+    pub max_lag: Option<u64>,
+    /// Critical if the tip is older than this many seconds
+    pub max_age: Option<u64>,
+    /// Warning above this many blocks behind
+    pub warn_lag: Option<u64>,
+    /// Warning if the tip is older than this many seconds
+    pub warn_age: Option<u64>,
+}
+
+/// Status of a `value`, given its warning and critical thresholds
+pub fn threshold_status(value: u64, warn: Option<u64>, crit: Option<u64>) -> CheckStatus {
+    if matches!(crit, Some(crit) if crit < value) {
+        CheckStatus::Critical
+    } else if matches!(warn, Some(warn) if warn < value) {
+        CheckStatus::Warning
+    } else {
+        CheckStatus::Ok
+    }
+}
+
+/// Nagios performance data of `value`, e.g. `lag=5;3;10`
+pub fn perf_data(label: &str, value: u64, warn: Option<u64>, crit: Option<u64>) -> String {
+    let threshold = |threshold: Option<u64>| threshold.map(|t| t.to_string()).unwrap_or_default();
+    format!("{label}={value};{};{}", threshold(warn), threshold(crit))
+}
@@ -0,0 +1,130 @@
+//! The local clock, which all the first-seen timestamps rely on
+//!
+//! In tests, or with the `test-util` feature, it can be replaced by a [`MockClock`].
+#[cfg(any(test, feature = "test-util"))]
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex, MutexGuard,
+};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use anyhow::{format_err, Result};
+use headers::HeaderMapExt;
+use serde::Serialize;
+
+/// How often to check the clock after the startup check
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(600);
+
+/// Skew worth warning about
+pub const WARN_SKEW_SECS: i64 = 10;
+
+/// How far in the future can a block be timestamped before it's suspicious;
+/// same as Bitcoin's consensus limit
+pub const MAX_FUTURE_BLOCK_TIME_SECS: u64 = 7200;
+
+/// Time set with [`MockClock`], 0 if not mocked
+#[cfg(any(test, feature = "test-util"))]
+static MOCK_NOW_TS: AtomicU64 = AtomicU64::new(0);
+
+/// Held by the [`MockClock`] in use, so tests using one run one at a time
+#[cfg(any(test, feature = "test-util"))]
+static MOCK_CLOCK_LOCK: once_cell::sync::Lazy<Mutex<()>> =
+    once_cell::sync::Lazy::new(Default::default);
+
+/// Current unix timestamp, see [`crate::get_now_ts`]
+pub fn now_ts() -> u64 {
+    #[cfg(any(test, feature = "test-util"))]
+    {
+        let mock_now_ts = MOCK_NOW_TS.load(Ordering::SeqCst);
+        if mock_now_ts != 0 {
+            return mock_now_ts;
+        }
+    }
+
+    u64::try_from(time::OffsetDateTime::now_utc().unix_timestamp()).expect("no negative timestamps")
+}
+
+/// Handle to a clock standing still until moved, for deterministic tests of
+/// everything using [`now_ts`], e.g. rate limiting of pollers
+///
+/// The clock is process-wide, so installing one waits for any other to be
+/// dropped first.
+#[cfg(any(test, feature = "test-util"))]
+pub struct MockClock {
+    _lock: MutexGuard<'static, ()>,
+}
+
+#[cfg(any(test, feature = "test-util"))]
+#[cfg_attr(not(test), allow(dead_code))]
+impl MockClock {
+    /// Stop the clock at `now_ts`
+    pub fn install(now_ts: u64) -> Self {
+        assert_ne!(now_ts, 0, "0 means not mocked");
+        // a test failing with the clock installed doesn't matter to the others
+        let lock = MOCK_CLOCK_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        MOCK_NOW_TS.store(now_ts, Ordering::SeqCst);
+        Self { _lock: lock }
+    }
+
+    pub fn set(&self, now_ts: u64) {
+        assert_ne!(now_ts, 0, "0 means not mocked");
+        MOCK_NOW_TS.store(now_ts, Ordering::SeqCst);
+    }
+
+    pub fn advance(&self, secs: u64) {
+        MOCK_NOW_TS.fetch_add(secs, Ordering::SeqCst);
+    }
+}
+
+/// Dropping the handle restores the real clock
+#[cfg(any(test, feature = "test-util"))]
+impl Drop for MockClock {
+    fn drop(&mut self) {
+        MOCK_NOW_TS.store(0, Ordering::SeqCst);
+    }
+}
+
+/// Result of the latest clock check, as exposed on `/status`
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ClockStatus {
+    /// How far ahead of the reference clock the local one is (negative if behind)
+    skew_secs: i64,
+    reference_url: String,
+    checked_ts: u64,
+}
+
+impl ClockStatus {
+    pub fn skew_secs(&self) -> i64 {
+        self.skew_secs
+    }
+}
+
+/// Compare the local clock with the `Date` header of a response from `url`
+///
+/// Only precise to about a second, which is enough for block timestamps.
+pub async fn check(client: &reqwest::Client, url: &str) -> Result<ClockStatus> {
+    let start = Instant::now();
+    let resp = client.head(url).send().await?;
+    let round_trip = start.elapsed();
+
+    let remote: SystemTime = resp
+        .headers()
+        .typed_get::<headers::Date>()
+        .ok_or_else(|| format_err!("no `Date` header in the response of {url}"))?
+        .into();
+    // assume the date was taken halfway through the request
+    let local = SystemTime::now() - round_trip / 2;
+
+    let to_secs = |ts: SystemTime| -> Result<i64> {
+        Ok(i64::try_from(ts.duration_since(UNIX_EPOCH)?.as_secs())?)
+    };
+
+    Ok(ClockStatus {
+        skew_secs: to_secs(local)? - to_secs(remote)?,
+        reference_url: url.to_owned(),
+        checked_ts: crate::get_now_ts(),
+    })
+}
@@ -0,0 +1,73 @@
+//! Dumping the heights recorded in the journal for offline analysis
+use std::fmt::Write;
+
+use anyhow::Result;
+use strum::EnumString;
+
+use crate::journal;
+
+#[derive(Debug, Clone, Copy, EnumString)]
+#[strum(serialize_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    /// JSON lines, same as the journal itself
+    Jsonl,
+}
+
+impl ExportFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv",
+            ExportFormat::Jsonl => "application/x-ndjson",
+        }
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// Render the `entries` of `ticker` (all chains if `None`)
+pub fn render(
+    entries: &[journal::Entry],
+    ticker: Option<&str>,
+    format: ExportFormat,
+) -> Result<String> {
+    let entries = entries
+        .iter()
+        .filter(|entry| ticker.is_none() || ticker == Some(entry.ticker.as_str()));
+
+    let mut out = String::new();
+    match format {
+        ExportFormat::Csv => {
+            out.push_str("ts,ticker,source,height,hash,block_time\n");
+            for entry in entries {
+                writeln!(
+                    out,
+                    "{},{},{},{},{},{}",
+                    entry.ts,
+                    csv_field(&entry.ticker),
+                    csv_field(&entry.source),
+                    entry.state.height,
+                    csv_field(&entry.state.hash.to_string()),
+                    entry
+                        .state
+                        .block_time
+                        .map(|block_time| block_time.to_string())
+                        .unwrap_or_default()
+                )?;
+            }
+        }
+        ExportFormat::Jsonl => {
+            for entry in entries {
+                writeln!(out, "{}", serde_json::to_string(entry)?)?;
+            }
+        }
+    }
+
+    Ok(out)
+}
@@ -0,0 +1,61 @@
+//! Pushing measurements to Graphite, in the Carbon plaintext protocol
+use std::fmt::Write as _;
+
+use anyhow::Result;
+use tokio::{io::AsyncWriteExt, net::TcpStream};
+
+use crate::measurement::Measurement;
+
+/// Make `s` a single path component
+fn sanitize(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c == '.' || c.is_whitespace() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// `<prefix>.<ticker>.<field>` of chains, and
+/// `<prefix>.<ticker>.sources.<source>.<field>` of sources
+pub fn to_lines(prefix: &str, measurements: &[Measurement]) -> String {
+    let mut lines = String::new();
+    for measurement in measurements {
+        let mut path = format!("{prefix}.{}", sanitize(measurement.chain.ticker()));
+        if let Some(source) = measurement.source {
+            path = format!("{path}.sources.{}", sanitize(source.short_name()));
+        }
+        for (field, value) in &measurement.fields {
+            let _ = writeln!(lines, "{path}.{field} {value} {}", measurement.ts);
+        }
+    }
+    lines
+}
+
+/// Carbon daemon, connected to again for every write
+pub struct Writer {
+    /// `<host>:<port>`
+    addr: String,
+    prefix: String,
+}
+
+impl Writer {
+    pub fn new(addr: &str, prefix: &str) -> Self {
+        Self {
+            addr: addr.to_owned(),
+            prefix: prefix.trim_end_matches('.').to_owned(),
+        }
+    }
+
+    pub async fn write(&self, measurements: &[Measurement]) -> Result<()> {
+        let mut stream = TcpStream::connect(&self.addr).await?;
+        stream
+            .write_all(to_lines(&self.prefix, measurements).as_bytes())
+            .await?;
+        stream.shutdown().await?;
+        Ok(())
+    }
+}
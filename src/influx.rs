@@ -0,0 +1,99 @@
+//! Pushing measurements to InfluxDB, in its line protocol
+use std::fmt::Write;
+
+use anyhow::{format_err, Result};
+use reqwest::Url;
+
+use crate::{measurement::Measurement, opts::Opts};
+
+/// Escape commas, spaces and equal signs of tag keys and values
+fn escape(s: &str) -> String {
+    s.replace(',', "\\,")
+        .replace(' ', "\\ ")
+        .replace('=', "\\=")
+}
+
+pub fn to_lines(measurements: &[Measurement]) -> String {
+    let mut lines = String::new();
+    for measurement in measurements {
+        let tags: String = measurement
+            .tags()
+            .iter()
+            .map(|(key, value)| format!(",{key}={}", escape(value)))
+            .collect();
+        let fields: Vec<_> = measurement
+            .fields
+            .iter()
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect();
+        let _ = writeln!(
+            lines,
+            "{}{tags} {} {}",
+            measurement.name(),
+            fields.join(","),
+            measurement.ts
+        );
+    }
+    lines
+}
+
+/// InfluxDB v1 database, or v2 bucket
+pub struct Writer {
+    write_url: Url,
+    token: Option<String>,
+    client: reqwest::Client,
+}
+
+impl Writer {
+    /// Write to the InfluxDB at `url`, configured by the `--influx-*` options
+    pub fn new(url: &str, opts: &Opts) -> Result<Self> {
+        let mut url: Url = url.parse()?;
+        // so the endpoints are joined to the whole path
+        if !url.path().ends_with('/') {
+            url.set_path(&format!("{}/", url.path()));
+        }
+        let write_url = match (
+            opts.influx_db.as_deref(),
+            opts.influx_org.as_deref(),
+            opts.influx_bucket.as_deref(),
+        ) {
+            (Some(db), None, None) => Url::parse_with_params(
+                url.join("write")?.as_str(),
+                &[("db", db), ("precision", "s")],
+            )?,
+            (None, Some(org), Some(bucket)) => Url::parse_with_params(
+                url.join("api/v2/write")?.as_str(),
+                &[("org", org), ("bucket", bucket), ("precision", "s")],
+            )?,
+            _ => {
+                return Err(format_err!(
+                    "`--influx-url` requires either `--influx-db`, or `--influx-org` and `--influx-bucket`"
+                ))
+            }
+        };
+
+        Ok(Self {
+            write_url,
+            token: opts
+                .influx_token
+                .as_deref()
+                .map(|token| crate::util::load_secret(token, "InfluxDB token"))
+                .transpose()?,
+            client: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(10))
+                .build()?,
+        })
+    }
+
+    pub async fn write(&self, measurements: &[Measurement]) -> Result<()> {
+        let mut request = self
+            .client
+            .post(self.write_url.clone())
+            .body(to_lines(measurements));
+        if let Some(token) = self.token.as_ref() {
+            request = request.header(reqwest::header::AUTHORIZATION, format!("Token {token}"));
+        }
+        request.send().await?.error_for_status()?;
+        Ok(())
+    }
+}
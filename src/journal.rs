@@ -0,0 +1,520 @@
+//! Append-only log of accepted chain state updates, for replaying them later
+//!
+//! Older entries are thinned out by [`Journal::compact`], as set by the
+//! [`RetentionPolicy`].
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{format_err, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+use crate::{opts::Opts, source::ChainId, ChainState};
+
+/// A single line of the journal
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    pub ts: u64,
+    /// Short name of the source
+    pub source: String,
+    pub ticker: String,
+    #[serde(flatten)]
+    pub state: ChainState,
+}
+
+/// Entries older than the raw ones are kept one per this long, until
+/// [`Retention::downsampled_secs`]
+const DOWNSAMPLED_INTERVAL_SECS: u64 = 5 * 60;
+
+/// Entries older than the downsampled ones are kept one per this long
+const HOURLY_INTERVAL_SECS: u64 = 60 * 60;
+
+/// How long to keep the entries of a chain, by their age
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Retention {
+    /// Keep every entry for this long
+    pub raw_secs: u64,
+    /// Then one per [`DOWNSAMPLED_INTERVAL_SECS`] for this long, and one per
+    /// [`HOURLY_INTERVAL_SECS`] after
+    pub downsampled_secs: u64,
+    /// Drop the entries older than this
+    pub max_age_secs: Option<u64>,
+}
+
+impl Retention {
+    /// How often to keep an entry of the given age, `None` to drop it, and
+    /// `Some(0)` to keep all of them
+    fn interval_secs(&self, age_secs: u64) -> Option<u64> {
+        if matches!(self.max_age_secs, Some(max_age_secs) if max_age_secs <= age_secs) {
+            None
+        } else if age_secs < self.raw_secs {
+            Some(0)
+        } else if age_secs < self.downsampled_secs {
+            Some(DOWNSAMPLED_INTERVAL_SECS)
+        } else {
+            Some(HOURLY_INTERVAL_SECS)
+        }
+    }
+}
+
+/// [`Retention`] of each chain, from `--journal-*` and `--chain-tuning`
+#[derive(Debug, Clone)]
+pub struct RetentionPolicy {
+    default: Retention,
+    chains: HashMap<ChainId, Retention>,
+}
+
+impl RetentionPolicy {
+    pub fn new(opts: &Opts) -> Self {
+        let default = Retention {
+            raw_secs: opts.journal_raw_secs,
+            downsampled_secs: opts.journal_downsampled_secs,
+            max_age_secs: opts.journal_max_age_secs,
+        };
+        let chains = opts
+            .chain_tunings
+            .iter()
+            .map(|tuning| {
+                let chain = tuning.chain;
+                let tuning = opts.tuning(chain);
+                let retention = Retention {
+                    raw_secs: tuning.journal_raw_secs.unwrap_or(default.raw_secs),
+                    downsampled_secs: tuning
+                        .journal_downsampled_secs
+                        .unwrap_or(default.downsampled_secs),
+                    max_age_secs: tuning.journal_max_age_secs.or(default.max_age_secs),
+                };
+                (chain, retention)
+            })
+            .collect();
+
+        Self { default, chains }
+    }
+
+    fn get(&self, ticker: &str) -> Retention {
+        ChainId::from_ticker(ticker)
+            .and_then(|chain| self.chains.get(&chain).copied())
+            .unwrap_or(self.default)
+    }
+
+    /// Entries of `entries` (oldest first) to keep at `now`
+    ///
+    /// Of those kept one per interval, the latest one of each source and chain
+    /// in the interval is kept.
+    fn retain(&self, entries: Vec<Entry>, now: u64) -> Vec<Entry> {
+        let mut keep = vec![false; entries.len()];
+        let mut latest_in_interval = HashMap::new();
+        for (i, entry) in entries.iter().enumerate() {
+            let age_secs = now.saturating_sub(entry.ts);
+            match self.get(&entry.ticker).interval_secs(age_secs) {
+                None => {}
+                Some(0) => keep[i] = true,
+                Some(interval_secs) => {
+                    latest_in_interval.insert(
+                        (
+                            entry.source.as_str(),
+                            entry.ticker.as_str(),
+                            interval_secs,
+                            entry.ts / interval_secs,
+                        ),
+                        i,
+                    );
+                }
+            }
+        }
+        for i in latest_in_interval.into_values() {
+            keep[i] = true;
+        }
+
+        entries
+            .into_iter()
+            .zip(keep)
+            .filter(|(_, keep)| *keep)
+            .map(|(entry, _)| entry)
+            .collect()
+    }
+}
+
+/// Journal file, rotated to `<path>.1` once it grows over `max_bytes`
+pub struct Journal {
+    path: PathBuf,
+    file: File,
+    len: u64,
+    max_bytes: u64,
+}
+
+impl Journal {
+    pub fn open(path: PathBuf, max_bytes: u64) -> Result<Self> {
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&path)?;
+        let len = drop_partial_line(&path, &mut file)?;
+
+        Ok(Self {
+            path,
+            file,
+            len,
+            max_bytes,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn rotate(&mut self) -> Result<()> {
+        fs::rename(&self.path, rotated_path(&self.path))?;
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.len = 0;
+        Ok(())
+    }
+
+    /// Thin out the entries of both the journal and its rotated part as of
+    /// `now`, rewriting the ones kept to the journal itself
+    pub fn compact(&mut self, now: u64, policy: &RetentionPolicy) -> Result<()> {
+        let entries = read_all(&self.path)?;
+        let before = entries.len();
+        let entries = policy.retain(entries, now);
+        if entries.len() == before {
+            return Ok(());
+        }
+
+        let mut compacted = self.path.clone().into_os_string();
+        compacted.push(".compacted");
+        let compacted = PathBuf::from(compacted);
+        let mut contents = vec![];
+        for entry in &entries {
+            serde_json::to_writer(&mut contents, entry)?;
+            contents.push(b'\n');
+        }
+        fs::write(&compacted, &contents)?;
+        // the rotated part goes after, so a crash in between duplicates
+        // entries rather than losing them
+        fs::rename(&compacted, &self.path)?;
+        let rotated = rotated_path(&self.path);
+        if rotated.exists() {
+            fs::remove_file(rotated)?;
+        }
+
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        self.len = contents.len() as u64;
+        debug!(
+            "Compacted the journal from {before} to {} entries",
+            entries.len()
+        );
+        Ok(())
+    }
+
+    pub fn append(&mut self, entry: &Entry) -> Result<()> {
+        if self.max_bytes <= self.len {
+            self.rotate()?;
+        }
+
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        self.file.write_all(&line)?;
+        self.len += line.len() as u64;
+        Ok(())
+    }
+}
+
+/// How many entries can wait for the [`Writer`] before new ones are dropped
+const QUEUE_LEN: usize = 1024;
+
+enum Command {
+    Append(Entry),
+    /// Apply the retention as of the given time
+    Compact(u64),
+}
+
+/// Handle to a [`Journal`] written on a thread of its own, so recording an
+/// update never waits for the disk
+#[derive(Clone)]
+pub struct Writer {
+    path: PathBuf,
+    tx: mpsc::Sender<Command>,
+}
+
+impl Writer {
+    pub fn spawn(mut journal: Journal, policy: RetentionPolicy) -> Result<Self> {
+        let path = journal.path().to_owned();
+        let (tx, mut rx) = mpsc::channel(QUEUE_LEN);
+        std::thread::Builder::new()
+            .name("journal".into())
+            .spawn(move || {
+                while let Some(command) = rx.blocking_recv() {
+                    match command {
+                        Command::Append(entry) => {
+                            if let Err(e) = journal.append(&entry) {
+                                warn!("Couldn't write to the journal: {e}");
+                            }
+                        }
+                        Command::Compact(now) => {
+                            if let Err(e) = journal.compact(now, &policy) {
+                                warn!("Couldn't compact the journal: {e}");
+                            }
+                        }
+                    }
+                }
+            })?;
+
+        Ok(Self { path, tx })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Queue `entry` to be appended, dropping it if the writer fell behind
+    pub fn append(&self, entry: Entry) {
+        if self.tx.try_send(Command::Append(entry)).is_err() {
+            warn!("Couldn't queue an entry for the journal, dropping it");
+        }
+    }
+
+    /// Queue applying the retention, as of now
+    pub fn compact(&self) {
+        if self
+            .tx
+            .try_send(Command::Compact(crate::get_now_ts()))
+            .is_err()
+        {
+            warn!("Couldn't queue compacting the journal, skipping it");
+        }
+    }
+}
+
+/// Cut off an entry left partly written at the end of `file` (e.g. by a
+/// crash), so the next one isn't appended to it, returning the new length
+fn drop_partial_line(path: &Path, file: &mut File) -> Result<u64> {
+    let len = file.metadata()?.len();
+    if len == 0 {
+        return Ok(len);
+    }
+    let mut last = [0];
+    file.seek(SeekFrom::Start(len - 1))?;
+    file.read_exact(&mut last)?;
+    if last[0] == b'\n' {
+        return Ok(len);
+    }
+
+    let mut contents = vec![];
+    file.seek(SeekFrom::Start(0))?;
+    file.read_to_end(&mut contents)?;
+    let complete = contents
+        .iter()
+        .rposition(|&byte| byte == b'\n')
+        .map_or(0, |i| i + 1) as u64;
+    warn!(
+        "Dropping an unterminated entry at the end of {}",
+        path.display()
+    );
+    file.set_len(complete)?;
+    Ok(complete)
+}
+
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut rotated = path.to_owned().into_os_string();
+    rotated.push(".1");
+    rotated.into()
+}
+
+/// Entries of both the journal at `path` and its rotated part, oldest first
+pub fn read_all(path: &Path) -> Result<Vec<Entry>> {
+    let rotated = rotated_path(path);
+    let mut entries = if rotated.exists() {
+        read(&rotated)?
+    } else {
+        vec![]
+    };
+    entries.extend(read(path)?);
+    Ok(entries)
+}
+
+/// All entries of the journal at `path`, oldest first
+///
+/// An unterminated last line is skipped, as it's an entry left partly
+/// written by a crash.
+pub fn read(path: &Path) -> Result<Vec<Entry>> {
+    let contents = fs::read_to_string(path)?;
+    let mut entries = vec![];
+    for (i, line) in contents.split_inclusive('\n').enumerate() {
+        if !line.ends_with('\n') {
+            warn!(
+                "{}:{}: skipping an unterminated last entry",
+                path.display(),
+                i + 1
+            );
+            break;
+        }
+        if line.trim().is_empty() {
+            continue;
+        }
+        entries.push(
+            serde_json::from_str(line)
+                .map_err(|e| format_err!("{}:{}: invalid entry: {e}", path.display(), i + 1))?,
+        );
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use clap::Parser;
+
+    use super::*;
+    use crate::{BlockHash, ChainHeight};
+
+    fn entry(height: u64) -> Entry {
+        let height = ChainHeight::new(height);
+        Entry {
+            ts: 1_700_000_000,
+            source: "blockstream".to_owned(),
+            ticker: "btc".to_owned(),
+            state: ChainState {
+                hash: BlockHash::from_height(height),
+                height,
+                ..Default::default()
+            },
+        }
+    }
+
+    const NOW_TS: u64 = 1_700_000_000;
+    const DAY_SECS: u64 = 24 * 60 * 60;
+
+    fn entry_at(ts: u64, ticker: &str) -> Entry {
+        Entry {
+            ts,
+            ticker: ticker.to_owned(),
+            ..entry(1)
+        }
+    }
+
+    fn retained(policy: &RetentionPolicy, entries: &[Entry]) -> Vec<u64> {
+        policy
+            .retain(entries.to_vec(), NOW_TS)
+            .into_iter()
+            .map(|entry| NOW_TS - entry.ts)
+            .collect()
+    }
+
+    /// Whether `ages` are at most one per interval, and not much fewer
+    fn one_per_interval(ages: &[u64], interval_secs: u64, span_secs: u64) -> bool {
+        let intervals: HashSet<_> = ages
+            .iter()
+            .map(|age| (NOW_TS - age) / interval_secs)
+            .collect();
+        intervals.len() == ages.len() && span_secs / interval_secs <= ages.len() as u64
+    }
+
+    #[test]
+    fn older_entries_are_downsampled() {
+        let policy = RetentionPolicy::new(&Opts::parse_from(["chain-monitor"]));
+        // a minute apart for the last 40 days
+        let entries: Vec<_> = (0..40 * DAY_SECS / 60)
+            .rev()
+            .map(|i| entry_at(NOW_TS - i * 60, "btc"))
+            .collect();
+
+        let ages = retained(&policy, &entries);
+        assert!(ages.windows(2).all(|ages| ages[1] < ages[0]));
+
+        let (raw, older): (Vec<u64>, Vec<u64>) =
+            ages.into_iter().partition(|&age| age < 2 * DAY_SECS);
+        assert_eq!(raw.len() as u64, 2 * DAY_SECS / 60);
+        let (downsampled, hourly): (Vec<u64>, Vec<u64>) =
+            older.into_iter().partition(|&age| age < 30 * DAY_SECS);
+        assert!(one_per_interval(
+            &downsampled,
+            DOWNSAMPLED_INTERVAL_SECS,
+            28 * DAY_SECS
+        ));
+        assert!(one_per_interval(
+            &hourly,
+            HOURLY_INTERVAL_SECS,
+            10 * DAY_SECS
+        ));
+    }
+
+    #[test]
+    fn chains_can_have_a_retention_of_their_own() {
+        let policy = RetentionPolicy::new(&Opts::parse_from([
+            "chain-monitor",
+            "--journal-max-age-secs",
+            "3600",
+            "--chain-tuning",
+            "eth=journal-raw-secs:0,journal-downsampled-secs:0,journal-max-age-secs:86400",
+        ]));
+        let entries = [
+            entry_at(NOW_TS - 7200, "btc"),
+            entry_at(NOW_TS - 7200, "eth"),
+            entry_at(NOW_TS - 7100, "eth"),
+            entry_at(NOW_TS - 60, "btc"),
+        ];
+
+        assert_eq!(retained(&policy, &entries), [7100, 60]);
+    }
+
+    #[test]
+    fn compacting_merges_the_rotated_part() -> Result<()> {
+        let path =
+            std::env::temp_dir().join(format!("journal-compact-{}.jsonl", std::process::id()));
+        let policy = RetentionPolicy::new(&Opts::parse_from([
+            "chain-monitor",
+            "--journal-max-age-secs",
+            "3600",
+        ]));
+        let mut journal = Journal::open(path.clone(), 100)?;
+        journal.append(&entry_at(NOW_TS - 7200, "btc"))?;
+        journal.append(&entry_at(NOW_TS - 60, "btc"))?;
+        journal.append(&entry_at(NOW_TS, "btc"))?;
+        assert!(rotated_path(&path).exists());
+
+        journal.compact(NOW_TS, &policy)?;
+        journal.append(&entry_at(NOW_TS + 60, "btc"))?;
+        let ts: Vec<_> = read_all(&path)?.into_iter().map(|entry| entry.ts).collect();
+        assert_eq!(ts, [NOW_TS - 60, NOW_TS, NOW_TS + 60]);
+
+        fs::remove_file(rotated_path(&path))?;
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[test]
+    fn entries_cut_off_by_a_crash_are_dropped() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("journal-{}.jsonl", std::process::id()));
+        let mut journal = Journal::open(path.clone(), u64::MAX)?;
+        journal.append(&entry(1))?;
+        journal.append(&entry(2))?;
+        drop(journal);
+        let mut file = OpenOptions::new().append(true).open(&path)?;
+        file.write_all(br#"{"ts":1700000003,"sou"#)?;
+        drop(file);
+
+        assert_eq!(read(&path)?.len(), 2);
+
+        let mut journal = Journal::open(path.clone(), u64::MAX)?;
+        journal.append(&entry(3))?;
+        let heights: Vec<_> = read(&path)?
+            .into_iter()
+            .map(|entry| entry.state.height)
+            .collect();
+        assert_eq!(heights, [1, 2, 3].map(ChainHeight::new).to_vec());
+
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -3,57 +3,114 @@ use anyhow::Result;
 use axum::{
     async_trait,
     extract::{
+        connect_info::IntoMakeServiceWithConnectInfo,
         ws::{Message, WebSocket, WebSocketUpgrade},
-        Extension, TypedHeader,
+        Extension, Query, TypedHeader,
     },
     http::StatusCode,
     middleware,
-    response::{Headers, Html, IntoResponse},
-    routing::{get, get_service, IntoMakeService},
+    response::{Headers, IntoResponse},
+    routing::get,
     Json, Router,
 };
-use futures::{sink::SinkExt, stream::StreamExt};
-use metrics::gauge;
+use futures::{
+    sink::{Sink, SinkExt},
+    stream::StreamExt,
+};
+use metrics::{counter, decrement_gauge, gauge, increment_counter, increment_gauge};
 use serde::{Deserialize, Serialize};
-use source::{ChainId, Source, SourceId};
+use source::{ChainId, HeightKind, NetworkType, Source, SourceError, SourceErrorKind, SourceId};
 use std::{
     cmp,
-    collections::{hash_map::Entry::*, HashMap},
+    collections::{hash_map::Entry::*, HashMap, HashSet},
     future::ready,
     net::SocketAddr,
-    sync::Arc,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
     time::Duration,
 };
-use tokio::{
-    sync::{broadcast, Mutex},
-    time::timeout,
-};
-use tower_http::{
-    services::ServeDir,
-    trace::{DefaultMakeSpan, TraceLayer},
-};
-use tracing::debug;
+use tokio::sync::{broadcast, watch, Mutex, Notify};
+use tracing::{debug, Instrument};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+mod access_log;
+mod admin;
+mod alert;
+mod assets;
+mod availability;
+mod check;
+mod clock;
+mod export;
+mod graphite;
+mod influx;
+mod journal;
+mod measurement;
+mod milestone;
+mod notifier;
 mod opts;
 mod prom;
+mod prom_rules;
+mod silence;
+mod snapshot;
 mod source;
+mod supervisor;
+mod types;
 mod util;
+mod webhook;
 
 use opts::Opts;
+pub use types::{BlockHash, ChainHeight};
 
-type ChainHeight = u64;
-type BlockHash = String;
+/// Sources are only polled every so often, so fast chains can look behind
+/// schedule without anything being wrong; don't alert before this much time
+/// worth of blocks is missing
+const POLLING_LAG_SECS: u64 = 90;
 
 pub fn get_now_ts() -> u64 {
-    u64::try_from(time::OffsetDateTime::now_utc().unix_timestamp()).expect("no negative timestamps")
+    clock::now_ts()
+}
+
+/// When was a state last confirmed by its source
+///
+/// Shared by all the clones of the state, so confirming an unchanged state
+/// shows in the published snapshots without rebuilding them.
+#[derive(Clone, Debug, Default)]
+pub struct CheckedTs(Arc<AtomicU64>);
+
+impl CheckedTs {
+    fn new(ts: u64) -> Self {
+        Self(Arc::new(AtomicU64::new(ts)))
+    }
+
+    fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    fn set(&self, ts: u64) {
+        self.0.store(ts, Ordering::Relaxed);
+    }
+}
+
+impl Serialize for CheckedTs {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(self.get())
+    }
+}
+
+impl<'de> Deserialize<'de> for CheckedTs {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        u64::deserialize(deserializer).map(Self::new)
+    }
 }
 
 #[derive(Serialize, Clone, Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ChainStateTs {
     first_seen_ts: u64,
-    last_checked_ts: u64,
+    last_checked_ts: CheckedTs,
     #[serde(flatten)]
     state: ChainState,
 }
@@ -69,17 +126,43 @@ impl ChainStateTs {
     }
 }
 
-#[derive(Serialize, Clone, Debug, PartialEq, Eq, Deserialize)]
+#[derive(Serialize, Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ChainState {
     hash: BlockHash,
     height: ChainHeight,
+    /// Timestamp of the block, as recorded in the block itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    block_time: Option<u64>,
+    /// Secondary counter of the chain, see [`ChainId::secondary_height_kind`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    secondary_height: Option<ChainHeight>,
+    /// Latest block considered safe from reorgs, on chains that track it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    safe_height: Option<ChainHeight>,
+    /// Latest finalized block, on chains that track it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    finalized_height: Option<ChainHeight>,
+    /// Cumulative work of the chain up to this block, hex-encoded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    chain_work: Option<String>,
 }
 
 impl ChainState {
+    /// How long ago was the tip block produced, if known
+    fn tip_age_secs(&self, now: u64) -> Option<u64> {
+        self.block_time
+            .map(|block_time| now.saturating_sub(block_time))
+    }
+
+    fn chain_work(&self) -> Option<u128> {
+        self.chain_work.as_deref().and_then(util::parse_chain_work)
+    }
+
     fn to_state_ts(self) -> ChainStateTs {
         ChainStateTs {
             first_seen_ts: get_now_ts(),
-            last_checked_ts: get_now_ts(),
+            last_checked_ts: CheckedTs::new(get_now_ts()),
             state: self,
         }
     }
@@ -100,11 +183,14 @@ pub struct ChainStateUpdateTs {
 }
 
 impl ChainStateUpdateTs {
-    fn to_ws_update(self) -> WSChainStateUpdateTs {
+    /// `confidence` is the one of the best state of the chain, not of this update
+    fn into_ws_update(self, confidence: Option<Confidence>) -> WSChainStateUpdateTs {
         WSChainStateUpdateTs {
+            confidence,
             first_seen_ts: self.state.first_seen_ts,
-            hash: self.state.state.hash,
-            height: self.state.state.height,
+            state: self.state.state,
+            height_kind: self.chain.height_kind(),
+            secondary_height_kind: self.chain.secondary_height_kind(),
             source: self.source,
             chain: self.chain,
         }
@@ -116,93 +202,1312 @@ impl ChainStateUpdateTs {
 pub struct WSChainStateUpdateTs {
     source: SourceId,
     chain: ChainId,
+    height_kind: HeightKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secondary_height_kind: Option<HeightKind>,
     first_seen_ts: u64,
-    hash: BlockHash,
-    height: ChainHeight,
+    /// Of the best state of the chain, see [`BestChainStateTs::confidence`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    confidence: Option<Confidence>,
+    #[serde(flatten)]
+    state: ChainState,
 }
 
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct SourceInfo {
     id: SourceId,
-    url: String,
+    url: Option<&'static str>,
     short_name: &'static str,
     full_name: &'static str,
 }
 
+/// Explorer page of a chain on a given source
+///
+/// See [`source::Source::url_for_chain`].
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+#[derive(Clone, Debug)]
+pub struct SourceChainUrl {
+    source: SourceId,
+    chain: ChainId,
+    url: String,
+}
+
+impl SourceChainUrl {
+    fn to_block_url(&self, state: &ChainState) -> String {
+        self.url
+            .replace("{height}", &state.height.to_string())
+            .replace("{hash}", state.hash.as_str())
+    }
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct ChainInfo {
     id: ChainId,
     short_name: &'static str,
     full_name: &'static str,
     block_time_secs: u32,
+    /// For grouping chains by network
+    network_type: NetworkType,
+    height_kind: HeightKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secondary_height_kind: Option<HeightKind>,
+}
+
+/// Statistics of unconfirmed transactions of a chain
+#[derive(Serialize, Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MempoolStats {
+    /// Number of transactions
+    tx_count: u64,
+    /// Total virtual size of all transactions
+    vsize: u64,
+    /// Minimum fee rate (sat/vB) to get into the mempool
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min_fee_rate: Option<f64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct MempoolStatsUpdate {
+    source: SourceId,
+    chain: ChainId,
+    stats: MempoolStats,
+}
+
+/// Mining difficulty of a PoW chain
+#[derive(Serialize, Clone, Debug, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DifficultyStats {
+    difficulty: f64,
+    /// Height at which the difficulty will be adjusted next
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    next_adjustment_height: Option<ChainHeight>,
+    /// Blocks left until the next adjustment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    remaining_blocks: Option<u64>,
+    /// Estimated change of the difficulty at the next adjustment, in percent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expected_change_pct: Option<f64>,
+}
+
+#[derive(Clone, Debug)]
+pub struct DifficultyStatsUpdate {
+    source: SourceId,
+    chain: ChainId,
+    stats: DifficultyStats,
+}
+
+/// Auxiliary chain statistics (mempool, difficulty...) as reported by a source
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceStatsTs<T> {
+    source: SourceId,
+    last_checked_ts: u64,
+    #[serde(flatten)]
+    stats: T,
+}
+
+type SourceStats<T> = Mutex<HashMap<(SourceId, ChainId), SourceStatsTs<T>>>;
+
+impl<T: Clone> SourceStatsTs<T> {
+    async fn record(stats: &SourceStats<T>, source: SourceId, chain: ChainId, value: T) {
+        stats.lock().await.insert(
+            (source, chain),
+            SourceStatsTs {
+                source,
+                last_checked_ts: get_now_ts(),
+                stats: value,
+            },
+        );
+    }
+
+    async fn by_ticker(stats: &SourceStats<T>) -> HashMap<&'static str, Vec<SourceStatsTs<T>>> {
+        let mut by_ticker: HashMap<_, Vec<_>> = HashMap::new();
+
+        for ((_, chain), stats) in stats.lock().await.iter() {
+            by_ticker
+                .entry(chain.ticker())
+                .or_default()
+                .push(stats.clone());
+        }
+
+        by_ticker
+    }
+}
+
+/// Last error reported by a source
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceErrorTs {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    chain: Option<ChainId>,
+    kind: SourceErrorKind,
+    message: String,
+    ts: u64,
+}
+
+/// Last panic caught while running a source
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SourcePanicTs {
+    message: String,
+    ts: u64,
+}
+
+/// Health of a source, as exposed on `/status`
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SourceStatus {
+    /// False after a panic, until the restarted source completes a round of updates
+    healthy: bool,
+    errors_total: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_error: Option<SourceErrorTs>,
+    /// Responses not matching the expected schema, likely due to an API change;
+    /// kept apart from `last_error`, so they aren't buried by transient errors
+    schema_changes_total: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_schema_change: Option<SourceErrorTs>,
+    panics_total: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_panic: Option<SourcePanicTs>,
+    /// Why the source isn't running at all, e.g. a missing API key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    skipped: Option<&'static str>,
+    /// Ratio of successful updates over the last hour, day and week
+    success_rate: availability::Outcomes,
+}
+
+impl Default for SourceStatus {
+    fn default() -> Self {
+        Self {
+            healthy: true,
+            errors_total: 0,
+            last_error: None,
+            schema_changes_total: 0,
+            last_schema_change: None,
+            panics_total: 0,
+            last_panic: None,
+            skipped: None,
+            success_rate: Default::default(),
+        }
+    }
+}
+
+/// Everything exposed on `/status`
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Status {
+    #[serde(flatten)]
+    sources: HashMap<&'static str, SourceStatus>,
+    /// Latest check of the local clock, if enabled with `--clock-check-url`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    clock: Option<clock::ClockStatus>,
+    /// Silences and maintenance windows in effect or yet to come
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    silences: Vec<silence::Silence>,
+}
+
+/// What's running, exposed on `/version`
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct VersionInfo {
+    version: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    git_commit: Option<&'static str>,
+    build_ts: u64,
+    /// Cargo features the binary was built with
+    features: Vec<&'static str>,
+    /// Short names of the sources in use
+    sources: Vec<&'static str>,
+}
+
+/// Criterion that decided which source's state is the best one
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum BestStateSelection {
+    /// Most chainwork, see [`ChainStates::use_chain_work`]
+    ChainWork,
+    /// Highest height agreed on by enough source weight, ties broken by
+    /// source weight, priority and then earliest first-seen
+    Height,
+}
+
+/// Best known state of a chain, along with the source that reported it
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BestChainStateTs {
+    source: SourceId,
+    selected_by: BestStateSelection,
+    height_kind: HeightKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secondary_height_kind: Option<HeightKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tip_age_secs: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    confidence: Option<Confidence>,
+    #[serde(flatten)]
+    state: ChainStateTs,
+}
+
+/// Best state not confirmed by any source for longer than this isn't fresh
+const CONFIDENCE_FRESH_SECS: u64 = 5 * 60;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ConfidenceLevel {
+    /// Fresh, without anomalies, and agreed on by most of the sources,
+    /// at least two
+    High,
+    /// Fresh and without anomalies, but not backed by most of the sources
+    Medium,
+    /// Stale, or with anomalies
+    Low,
+}
+
+/// How much the best state of a chain can be trusted
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Confidence {
+    level: ConfidenceLevel,
+    /// Sources within `--consensus-tolerance-blocks` of the best height
+    agreeing_sources: usize,
+    total_sources: usize,
+    /// Since when was the best state last confirmed by its source
+    checked_age_secs: u64,
+    paused: bool,
+    /// Sources whose height went backwards, while being held back
+    regressing_sources: usize,
+    active_alerts: Vec<alert::AlertKind>,
 }
 
+/// Weight of sources not given one with `--source-weight`
+const DEFAULT_SOURCE_WEIGHT: u64 = 1;
+
+/// Settings affecting how [`ChainStates`] are recorded and compared
 #[derive(Default)]
+pub struct ChainStatesOpts {
+    /// Prefer tips with most chainwork, see [`ChainId::has_chain_work`]
+    use_chain_work: bool,
+    /// Sources to prefer when several report the best state, most preferred first
+    source_priority: Vec<SourceId>,
+    /// Trust weights of sources, [`DEFAULT_SOURCE_WEIGHT`] if not listed
+    source_weights: HashMap<SourceId, u64>,
+    /// For how long to keep the previous state of a source whose height went backwards
+    regression_grace_secs: u64,
+}
+
+/// States of a single chain, as reported by all the sources
+#[derive(Clone)]
 pub struct ChainStates {
-    states: HashMap<(SourceId, ChainId), ChainStateTs>,
-    best_height: HashMap<ChainId, ChainHeight>,
+    chain: ChainId,
+    states: HashMap<SourceId, ChainStateTs>,
+    best_height: Option<ChainHeight>,
+    /// When did the height reported by a source go backwards, while still held back
+    regressions: HashMap<SourceId, u64>,
+    /// How many times was each source the first to report a new best height
+    first_seen: HashMap<SourceId, u64>,
+    opts: Arc<ChainStatesOpts>,
+}
+
+impl ChainStates {
+    fn new(chain: ChainId, opts: Arc<ChainStatesOpts>) -> Self {
+        Self {
+            chain,
+            states: Default::default(),
+            best_height: None,
+            regressions: Default::default(),
+            first_seen: Default::default(),
+            opts,
+        }
+    }
+
+    /// Record that `source` still reports the same state
+    ///
+    /// Returns whether anything but the time of the check changed.
+    fn touch(&mut self, source: SourceId, now: u64) -> bool {
+        if let Some(state) = self.states.get(&source) {
+            state.last_checked_ts.set(now);
+        }
+        self.regressions.remove(&source).is_some()
+    }
+
+    /// Track sources reporting lower height than they did previously
+    ///
+    /// Returns whether the update should be held back, keeping the previous
+    /// state for the grace period.
+    fn check_regression(&mut self, update: &ChainStateUpdate, now: u64) -> bool {
+        let prev_height = match self.states.get(&update.source) {
+            Some(prev) if update.state.height < prev.state.height => {
+                prev.last_checked_ts.set(now);
+                prev.state.height
+            }
+            _ => {
+                self.regressions.remove(&update.source);
+                return false;
+            }
+        };
+
+        let since = *self.regressions.entry(update.source).or_insert_with(|| {
+            tracing::warn!(
+                "{:?} {:?} height went backwards: {} -> {}",
+                update.source,
+                update.chain,
+                prev_height,
+                update.state.height
+            );
+            increment_counter!(
+                "chain_monitor_source_regressions_total",
+                "source" => update.source.short_name().to_lowercase(),
+                "chain" => update.chain.short_name().to_lowercase(),
+                "ticker" => update.chain.ticker()
+            );
+            now
+        });
+
+        if now.saturating_sub(since) < self.opts.regression_grace_secs {
+            true
+        } else {
+            self.regressions.remove(&update.source);
+            false
+        }
+    }
+
+    /// Drop the state reported by `source`, which stopped reporting it
+    ///
+    /// Returns whether there was anything to drop.
+    fn forget(&mut self, source: SourceId) -> bool {
+        let forgotten = self.states.remove(&source).is_some();
+        self.regressions.remove(&source).is_some() || forgotten
+    }
+
+    /// Put back a state saved in a snapshot, before any updates
+    fn restore(&mut self, source: SourceId, state_ts: ChainStateTs) {
+        self.best_height = Some(cmp::max(
+            self.best_height.unwrap_or_default(),
+            state_ts.state.height,
+        ));
+        self.states.insert(source, state_ts);
+    }
+
+    /// Record a new state reported by `source`
+    ///
+    /// Returns the recorded state, if it differs from the previous one.
+    fn record(&mut self, source: SourceId, state_ts: ChainStateTs) -> Option<ChainStateTs> {
+        // the very first height is just whoever got polled first
+        if matches!(self.best_height, Some(best_height) if best_height < state_ts.state.height) {
+            *self.first_seen.entry(source).or_default() += 1;
+            increment_counter!(
+                "chain_monitor_source_first_seen_total",
+                "source" => source.short_name().to_lowercase(),
+                "chain" => self.chain.short_name().to_lowercase(),
+                "ticker" => self.chain.ticker()
+            );
+        }
+
+        self.best_height = Some(cmp::max(
+            self.best_height.unwrap_or_default(),
+            state_ts.state.height,
+        ));
+
+        match self.states.entry(source) {
+            Occupied(mut e) => {
+                let old_state = e.get().clone();
+                let new_state = old_state.update_by(state_ts);
+                e.insert(new_state.clone());
+                Some(new_state).filter(|new_state| new_state.state != old_state.state)
+            }
+            Vacant(e) => {
+                e.insert(state_ts.clone());
+                Some(state_ts)
+            }
+        }
+    }
+
+    fn source_weight(&self, source: SourceId) -> u64 {
+        self.opts
+            .source_weights
+            .get(&source)
+            .copied()
+            .unwrap_or(DEFAULT_SOURCE_WEIGHT)
+    }
+
+    /// Highest height reported by sources with a total weight at least that
+    /// of the heaviest source reporting
+    ///
+    /// With equal weights that's simply the highest height, while a trusted
+    /// source can't be outvoted by a single lighter one running ahead.
+    fn consensus_height(&self) -> Option<ChainHeight> {
+        let quorum = self
+            .states
+            .keys()
+            .map(|source| self.source_weight(*source))
+            .max()?;
+
+        let mut heights: Vec<_> = self
+            .states
+            .iter()
+            .map(|(source, state)| (state.state.height, self.source_weight(*source)))
+            .collect();
+        heights.sort_unstable_by_key(|(height, _)| cmp::Reverse(*height));
+
+        let mut agreeing_weight = 0;
+        heights.into_iter().find_map(|(height, weight)| {
+            agreeing_weight += weight;
+            Some(height).filter(|_| quorum <= agreeing_weight)
+        })
+    }
+
+    /// Ordering of states otherwise equally good, lower is preferred
+    ///
+    /// Heavier sources come first, then the ones listed in `source_priority`,
+    /// then the ones that saw the block earlier. Source id makes it
+    /// deterministic in case of a tie.
+    fn tie_break_key(
+        &self,
+        source: SourceId,
+        state: &ChainStateTs,
+    ) -> (cmp::Reverse<u64>, usize, u64, SourceId) {
+        let source_priority = &self.opts.source_priority;
+        let priority = source_priority
+            .iter()
+            .position(|s| *s == source)
+            .unwrap_or(source_priority.len());
+        (
+            cmp::Reverse(self.source_weight(source)),
+            priority,
+            state.first_seen_ts,
+            source,
+        )
+    }
+
+    /// Pick the best of the states
+    fn best_state(&self) -> Option<(SourceId, &ChainStateTs, BestStateSelection)> {
+        let states = self.states.iter().map(|(source, state)| (*source, state));
+
+        if self.opts.use_chain_work && self.chain.has_chain_work() {
+            let most_work = states
+                .clone()
+                .filter_map(|(source, state)| {
+                    state
+                        .state
+                        .chain_work()
+                        .map(|chain_work| (chain_work, source, state))
+                })
+                .max_by_key(|(chain_work, source, state)| {
+                    (
+                        *chain_work,
+                        cmp::Reverse(self.tie_break_key(*source, state)),
+                    )
+                });
+            if let Some((_, source, state)) = most_work {
+                return Some((source, state, BestStateSelection::ChainWork));
+            }
+        }
+
+        // Note: `best_height` is not used here, as the source that reported it
+        // might have regressed since.
+        let consensus_height = self.consensus_height()?;
+        states
+            .filter(|(_, state)| state.state.height == consensus_height)
+            .max_by_key(|(source, state)| {
+                (
+                    state.state.height,
+                    cmp::Reverse(self.tie_break_key(*source, state)),
+                )
+            })
+            .map(|(source, state)| (source, state, BestStateSelection::Height))
+    }
+
+    /// When was the current best block produced
+    ///
+    /// Uses block timestamps when known, and falls back to when it was first
+    /// seen by any source otherwise.
+    fn best_tip_ts(&self) -> Option<u64> {
+        let best_height = self.best_height?;
+        let best_states = self
+            .states
+            .values()
+            .filter(|state| state.state.height == best_height);
+
+        best_states
+            .clone()
+            .filter_map(|state| state.state.block_time)
+            .max()
+            .or_else(|| best_states.map(|state| state.first_seen_ts).min())
+    }
+
+    /// Height the chain should have reached by `now`, judging by its
+    /// nominal block time
+    fn expected_height(&self, now: u64) -> Option<ChainHeight> {
+        let best_height = self.best_height?;
+        let tip_ts = self.best_tip_ts()?;
+
+        Some(best_height + now.saturating_sub(tip_ts) / u64::from(self.chain.block_time_secs()))
+    }
+
+    /// Height of the best state, and how many sources report a height at
+    /// most `tolerance` blocks away from it
+    fn consensus(&self, tolerance: u64) -> Option<(ChainHeight, usize)> {
+        let (_, best_state, _) = self.best_state()?;
+        let best_height = best_state.state.height;
+        let agreeing = self
+            .states
+            .values()
+            .filter(|state| {
+                cmp::max(state.state.height, best_height)
+                    - cmp::min(state.state.height, best_height)
+                    <= tolerance
+            })
+            .count();
+
+        Some((best_height, agreeing))
+    }
+
+    fn to_best_state(&self, now: u64) -> Option<BestChainStateTs> {
+        self.best_state()
+            .map(|(source, state, selected_by)| BestChainStateTs {
+                source,
+                selected_by,
+                height_kind: self.chain.height_kind(),
+                secondary_height_kind: self.chain.secondary_height_kind(),
+                url: None,
+                tip_age_secs: state.state.tip_age_secs(now),
+                confidence: None,
+                state: state.clone(),
+            })
+    }
+
+    fn confidence(
+        &self,
+        now: u64,
+        tolerance: u64,
+        paused: bool,
+        active_alerts: Vec<alert::AlertKind>,
+    ) -> Option<Confidence> {
+        let (_, best_state, _) = self.best_state()?;
+        let (_, agreeing_sources) = self.consensus(tolerance)?;
+        let total_sources = self.states.len();
+        let checked_age_secs = now.saturating_sub(best_state.last_checked_ts.get());
+
+        let level = if paused
+            || !self.regressions.is_empty()
+            || !active_alerts.is_empty()
+            || CONFIDENCE_FRESH_SECS < checked_age_secs
+        {
+            ConfidenceLevel::Low
+        } else if 1 < agreeing_sources && total_sources < agreeing_sources * 2 {
+            ConfidenceLevel::High
+        } else {
+            ConfidenceLevel::Medium
+        };
+
+        Some(Confidence {
+            level,
+            agreeing_sources,
+            total_sources,
+            checked_age_secs,
+            paused,
+            regressing_sources: self.regressions.len(),
+            active_alerts,
+        })
+    }
+}
+
+/// [`ChainStates`] of a single chain, shared between the recorder and readers
+///
+/// Updates of different chains don't contend with each other, and readers
+/// never wait for the recorder, working on the latest published snapshot instead.
+struct ChainShard {
+    states: Mutex<ChainStates>,
+    snapshot: watch::Sender<Arc<ChainStates>>,
+}
+
+impl ChainShard {
+    fn new(chain: ChainId, opts: Arc<ChainStatesOpts>) -> Self {
+        let states = ChainStates::new(chain, opts);
+        let (snapshot, _rx) = watch::channel(Arc::new(states.clone()));
+        Self {
+            states: Mutex::new(states),
+            snapshot,
+        }
+    }
+
+    fn snapshot(&self) -> Arc<ChainStates> {
+        self.snapshot.borrow().clone()
+    }
+
+    /// Modify the states, publishing the result to readers only if `f`
+    /// returns that it changed anything they see
+    async fn modify<R>(&self, f: impl FnOnce(&mut ChainStates) -> (R, bool)) -> R {
+        let mut states = self.states.lock().await;
+        let (res, changed) = f(&mut states);
+        if changed {
+            self.snapshot.send_replace(Arc::new(states.clone()));
+        }
+        res
+    }
+}
+
+/// Polls requested through `/admin/refresh`, bypassing rate limiting
+#[derive(Default)]
+struct RefreshRequests {
+    /// Number of the latest request
+    last: u64,
+    /// Number of the latest request of a chain, by a single source or any
+    latest: HashMap<(ChainId, Option<SourceId>), u64>,
+}
+
+// Our shared state
+pub struct AppState {
+    /// Grows as mirrored sources report their first states, see [`AppState::add_source`]
+    sources: std::sync::RwLock<Vec<SourceInfo>>,
+    chains: Vec<ChainInfo>,
+    urls: Vec<SourceChainUrl>,
+    chain_states_opts: Arc<ChainStatesOpts>,
+    chain_states: HashMap<ChainId, ChainShard>,
+    custom_milestones: Vec<milestone::CustomMilestone>,
+    behind_schedule_alert_blocks: u64,
+    /// See [`opts::Opts::chain_tunings`]
+    chain_tunings: HashMap<ChainId, opts::Tuning>,
+    consensus_tolerance_blocks: u64,
+    reject_malformed_hashes: bool,
+    alerts: Mutex<alert::Alerts>,
+    mempool_stats: SourceStats<MempoolStats>,
+    difficulty_stats: SourceStats<DifficultyStats>,
+    source_statuses: Mutex<HashMap<SourceId, SourceStatus>>,
+    skipped_sources: source::SkippedSources,
+    /// Chains neither polled nor alerted on, until resumed
+    paused_chains: Mutex<HashSet<ChainId>>,
+    refresh_requests: Mutex<RefreshRequests>,
+    /// Wakes up sources waiting for their next round, see [`supervisor`]
+    refresh_notify: Notify,
+    webhooks: webhook::Webhooks,
+    notifiers: Vec<Arc<dyn notifier::Notifier>>,
+    silences: Mutex<silence::Silences>,
+    clock_status: Mutex<Option<clock::ClockStatus>>,
+    ws_tokens: Vec<opts::WsToken>,
+    /// Leave all but the mainnets out of the API, see [`Opts::hide_testnets`]
+    hide_testnets: bool,
+    /// Where to log accepted updates, if enabled with `--journal`
+    journal: Option<journal::Writer>,
+    tx: broadcast::Sender<ChainStateUpdateTs>,
 }
 
-impl ChainStates {
-    fn to_best_states(&self) -> HashMap<&'static str, ChainStateTs> {
-        self.best_height
-            .iter()
-            .filter_map(|(best_height_chain, best_height)| {
-                self.states
-                    .iter()
-                    .filter(|((_, state_chain), state)| {
-                        best_height_chain == state_chain && state.state.height == *best_height
-                    })
-                    .next()
-                    .map(|s| (best_height_chain.ticker(), s.1.clone()))
-            })
-            .collect()
+impl AppState {
+    fn chain_snapshots(&self) -> impl Iterator<Item = Arc<ChainStates>> + '_ {
+        self.chain_states.values().map(ChainShard::snapshot)
+    }
+
+    async fn get_all_chain_states(&self) -> Vec<ChainStateUpdateTs> {
+        self.chain_snapshots()
+            .flat_map(|chain_states| {
+                chain_states
+                    .states
+                    .iter()
+                    .map(|(source, state)| ChainStateUpdateTs {
+                        source: *source,
+                        chain: chain_states.chain,
+                        state: state.clone(),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    fn snapshot_entries(&self) -> Vec<snapshot::Entry> {
+        self.chain_snapshots()
+            .flat_map(|chain_states| {
+                chain_states
+                    .states
+                    .iter()
+                    .map(|(source, state)| snapshot::Entry {
+                        source: source.short_name().to_owned(),
+                        ticker: chain_states.chain.ticker().to_owned(),
+                        state: state.clone(),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Load the states of a snapshot, skipping the ones of chains and
+    /// sources no longer in use
+    async fn restore_snapshot(&self, entries: Vec<snapshot::Entry>) {
+        let mut restored = 0;
+        for entry in entries {
+            let source = self
+                .sources()
+                .into_iter()
+                .find(|source_info| source_info.short_name == entry.source);
+            let shard =
+                ChainId::from_ticker(&entry.ticker).and_then(|chain| self.chain_states.get(&chain));
+            if let (Some(source_info), Some(shard)) = (source, shard) {
+                shard
+                    .modify(|chain_states| {
+                        (chain_states.restore(source_info.id, entry.state), true)
+                    })
+                    .await;
+                restored += 1;
+            }
+        }
+        tracing::info!("Restored {restored} chain states from the snapshot");
+    }
+
+    fn confidence_of(
+        &self,
+        chain_states: &ChainStates,
+        now: u64,
+        paused_chains: &HashSet<ChainId>,
+        alerts: &[alert::Alert],
+    ) -> Option<Confidence> {
+        let chain = chain_states.chain;
+        chain_states.confidence(
+            now,
+            self.consensus_tolerance_blocks,
+            paused_chains.contains(&chain),
+            alerts
+                .iter()
+                .filter(|alert| alert.chain() == chain)
+                .map(alert::Alert::kind)
+                .collect(),
+        )
+    }
+
+    /// Confidence in the current best state of `chain`
+    async fn get_confidence(&self, chain: ChainId) -> Option<Confidence> {
+        let chain_states = self.chain_states.get(&chain)?.snapshot();
+        let paused_chains = self.paused_chains.lock().await.clone();
+        let alerts = self.get_alerts().await;
+
+        self.confidence_of(&chain_states, get_now_ts(), &paused_chains, &alerts)
+    }
+
+    /// Best height, tip age and agreeing sources of every chain, and height,
+    /// blocks behind the best one and delay of seeing the block of every source
+    fn measurements(&self) -> Vec<measurement::Measurement> {
+        let now = get_now_ts();
+        let mut measurements = vec![];
+
+        for chain_states in self.chain_snapshots() {
+            let chain = chain_states.chain;
+            let best_height = match chain_states.best_height {
+                Some(best_height) => best_height,
+                None => continue,
+            };
+
+            let mut fields = vec![("best_height", best_height.as_f64())];
+            if let Some(tip_ts) = chain_states.best_tip_ts() {
+                fields.push(("tip_age_secs", now.saturating_sub(tip_ts) as f64));
+            }
+            if let Some((_, agreeing)) = chain_states.consensus(self.consensus_tolerance_blocks) {
+                fields.push(("consensus_sources", agreeing as f64));
+            }
+            measurements.push(measurement::Measurement {
+                ts: now,
+                chain,
+                source: None,
+                fields,
+            });
+
+            for (source, state) in &chain_states.states {
+                let mut fields = vec![
+                    ("height", state.state.height.as_f64()),
+                    (
+                        "lag_blocks",
+                        (cmp::max(best_height, state.state.height) - state.state.height) as f64,
+                    ),
+                ];
+                // since the first source saw the same block
+                let first_seen_ts = chain_states
+                    .states
+                    .values()
+                    .filter(|other| other.state.hash == state.state.hash)
+                    .map(|other| other.first_seen_ts)
+                    .min();
+                if let Some(first_seen_ts) = first_seen_ts {
+                    fields.push((
+                        "latency_secs",
+                        state.first_seen_ts.saturating_sub(first_seen_ts) as f64,
+                    ));
+                }
+                measurements.push(measurement::Measurement {
+                    ts: now,
+                    chain,
+                    source: Some(*source),
+                    fields,
+                });
+            }
+        }
+
+        measurements
+    }
+
+    /// Compare a chain with the thresholds of a `/check`, returning its status
+    /// and the plain text line describing it
+    fn check(&self, params: &check::CheckParams) -> (check::CheckStatus, String) {
+        use check::{perf_data, threshold_status, CheckStatus};
+
+        let unknown = |message: String| (CheckStatus::Unknown, format!("UNKNOWN - {message}"));
+        let chain_states = match ChainId::from_ticker(&params.chain)
+            .and_then(|chain| self.chain_states.get(&chain))
+        {
+            Some(shard) => shard.snapshot(),
+            None => return unknown(format!("chain not monitored: {}", params.chain)),
+        };
+        let now = get_now_ts();
+        let (best_height, expected_height, tip_ts) = match (
+            chain_states.best_height,
+            chain_states.expected_height(now),
+            chain_states.best_tip_ts(),
+        ) {
+            (Some(best_height), Some(expected_height), Some(tip_ts)) => {
+                (best_height, expected_height, tip_ts)
+            }
+            _ => return unknown(format!("no height of {} known yet", params.chain)),
+        };
+
+        let lag = expected_height - best_height;
+        let age = now.saturating_sub(tip_ts);
+        let status = cmp::max(
+            threshold_status(lag, params.warn_lag, params.max_lag),
+            threshold_status(age, params.warn_age, params.max_age),
+        );
+        (
+            status,
+            format!(
+                "{status} - {} at height {best_height}, {lag} blocks behind schedule, tip {age}s old | {} {}",
+                params.chain,
+                perf_data("lag", lag, params.warn_lag, params.max_lag),
+                perf_data("age", age, params.warn_age, params.max_age),
+            ),
+        )
+    }
+
+    fn shows_chain(&self, chain: ChainId, network: Option<NetworkType>) -> bool {
+        shows_network(chain, network, self.hide_testnets)
+    }
+
+    fn get_chains(&self, network: Option<NetworkType>) -> Vec<ChainInfo> {
+        self.chains
+            .iter()
+            .filter(|chain_info| self.shows_chain(chain_info.id, network))
+            .cloned()
+            .collect()
+    }
+
+    async fn get_best_states(
+        &self,
+        network: Option<NetworkType>,
+    ) -> HashMap<&'static str, BestChainStateTs> {
+        let now = get_now_ts();
+        let paused_chains = self.paused_chains.lock().await.clone();
+        let alerts = self.get_alerts().await;
+        let mut best_states: HashMap<_, _> = self
+            .chain_snapshots()
+            .filter(|chain_states| self.shows_chain(chain_states.chain, network))
+            .filter_map(|chain_states| {
+                chain_states.to_best_state(now).map(|best_state| {
+                    (
+                        chain_states.chain.ticker(),
+                        BestChainStateTs {
+                            confidence: self.confidence_of(
+                                &chain_states,
+                                now,
+                                &paused_chains,
+                                &alerts,
+                            ),
+                            ..best_state
+                        },
+                    )
+                })
+            })
+            .collect();
+
+        for url in &self.urls {
+            if let Some(best_state) = best_states.get_mut(url.chain.ticker()) {
+                if best_state.source == url.source {
+                    best_state.url = Some(url.to_block_url(&best_state.state.state));
+                }
+            }
+        }
+
+        best_states
+    }
+
+    /// Compare chain heights with their expected heights, exporting the
+    /// difference and alerting on chains that seem to have halted
+    async fn check_schedule(&self) {
+        let now = get_now_ts();
+        let mut firing = vec![];
+        let paused_chains = self.paused_chains.lock().await.clone();
+
+        for chain_states in self.chain_snapshots() {
+            let chain = &chain_states.chain;
+            if paused_chains.contains(chain) {
+                continue;
+            }
+            let (best_height, expected_height) =
+                match (chain_states.best_height, chain_states.expected_height(now)) {
+                    (Some(best_height), Some(expected_height)) => (best_height, expected_height),
+                    _ => continue,
+                };
+            let behind_schedule = expected_height - best_height;
+
+            let labels = [
+                ("chain", chain.short_name().to_lowercase()),
+                ("ticker", chain.ticker().to_owned()),
+                ("network_type", chain.network_type().to_string()),
+            ];
+            gauge!(
+                "chain_monitor_chain_expected_height",
+                expected_height.as_f64(),
+                &labels
+            );
+            gauge!(
+                "chain_monitor_chain_behind_schedule_blocks",
+                behind_schedule as f64,
+                &labels
+            );
+
+            let alert_blocks = cmp::max(
+                self.tuning(*chain)
+                    .stall_multiplier
+                    .unwrap_or(self.behind_schedule_alert_blocks),
+                POLLING_LAG_SECS / u64::from(chain.block_time_secs()),
+            );
+            if alert_blocks <= behind_schedule {
+                firing.push(alert::Firing {
+                    chain: *chain,
+                    kind: alert::AlertKind::BehindSchedule,
+                    message: format!(
+                        "at height {best_height}, expected {expected_height} ({behind_schedule} blocks behind schedule)"
+                    ),
+                });
+            }
+        }
+
+        let events = self
+            .alerts
+            .lock()
+            .await
+            .update(alert::AlertKind::BehindSchedule, firing, now);
+        self.notify(events, now).await;
+    }
+
+    /// Notify of alert `events`, except the silenced ones
+    async fn notify(&self, mut events: Vec<alert::AlertEvent>, now: u64) {
+        {
+            let silences = self.silences.lock().await;
+            events.retain(|event| {
+                let chain = event.alert.chain();
+                let sources: Vec<_> = self
+                    .chain_states
+                    .get(&chain)
+                    .map(|shard| shard.snapshot().states.keys().copied().collect())
+                    .unwrap_or_default();
+                let silenced = silences.is_silenced(chain, &sources, now);
+                if silenced {
+                    tracing::info!("Not notifying of silenced {:?} of {chain:?}", event.event);
+                }
+                !silenced
+            });
+        }
+        self.webhooks.notify(&events).await;
+        notifier::notify_all(&self.notifiers, &events);
+    }
+
+    async fn add_silence(
+        &self,
+        target: silence::SilenceTarget,
+        duration_secs: u64,
+        reason: String,
+    ) -> silence::Silence {
+        let silence = self
+            .silences
+            .lock()
+            .await
+            .add(target, duration_secs, reason, get_now_ts());
+        tracing::info!("Silenced {target:?} for {duration_secs}s");
+        silence
+    }
+
+    async fn remove_silence(&self, id: u64) -> bool {
+        self.silences.lock().await.remove(id)
+    }
+
+    async fn set_chain_paused(&self, chain: ChainId, paused: bool) {
+        let mut paused_chains = self.paused_chains.lock().await;
+        let changed = if paused {
+            paused_chains.insert(chain)
+        } else {
+            paused_chains.remove(&chain)
+        };
+        if changed {
+            tracing::info!("{} {:?}", if paused { "Paused" } else { "Resumed" }, chain);
+        }
+    }
+
+    /// Tickers of the paused chains
+    async fn get_paused_chains(&self) -> Vec<&'static str> {
+        let mut tickers: Vec<_> = self
+            .paused_chains
+            .lock()
+            .await
+            .iter()
+            .map(|chain| chain.ticker())
+            .collect();
+        tickers.sort_unstable();
+        tickers
+    }
+
+    /// Make `chain` polled right away, by `source` or all sources
+    ///
+    /// Returns `false` if the chain isn't monitored.
+    async fn request_refresh(&self, chain: ChainId, source: Option<SourceId>) -> bool {
+        if !self.chain_states.contains_key(&chain) {
+            return false;
+        }
+
+        {
+            let mut requests = self.refresh_requests.lock().await;
+            requests.last += 1;
+            let last = requests.last;
+            requests.latest.insert((chain, source), last);
+        }
+        tracing::info!("Refresh of {chain:?} requested from {source:?}");
+        self.refresh_notify.notify_waiters();
+        true
+    }
+
+    async fn get_alerts(&self) -> Vec<alert::Alert> {
+        self.alerts.lock().await.active()
+    }
+
+    async fn get_milestones(&self) -> HashMap<&'static str, Vec<milestone::Milestone>> {
+        let now = get_now_ts();
+        self.chain_snapshots()
+            .filter_map(|chain_states| {
+                let chain = chain_states.chain;
+                chain_states.best_height.map(|best_height| {
+                    (
+                        chain.ticker(),
+                        milestone::upcoming(chain, best_height, &self.custom_milestones, now),
+                    )
+                })
+            })
+            .filter(|(_, milestones)| !milestones.is_empty())
+            .collect()
+    }
+
+    async fn get_mempool_stats(&self) -> HashMap<&'static str, Vec<SourceStatsTs<MempoolStats>>> {
+        SourceStatsTs::by_ticker(&self.mempool_stats).await
+    }
+
+    async fn get_difficulty_stats(
+        &self,
+    ) -> HashMap<&'static str, Vec<SourceStatsTs<DifficultyStats>>> {
+        SourceStatsTs::by_ticker(&self.difficulty_stats).await
+    }
+
+    async fn get_source_statuses(&self) -> HashMap<&'static str, SourceStatus> {
+        let statuses = self.source_statuses.lock().await;
+
+        self.sources()
+            .iter()
+            .map(|source_info| {
+                (
+                    source_info.short_name,
+                    statuses.get(&source_info.id).cloned().unwrap_or_default(),
+                )
+            })
+            .chain(self.skipped_sources.iter().map(|&(source, reason)| {
+                (
+                    source.short_name(),
+                    SourceStatus {
+                        skipped: Some(reason),
+                        ..Default::default()
+                    },
+                )
+            }))
+            .collect()
+    }
+
+    /// Mark `sources` unhealthy, see [`supervisor::supervise`]
+    async fn record_source_panic(&self, sources: &HashSet<SourceId>, message: String) {
+        let now = get_now_ts();
+        let mut statuses = self.source_statuses.lock().await;
+
+        for &source in sources {
+            increment_counter!(
+                "chain_monitor_source_panics_total",
+                "source" => source.short_name().to_lowercase()
+            );
+
+            let status = statuses.entry(source).or_default();
+            status.healthy = false;
+            status.panics_total += 1;
+            status.last_panic = Some(SourcePanicTs {
+                message: message.clone(),
+                ts: now,
+            });
+        }
+    }
+
+    /// How many times was each source the first to report a new height, by chain
+    async fn get_first_seen_stats(&self) -> HashMap<&'static str, HashMap<&'static str, u64>> {
+        self.chain_snapshots()
+            .filter(|chain_states| !chain_states.first_seen.is_empty())
+            .map(|chain_states| {
+                (
+                    chain_states.chain.ticker(),
+                    chain_states
+                        .first_seen
+                        .iter()
+                        .map(|(source, count)| (source.short_name(), *count))
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    fn get_version_info(&self) -> VersionInfo {
+        let git_commit = match env!("CHAIN_MONITOR_GIT_COMMIT") {
+            "" => None,
+            git_commit => Some(git_commit),
+        };
+        VersionInfo {
+            version: env!("CARGO_PKG_VERSION"),
+            git_commit,
+            build_ts: env!("CHAIN_MONITOR_BUILD_TS").parse().unwrap_or_default(),
+            features: env!("CHAIN_MONITOR_FEATURES")
+                .split(',')
+                .filter(|feature| !feature.is_empty())
+                .collect(),
+            sources: self
+                .sources()
+                .iter()
+                .map(|source_info| source_info.short_name)
+                .collect(),
+        }
+    }
+
+    async fn get_status(&self) -> Status {
+        Status {
+            sources: self.get_source_statuses().await,
+            clock: self.clock_status.lock().await.clone(),
+            silences: self.silences.lock().await.current(get_now_ts()),
+        }
+    }
+
+    /// Compare the local clock with the one of `url`, warning on a skew
+    async fn check_clock(&self, client: &reqwest::Client, url: &str) {
+        let status = match clock::check(client, url).await {
+            Ok(status) => status,
+            Err(e) => {
+                tracing::warn!("Couldn't check the clock against {url}: {e}");
+                return;
+            }
+        };
+
+        let skew_secs = status.skew_secs();
+        if clock::WARN_SKEW_SECS <= skew_secs.abs() {
+            tracing::warn!(
+                "Local clock is {skew_secs}s off from {url}; first-seen times will be off too"
+            );
+        } else {
+            debug!("Local clock is {skew_secs}s off from {url}");
+        }
+        gauge!("chain_monitor_clock_skew_seconds", skew_secs as f64);
+
+        *self.clock_status.lock().await = Some(status);
+    }
+
+    /// Export the success rates of sources as gauges
+    async fn export_source_availability(&self) {
+        let now = get_now_ts();
+        let statuses = self.source_statuses.lock().await;
+
+        for (source, status) in statuses.iter() {
+            for (window, window_secs) in availability::WINDOWS {
+                if let Some(success_rate) = status.success_rate.success_rate(*window_secs, now) {
+                    gauge!(
+                        "chain_monitor_source_success_rate",
+                        success_rate,
+                        "source" => source.short_name().to_lowercase(),
+                        "window" => *window
+                    );
+                }
+            }
+        }
+    }
+
+    async fn mark_sources_healthy(&self, sources: &HashSet<SourceId>) {
+        let mut statuses = self.source_statuses.lock().await;
+
+        for &source in sources {
+            statuses.entry(source).or_default().healthy = true;
+        }
     }
-}
 
-// Our shared state
-pub struct AppState {
-    sources: Vec<SourceInfo>,
-    chains: Vec<ChainInfo>,
-    chain_states: Mutex<ChainStates>,
-    tx: broadcast::Sender<ChainStateUpdateTs>,
-}
+    /// Chains a websocket client presenting `token` can see, `None` if it
+    /// isn't allowed to connect at all
+    fn authorize_ws(&self, token: Option<&str>) -> Option<ChainFilter> {
+        if self.ws_tokens.is_empty() {
+            return Some(ChainFilter {
+                chains: None,
+                network: None,
+                hide_testnets: self.hide_testnets,
+            });
+        }
 
-impl AppState {
-    async fn get_all_chain_states(&self) -> Vec<ChainStateUpdateTs> {
-        self.chain_states
-            .lock()
-            .await
-            .states
+        let ws_token = self
+            .ws_tokens
             .iter()
-            .map(|(k, v)| ChainStateUpdateTs {
-                source: k.0.clone(),
-                chain: k.1.clone(),
-                state: v.clone(),
-            })
-            .collect()
+            .find(|ws_token| Some(ws_token.token.as_str()) == token)?;
+        tracing::info!("Websocket client authorized as `{}`", ws_token.name);
+        Some(ChainFilter {
+            chains: ws_token.chains.clone(),
+            network: None,
+            hide_testnets: self.hide_testnets,
+        })
     }
 
     fn subscribe_to_updates(&self) -> broadcast::Receiver<ChainStateUpdateTs> {
         self.tx.subscribe()
     }
 
-    pub fn add_source(&mut self, source: SourceId) {
-        match self
-            .sources
-            .binary_search_by_key(&source, |source_info| source_info.id)
-        {
+    fn sources(&self) -> Vec<SourceInfo> {
+        self.sources.read().expect("not poisoned").clone()
+    }
+
+    /// Register `source`, if it isn't yet
+    ///
+    /// Besides the sources known at startup, this registers sources seen
+    /// through mirrors once they report something.
+    pub fn add_source(&self, source: SourceId) {
+        let known = |sources: &[SourceInfo]| {
+            sources.binary_search_by_key(&source, |source_info| source_info.id)
+        };
+        if known(&self.sources.read().expect("not poisoned")).is_ok() {
+            return;
+        }
+
+        let mut sources = self.sources.write().expect("not poisoned");
+        match known(&sources) {
             Ok(_pos) => {}
             Err(pos) => {
-                self.sources.insert(
+                sources.insert(
                     pos,
                     SourceInfo {
                         id: source,
-                        url: "tbd".into(),
+                        url: source.url(),
                         short_name: source.short_name(),
                         full_name: source.full_name(),
                     },
@@ -210,7 +1515,7 @@ impl AppState {
             }
         }
     }
-    fn add_sources(&mut self, sources: std::collections::HashSet<SourceId>) {
+    fn add_sources(&self, sources: HashSet<SourceId>) {
         for source in sources {
             self.add_source(source);
         }
@@ -232,23 +1537,82 @@ impl AppState {
                         block_time_secs: chain.block_time_secs(),
                         short_name: chain.short_name(),
                         full_name: chain.full_name(),
+                        network_type: chain.network_type(),
+                        height_kind: chain.height_kind(),
+                        secondary_height_kind: chain.secondary_height_kind(),
                     },
                 );
+                self.chain_states.insert(
+                    chain,
+                    ChainShard::new(chain, self.chain_states_opts.clone()),
+                );
             }
         }
     }
-    fn add_chains(&mut self, chains: std::collections::HashSet<ChainId>) {
+    fn add_chains(&mut self, chains: HashSet<ChainId>) {
         for chain in chains {
             self.add_chain(chain);
         }
     }
 
-    fn new() -> AppState {
-        let (tx, _rx) = tokio::sync::broadcast::channel(1000);
+    fn add_urls(&mut self, source: &dyn Source) {
+        for source_info in self.sources() {
+            for chain_info in &self.chains {
+                if let Some(url) = source.url_for_chain(source_info.id, chain_info.id) {
+                    self.urls.push(SourceChainUrl {
+                        source: source_info.id,
+                        chain: chain_info.id,
+                        url,
+                    });
+                }
+            }
+        }
+    }
+
+    fn new(opts: &Opts) -> AppState {
+        let (tx, _rx) = tokio::sync::broadcast::channel(opts.broadcast_capacity);
         AppState {
             sources: Default::default(),
             chains: Default::default(),
-            chain_states: Mutex::new(ChainStates::default()),
+            urls: Default::default(),
+            chain_states_opts: Arc::new(ChainStatesOpts {
+                use_chain_work: opts.chainwork_best_state,
+                source_priority: opts.source_priority.clone(),
+                source_weights: opts
+                    .source_weights
+                    .iter()
+                    .map(|w| (w.source, w.weight))
+                    .collect(),
+                regression_grace_secs: opts.regression_grace_secs,
+            }),
+            chain_states: Default::default(),
+            custom_milestones: opts.milestones.clone(),
+            behind_schedule_alert_blocks: opts.behind_schedule_alert_blocks,
+            chain_tunings: opts
+                .chain_tunings
+                .iter()
+                .map(|tuning| (tuning.chain, opts.tuning(tuning.chain)))
+                .collect(),
+            consensus_tolerance_blocks: opts.consensus_tolerance_blocks,
+            reject_malformed_hashes: opts.reject_malformed_hashes,
+            alerts: Mutex::new(alert::Alerts::new(alert::AlertsOpts {
+                resolve_after_secs: opts.alert_resolve_after_secs,
+                escalate_after_secs: opts.alert_escalate_after_secs,
+            })),
+            mempool_stats: Default::default(),
+            difficulty_stats: Default::default(),
+            source_statuses: Default::default(),
+            skipped_sources: Default::default(),
+            paused_chains: Default::default(),
+            refresh_requests: Default::default(),
+            refresh_notify: Notify::new(),
+            webhooks: Default::default(),
+            notifiers: vec![],
+            silences: Mutex::new(silence::Silences::new(&opts.maintenance_windows)),
+            clock_status: Default::default(),
+            ws_tokens: opts.ws_tokens.clone(),
+            hide_testnets: opts.hide_testnets,
+            journal: None,
             tx,
         }
     }
@@ -257,7 +1621,27 @@ impl AppState {
 #[async_trait]
 pub trait ChainUpdateRecorder: Sync {
     async fn update(&self, update: ChainStateUpdate);
-    async fn how_far_behind(&self, source: SourceId, chain: ChainId) -> ChainHeight;
+    async fn update_mempool(&self, update: MempoolStatsUpdate);
+    async fn update_difficulty(&self, update: DifficultyStatsUpdate);
+    /// Record a failure of `source`, optionally specific to `chain`
+    async fn report_error(&self, source: SourceId, chain: Option<ChainId>, error: SourceError);
+    /// How many blocks behind the best known height is `source`
+    async fn how_far_behind(&self, source: SourceId, chain: ChainId) -> u64;
+    /// How long ago was the best known block of `chain` found
+    async fn tip_age_secs(&self, chain: ChainId) -> Option<u64>;
+    /// Whether `chain` was paused, and shouldn't be polled
+    async fn is_paused(&self, chain: ChainId) -> bool;
+    /// Number of the latest request to poll `chain` by `source` right away,
+    /// growing with every request; 0 if there was none
+    async fn refresh_requested(&self, source: SourceId, chain: ChainId) -> u64;
+    /// Settings of `chain` given with `--chain-tuning`
+    fn tuning(&self, chain: ChainId) -> opts::Tuning;
+    /// Record that `source` still reports the same state of `chain`, without
+    /// having fetched it again
+    async fn unchanged(&self, source: SourceId, chain: ChainId);
+    /// Drop the state of `chain` reported by `source`, as it stopped
+    /// reporting it (e.g. a mirror now attributing it to another source)
+    async fn forget(&self, source: SourceId, chain: ChainId);
 }
 
 #[async_trait]
@@ -267,41 +1651,193 @@ impl ChainUpdateRecorder for AppState {
             "{:?} {:?} update: {}",
             update.source, update.chain, update.state.height
         );
+        self.add_source(update.source);
+
+        let labels = [
+            ("source", update.source.short_name().to_lowercase()),
+            ("chain", update.chain.short_name().to_lowercase()),
+            ("ticker", update.chain.ticker().to_owned()),
+            ("network_type", update.chain.network_type().to_string()),
+            ("source_full_name", update.source.full_name().to_owned()),
+            ("chain_full_name", update.chain.full_name().to_owned()),
+        ];
+
+        self.source_statuses
+            .lock()
+            .await
+            .entry(update.source)
+            .or_default()
+            .success_rate
+            .record(true, get_now_ts());
+
+        let shard = match self.chain_states.get(&update.chain) {
+            Some(shard) => shard,
+            None => {
+                tracing::warn!(
+                    "{:?} {:?} update for a chain that is not monitored",
+                    update.source,
+                    update.chain
+                );
+                return;
+            }
+        };
 
-        gauge!(
-            "chain_monitor_chain_height",
-            update.state.height as f64,
-            "source" => update.source.short_name().to_lowercase(),
-            "chain" => update.chain.short_name().to_lowercase(),
-            "ticker" => update.chain.ticker(),
-            "network_type" => update.chain.network_type().to_string(),
-            "source_full_name" => update.source.full_name(),
-            "chain_full_name" => update.chain.full_name(),
-        );
+        // sources pushing updates on their own don't check for pausing
+        if self.is_paused(update.chain).await {
+            debug!(
+                "{:?} {:?} ignoring update of a paused chain",
+                update.source, update.chain
+            );
+            return;
+        }
+
+        // checked on the snapshot, to avoid taking the lock in the common case
+        let unchanged = shard
+            .snapshot()
+            .states
+            .get(&update.source)
+            .map(|state_ts| &state_ts.state)
+            == Some(&update.state);
+        if unchanged {
+            // tip age is the only thing changing over time
+            if let Some(tip_age_secs) = update.state.tip_age_secs(get_now_ts()) {
+                gauge!(
+                    "chain_monitor_chain_tip_age_seconds",
+                    tip_age_secs as f64,
+                    &labels
+                );
+            }
+            shard
+                .modify(|chain_states| ((), chain_states.touch(update.source, get_now_ts())))
+                .await;
+            return;
+        }
 
-        let (broadcast_update, state_ts) = {
-            let state_ts = update.state.to_state_ts();
-            let mut chain_states = self.chain_states.lock().await;
+        // sources without block hashes report the height in their place
+        let validated = if update.state.hash.is_height(update.state.height) {
+            Ok(())
+        } else {
+            update.state.hash.validate(update.chain)
+        };
+        if let Err(e) = validated {
+            tracing::warn!(
+                "{:?} {:?} reported malformed hash `{}`: {e}",
+                update.source,
+                update.chain,
+                update.state.hash
+            );
+            increment_counter!("chain_monitor_malformed_hashes_total", &labels);
+            if self.reject_malformed_hashes {
+                return;
+            }
+        }
 
-            {
-                let best_height = chain_states.best_height.entry(update.chain).or_insert(0);
-                *best_height = cmp::max(*best_height, state_ts.state.height);
+        if let Some(block_time) = update.state.block_time {
+            if get_now_ts() + clock::MAX_FUTURE_BLOCK_TIME_SECS < block_time {
+                tracing::warn!(
+                    "{:?} {:?} reported a block time {}s in the future; is the local clock right?",
+                    update.source,
+                    update.chain,
+                    block_time - get_now_ts()
+                );
+                increment_counter!("chain_monitor_future_block_times_total", &labels);
             }
+        }
 
-            match chain_states.states.entry((update.source, update.chain)) {
-                Occupied(mut e) => {
-                    let old_state = e.get().clone();
-                    let new_state = old_state.update_by(state_ts);
-                    e.insert(new_state.clone());
-                    (new_state.state != old_state.state, new_state)
-                }
-                Vacant(e) => {
-                    e.insert(state_ts.clone());
-                    (true, state_ts)
+        let recorded = shard
+            .modify(|chain_states| {
+                let regressions = chain_states.regressions.len();
+                if chain_states.check_regression(&update, get_now_ts()) {
+                    (None, regressions != chain_states.regressions.len())
+                } else {
+                    let state_ts = update.state.clone().to_state_ts();
+                    (Some(chain_states.record(update.source, state_ts)), true)
                 }
+            })
+            .await;
+        let changed = match recorded {
+            Some(changed) => changed,
+            None => {
+                debug!(
+                    "{:?} {:?} holding previous state during regression grace period",
+                    update.source, update.chain
+                );
+                return;
             }
         };
-        if broadcast_update {
+
+        gauge!(
+            "chain_monitor_chain_height",
+            update.state.height.as_f64(),
+            &[
+                &labels[..],
+                &[("height_kind", update.chain.height_kind().to_string())],
+            ]
+            .concat()
+        );
+
+        if let (Some(secondary_height), Some(secondary_height_kind)) = (
+            update.state.secondary_height,
+            update.chain.secondary_height_kind(),
+        ) {
+            gauge!(
+                "chain_monitor_chain_secondary_height",
+                secondary_height.as_f64(),
+                &[
+                    &labels[..],
+                    &[("height_kind", secondary_height_kind.to_string())],
+                ]
+                .concat()
+            );
+        }
+
+        if let Some(tip_age_secs) = update.state.tip_age_secs(get_now_ts()) {
+            gauge!(
+                "chain_monitor_chain_tip_age_seconds",
+                tip_age_secs as f64,
+                &labels
+            );
+        }
+
+        if let Some(safe_height) = update.state.safe_height {
+            gauge!(
+                "chain_monitor_chain_safe_height",
+                safe_height.as_f64(),
+                &labels
+            );
+        }
+
+        if let Some(finalized_height) = update.state.finalized_height {
+            gauge!(
+                "chain_monitor_chain_finalized_height",
+                finalized_height.as_f64(),
+                &labels
+            );
+        }
+
+        if let Some(journal) = self.journal.as_ref() {
+            let entry = journal::Entry {
+                ts: get_now_ts(),
+                source: update.source.short_name().to_owned(),
+                ticker: update.chain.ticker().to_owned(),
+                state: update.state.clone(),
+            };
+            journal.append(entry);
+        }
+
+        if let Some((best_height, agreeing)) =
+            shard.snapshot().consensus(self.consensus_tolerance_blocks)
+        {
+            let labels = [
+                ("chain", update.chain.short_name().to_lowercase()),
+                ("ticker", update.chain.ticker().to_owned()),
+                ("network_type", update.chain.network_type().to_string()),
+            ];
+            gauge!("chain_monitor_best_height", best_height.as_f64(), &labels);
+            gauge!("chain_monitor_consensus_sources", agreeing as f64, &labels);
+        }
+
+        if let Some(state_ts) = changed {
             // we don't care if anyone is subscribed
             let _ = self.tx.send(ChainStateUpdateTs {
                 source: update.source,
@@ -310,18 +1846,184 @@ impl ChainUpdateRecorder for AppState {
             });
         }
     }
-    async fn how_far_behind(&self, source: SourceId, chain: ChainId) -> ChainHeight {
-        let chain_states = self.chain_states.lock().await;
+    async fn update_mempool(&self, update: MempoolStatsUpdate) {
+        debug!(
+            "{:?} {:?} mempool update: {} txs",
+            update.source, update.chain, update.stats.tx_count
+        );
+
+        let labels = [
+            ("source", update.source.short_name().to_lowercase()),
+            ("chain", update.chain.short_name().to_lowercase()),
+            ("ticker", update.chain.ticker().to_owned()),
+            ("network_type", update.chain.network_type().to_string()),
+        ];
+
+        gauge!(
+            "chain_monitor_mempool_tx_count",
+            update.stats.tx_count as f64,
+            &labels
+        );
+        gauge!(
+            "chain_monitor_mempool_vsize_bytes",
+            update.stats.vsize as f64,
+            &labels
+        );
+        if let Some(min_fee_rate) = update.stats.min_fee_rate {
+            gauge!("chain_monitor_mempool_min_fee_rate", min_fee_rate, &labels);
+        }
+
+        SourceStatsTs::record(
+            &self.mempool_stats,
+            update.source,
+            update.chain,
+            update.stats,
+        )
+        .await;
+    }
+
+    async fn update_difficulty(&self, update: DifficultyStatsUpdate) {
+        debug!(
+            "{:?} {:?} difficulty update: {}",
+            update.source, update.chain, update.stats.difficulty
+        );
+
+        let labels = [
+            ("source", update.source.short_name().to_lowercase()),
+            ("chain", update.chain.short_name().to_lowercase()),
+            ("ticker", update.chain.ticker().to_owned()),
+            ("network_type", update.chain.network_type().to_string()),
+        ];
+
+        gauge!("chain_monitor_difficulty", update.stats.difficulty, &labels);
+        if let Some(remaining_blocks) = update.stats.remaining_blocks {
+            gauge!(
+                "chain_monitor_difficulty_adjustment_remaining_blocks",
+                remaining_blocks as f64,
+                &labels
+            );
+        }
+        if let Some(expected_change_pct) = update.stats.expected_change_pct {
+            gauge!(
+                "chain_monitor_difficulty_adjustment_expected_change_percent",
+                expected_change_pct,
+                &labels
+            );
+        }
+
+        SourceStatsTs::record(
+            &self.difficulty_stats,
+            update.source,
+            update.chain,
+            update.stats,
+        )
+        .await;
+    }
+
+    async fn report_error(&self, source: SourceId, chain: Option<ChainId>, error: SourceError) {
+        tracing::warn!(
+            "Couldn't update {} {}: {error}",
+            source.full_name(),
+            chain.map(ChainId::short_name).unwrap_or_default()
+        );
+
+        let kind: &'static str = error.kind.into();
+        increment_counter!(
+            "chain_monitor_source_errors_total",
+            "source" => source.short_name().to_lowercase(),
+            "chain" => chain.map(|chain| chain.short_name().to_lowercase()).unwrap_or_default(),
+            "kind" => kind
+        );
+
+        let mut statuses = self.source_statuses.lock().await;
+        let status = statuses.entry(source).or_default();
+        status.success_rate.record(false, get_now_ts());
+        status.errors_total += 1;
+        let error_ts = SourceErrorTs {
+            chain,
+            kind: error.kind,
+            message: error.message,
+            ts: get_now_ts(),
+        };
+        if error.kind == SourceErrorKind::SchemaChange {
+            // errors are logged anyway, only point out when it starts
+            let ongoing = status.last_error.as_ref().map(|error| error.kind)
+                == Some(SourceErrorKind::SchemaChange);
+            if !ongoing {
+                tracing::error!(
+                    "{} response doesn't match the expected schema, did its API change?",
+                    source.full_name()
+                );
+            }
+            status.schema_changes_total += 1;
+            status.last_schema_change = Some(error_ts.clone());
+        }
+        status.last_error = Some(error_ts);
+    }
+
+    async fn how_far_behind(&self, source: SourceId, chain: ChainId) -> u64 {
+        let chain_states = match self.chain_states.get(&chain) {
+            Some(shard) => shard.snapshot(),
+            None => return 0,
+        };
 
         let cur_height = chain_states
             .states
-            .get(&(source, chain))
+            .get(&source)
             .map(|s| s.state.height)
-            .unwrap_or(0);
-        let cur_best_height = chain_states.best_height.get(&chain).cloned().unwrap_or(0);
+            .unwrap_or_default();
+        let cur_best_height = chain_states.best_height.unwrap_or_default();
 
         cur_best_height - cur_height
     }
+
+    async fn tip_age_secs(&self, chain: ChainId) -> Option<u64> {
+        let tip_ts = self.chain_states.get(&chain)?.snapshot().best_tip_ts()?;
+
+        Some(get_now_ts().saturating_sub(tip_ts))
+    }
+
+    async fn is_paused(&self, chain: ChainId) -> bool {
+        self.paused_chains.lock().await.contains(&chain)
+    }
+
+    async fn refresh_requested(&self, source: SourceId, chain: ChainId) -> u64 {
+        let requests = self.refresh_requests.lock().await;
+        [None, Some(source)]
+            .iter()
+            .filter_map(|source| requests.latest.get(&(chain, *source)).copied())
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn tuning(&self, chain: ChainId) -> opts::Tuning {
+        self.chain_tunings.get(&chain).copied().unwrap_or_default()
+    }
+
+    async fn unchanged(&self, source: SourceId, chain: ChainId) {
+        let state = self
+            .chain_states
+            .get(&chain)
+            .and_then(|shard| shard.snapshot().states.get(&source).cloned());
+        // an update with the same state just counts as a check
+        if let Some(state_ts) = state {
+            self.update(ChainStateUpdate {
+                source,
+                chain,
+                state: state_ts.state,
+            })
+            .await;
+        }
+    }
+
+    async fn forget(&self, source: SourceId, chain: ChainId) {
+        debug!("{source:?} {chain:?} no longer reported");
+        if let Some(shard) = self.chain_states.get(&chain) {
+            shard
+                .modify(|chain_states| ((), chain_states.forget(source)))
+                .await;
+        }
+    }
 }
 
 type SharedAppState = Arc<AppState>;
@@ -333,159 +2035,377 @@ enum WSMessage<'a> {
     Init {
         sources: &'a [SourceInfo],
         chains: &'a [ChainInfo],
+        urls: &'a [SourceChainUrl],
     },
-    Update(WSChainStateUpdateTs),
+    Update(Box<WSChainStateUpdateTs>),
 }
 
 fn setup_server(
     opts: &Opts,
     app_state: SharedAppState,
-) -> Result<axum::Server<hyper::server::conn::AddrIncoming, IntoMakeService<Router>>> {
+) -> Result<
+    axum::Server<
+        hyper::server::conn::AddrIncoming,
+        IntoMakeServiceWithConnectInfo<Router, SocketAddr>,
+    >,
+> {
     let app = Router::new();
 
-    let recorder_handle = prom::setup_metrics_recorder()?;
+    let recorder_handle =
+        prom::setup_metrics_recorder(opts.metrics_prefix.as_deref(), &opts.metrics_labels)?;
 
     // enable dynamic files if the feature is enabled
-    let app = if opts.dynamic {
-        app.fallback(
-            get_service(ServeDir::new("assets").append_index_html_on_directories(true))
-                .handle_error(|error: std::io::Error| async move {
-                    (
-                        StatusCode::INTERNAL_SERVER_ERROR,
-                        format!("Unhandled internal error: {}", error),
-                    )
-                }),
-        )
-    } else {
-        app.route("/", get(index_html_handler))
-            .route("/favicon.ico", get(favicon_ico_handler))
-            .route("/style.css", get(style_css_handler))
-            .route("/script.js", get(script_js_handler))
-            .route("/sound1.mp3", get(sound1_mp3_handler))
-    };
+    let assets_dir = opts
+        .assets_dir
+        .clone()
+        .or_else(|| opts.dynamic.then(|| "assets".into()));
+    let app = app.merge(assets::router(assets_dir));
 
     let app = if opts.enable_prometheus {
-        app.route("/metrics", get(move || ready(recorder_handle.render())))
+        let mut metrics =
+            Router::new().route("/metrics", get(move || ready(recorder_handle.render())));
+        if let Some(metrics_token) = opts.metrics_token.as_deref() {
+            metrics = admin::require_token(metrics, metrics_token);
+        }
+        if !opts.metrics_allow.is_empty() {
+            metrics = prom::require_allowed_ip(metrics, opts.metrics_allow.clone());
+        }
+        app.merge(metrics)
     } else {
         app
     };
 
-    let app = app.route("/state", get(get_state_handler));
+    let app = app
+        .route("/chains", get(get_chains_handler))
+        .route("/state", get(get_state_handler))
+        .route("/mempool", get(get_mempool_handler))
+        .route("/difficulty", get(get_difficulty_handler))
+        .route("/milestones", get(get_milestones_handler))
+        .route("/alerts", get(get_alerts_handler))
+        .route("/status", get(get_status_handler))
+        .route("/version", get(get_version_handler))
+        .route("/check", get(check_handler))
+        .route("/stats/first-seen", get(get_first_seen_stats_handler))
+        .route("/export", get(export_handler));
+
+    let app = if let Some(admin_token) = opts.admin_token.as_deref() {
+        app.nest("/admin", admin::router(admin_token))
+            .nest("/api/v1", admin::api_router(admin_token))
+    } else {
+        app
+    };
 
     let app = app
         // routes are matched from bottom to top, so we have to put `nest` at the
         // top since it matches all routes
         .route("/ws", get(ws_handler))
         // logging so we can see whats going on
-        .layer(
-            TraceLayer::new_for_http()
-                .make_span_with(DefaultMakeSpan::default().include_headers(true)),
-        )
+        .layer(middleware::from_fn(access_log::log_requests))
         .layer(Extension(app_state))
         .route_layer(middleware::from_fn(prom::track_metrics));
 
     let addr = SocketAddr::from(([0, 0, 0, 0], opts.listen_port));
-    let server = axum::Server::bind(&addr).serve(app.into_make_service());
+    // client addresses are needed by `--metrics-allow`
+    let server = axum::Server::bind(&addr).serve(app.into_make_service_with_connect_info());
     tracing::info!("listening on {}", server.local_addr());
     Ok(server)
 }
 
-async fn index_html_handler() -> impl IntoResponse {
-    (
-        Headers([("Content-Type", "text/html")]),
-        Html(include_str!("../assets/index.html")),
-    )
+/// Whether the API shows `chain` to a request for the chains of `network`, or
+/// of all of them
+fn shows_network(chain: ChainId, network: Option<NetworkType>, hide_testnets: bool) -> bool {
+    let network_type = chain.network_type();
+    if hide_testnets && network_type != NetworkType::Mainnet {
+        return false;
+    }
+    match network {
+        Some(network) => network == network_type,
+        None => true,
+    }
 }
 
-async fn script_js_handler() -> impl IntoResponse {
-    (
-        Headers([("Content-Type", "application/javascript")]),
-        include_str!("../assets/script.js"),
-    )
+/// `?network=` of the endpoints listing chains, all networks without it
+#[derive(Deserialize)]
+struct NetworkParams {
+    network: Option<NetworkType>,
 }
 
-async fn style_css_handler() -> impl IntoResponse {
-    (
-        Headers([("Content-Type", "text/css")]),
-        include_str!("../assets/style.css"),
-    )
+async fn get_chains_handler(
+    Query(params): Query<NetworkParams>,
+    Extension(state): Extension<Arc<AppState>>,
+) -> axum::extract::Json<Vec<ChainInfo>> {
+    Json(state.get_chains(params.network))
 }
 
-async fn favicon_ico_handler() -> impl IntoResponse {
-    (
-        Headers([("Content-Type", "image/x-icon")]),
-        include_bytes!("../assets/favicon.ico") as &'static [u8],
-    )
+async fn get_state_handler(
+    Query(params): Query<NetworkParams>,
+    Extension(state): Extension<Arc<AppState>>,
+) -> axum::extract::Json<HashMap<&'static str, BestChainStateTs>> {
+    Json(state.get_best_states(params.network).await)
+}
+
+async fn get_mempool_handler(
+    Extension(state): Extension<Arc<AppState>>,
+) -> axum::extract::Json<HashMap<&'static str, Vec<SourceStatsTs<MempoolStats>>>> {
+    Json(state.get_mempool_stats().await)
 }
 
-async fn sound1_mp3_handler() -> impl IntoResponse {
+/// Nagios-style check of a chain, with the exit code of the status in `X-Check-Exit-Code`
+async fn check_handler(
+    Query(params): Query<check::CheckParams>,
+    Extension(state): Extension<Arc<AppState>>,
+) -> impl IntoResponse {
+    let (status, line) = state.check(&params);
     (
-        Headers([("Content-Type", "audio/mpeg")]),
-        include_bytes!("../assets/sound1.mp3") as &'static [u8],
+        status.http_status(),
+        Headers([
+            ("Content-Type", "text/plain".to_owned()),
+            ("X-Check-Exit-Code", status.exit_code().to_string()),
+        ]),
+        format!("{line}\n"),
     )
 }
 
-async fn get_state_handler(
+async fn get_version_handler(
     Extension(state): Extension<Arc<AppState>>,
-) -> axum::extract::Json<HashMap<&'static str, ChainStateTs>> {
-    Json(state.chain_states.lock().await.to_best_states())
+) -> axum::extract::Json<VersionInfo> {
+    Json(state.get_version_info())
+}
+
+async fn get_status_handler(
+    Extension(state): Extension<Arc<AppState>>,
+) -> axum::extract::Json<Status> {
+    Json(state.get_status().await)
+}
+
+async fn get_first_seen_stats_handler(
+    Extension(state): Extension<Arc<AppState>>,
+) -> axum::extract::Json<HashMap<&'static str, HashMap<&'static str, u64>>> {
+    Json(state.get_first_seen_stats().await)
+}
+
+#[derive(Deserialize)]
+struct ExportParams {
+    chain: Option<String>,
+    format: Option<String>,
+}
+
+/// Heights logged in the journal, see [`export::render`]
+async fn export_handler(
+    Query(params): Query<ExportParams>,
+    Extension(state): Extension<Arc<AppState>>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let format = match params.format.as_deref() {
+        Some(format) => export::ExportFormat::from_str(format)
+            .map_err(|_| (StatusCode::BAD_REQUEST, format!("unknown format: {format}")))?,
+        None => export::ExportFormat::Csv,
+    };
+    let path = match state.journal.as_ref() {
+        Some(journal) => journal.path().to_owned(),
+        None => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                "export needs the journal to be enabled with `--journal`".to_owned(),
+            ))
+        }
+    };
+
+    tokio::task::spawn_blocking(move || {
+        export::render(&journal::read_all(&path)?, params.chain.as_deref(), format)
+    })
+    .await
+    .map_err(anyhow::Error::from)
+    .and_then(|res| res)
+    .map(|body| (Headers([("Content-Type", format.content_type())]), body))
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+async fn get_alerts_handler(
+    Extension(state): Extension<Arc<AppState>>,
+) -> axum::extract::Json<Vec<alert::Alert>> {
+    Json(state.get_alerts().await)
+}
+
+async fn get_milestones_handler(
+    Extension(state): Extension<Arc<AppState>>,
+) -> axum::extract::Json<HashMap<&'static str, Vec<milestone::Milestone>>> {
+    Json(state.get_milestones().await)
+}
+
+async fn get_difficulty_handler(
+    Extension(state): Extension<Arc<AppState>>,
+) -> axum::extract::Json<HashMap<&'static str, Vec<SourceStatsTs<DifficultyStats>>>> {
+    Json(state.get_difficulty_stats().await)
+}
+
+#[derive(Deserialize)]
+struct WsParams {
+    token: Option<String>,
+    network: Option<NetworkType>,
+}
+
+/// Chains a websocket client gets updates of
+struct ChainFilter {
+    /// All if `None`
+    chains: Option<HashSet<ChainId>>,
+    /// Of all networks if `None`
+    network: Option<NetworkType>,
+    /// See [`Opts::hide_testnets`]
+    hide_testnets: bool,
+}
+
+impl ChainFilter {
+    fn allows(&self, chain: ChainId) -> bool {
+        shows_network(chain, self.network, self.hide_testnets)
+            && match &self.chains {
+                Some(chains) => chains.contains(&chain),
+                None => true,
+            }
+    }
 }
 
 async fn ws_handler(
     ws: WebSocketUpgrade,
     user_agent: Option<TypedHeader<headers::UserAgent>>,
+    bearer: Option<TypedHeader<headers::Authorization<headers::authorization::Bearer>>>,
+    Query(params): Query<WsParams>,
     Extension(state): Extension<Arc<AppState>>,
+    Extension(request_id): Extension<access_log::RequestId>,
 ) -> impl IntoResponse {
     if let Some(TypedHeader(user_agent)) = user_agent {
         tracing::debug!("`{}` connected", user_agent.as_str());
     }
 
-    ws.on_upgrade(|socket| handle_socket(socket, state))
+    let token = params
+        .token
+        .as_deref()
+        .or_else(|| bearer.as_ref().map(|TypedHeader(bearer)| bearer.token()));
+    let filter = match state.authorize_ws(token) {
+        Some(filter) => ChainFilter {
+            network: params.network,
+            ..filter
+        },
+        None => return StatusCode::UNAUTHORIZED.into_response(),
+    };
+
+    // the connection outlives the request span, so it needs its own
+    let span = tracing::info_span!("ws", request_id = %request_id.0);
+    ws.on_upgrade(|socket| handle_socket(socket, state, filter).instrument(span))
+        .into_response()
 }
 
-async fn handle_socket(socket: WebSocket, app_state: SharedAppState) {
-    if let Err(e) = handle_socket_try(socket, app_state).await {
+async fn handle_socket(socket: WebSocket, app_state: SharedAppState, filter: ChainFilter) {
+    increment_gauge!("chain_monitor_ws_clients", 1.0);
+    if let Err(e) = handle_socket_try(socket, app_state, filter).await {
         tracing::info!("Client disconnected: {e}");
     } else {
         tracing::info!("Client disconnected");
     }
+    decrement_gauge!("chain_monitor_ws_clients", 1.0);
+}
+
+async fn send_ws_message(
+    sender: &mut (impl Sink<Message, Error = axum::Error> + Unpin),
+    message: &WSMessage<'_>,
+) -> Result<()> {
+    let res = sender
+        .send(Message::Text(serde_json::to_string(message)?))
+        .await;
+    if res.is_err() {
+        increment_counter!("chain_monitor_ws_send_errors_total");
+    } else {
+        increment_counter!("chain_monitor_ws_messages_sent_total");
+    }
+    Ok(res?)
 }
 
-async fn handle_socket_try(socket: WebSocket, app_state: SharedAppState) -> Result<()> {
+async fn handle_socket_try(
+    socket: WebSocket,
+    app_state: SharedAppState,
+    filter: ChainFilter,
+) -> Result<()> {
     let (mut sender, _receiver) = socket.split();
 
     // subscribe early, so we don't miss anything
     let mut rx = app_state.subscribe_to_updates();
 
     // send all sources & chains info
-    sender
-        .send(Message::Text(serde_json::to_string(&WSMessage::Init {
-            sources: &app_state.sources,
-            chains: &app_state.chains,
-        })?))
-        .await?;
+    let chains: Vec<_> = app_state
+        .chains
+        .iter()
+        .filter(|chain_info| filter.allows(chain_info.id))
+        .cloned()
+        .collect();
+    let urls: Vec<_> = app_state
+        .urls
+        .iter()
+        .filter(|url| filter.allows(url.chain))
+        .cloned()
+        .collect();
+    send_ws_message(
+        &mut sender,
+        &WSMessage::Init {
+            sources: &app_state.sources(),
+            chains: &chains,
+            urls: &urls,
+        },
+    )
+    .await?;
 
     // send all the existing updates
-    for update in app_state.get_all_chain_states().await {
-        sender
-            .send(Message::Text(serde_json::to_string(&WSMessage::Update(
-                update.to_ws_update(),
-            ))?))
-            .await?;
-    }
+    send_all_chain_states(&mut sender, &app_state, &filter).await?;
 
     // keep sending new updates
-    while let Ok(update) = rx.recv().await {
-        sender
-            .send(Message::Text(serde_json::to_string(&WSMessage::Update(
-                update.to_ws_update(),
-            ))?))
-            .await?;
+    loop {
+        match rx.recv().await {
+            Ok(update) if !filter.allows(update.chain) => {}
+            Ok(update) => {
+                let confidence = app_state.get_confidence(update.chain).await;
+                send_ws_message(
+                    &mut sender,
+                    &WSMessage::Update(Box::new(update.into_ws_update(confidence))),
+                )
+                .await?;
+            }
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                tracing::info!("Client lagged behind by {missed} updates, resyncing");
+                increment_counter!("chain_monitor_ws_lagged_total");
+                counter!("chain_monitor_ws_missed_updates_total", missed);
+
+                // everything still buffered is older than the snapshot
+                while !matches!(
+                    rx.try_recv(),
+                    Err(broadcast::error::TryRecvError::Empty
+                        | broadcast::error::TryRecvError::Closed)
+                ) {}
+                send_all_chain_states(&mut sender, &app_state, &filter).await?;
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        }
     }
 
     Ok(())
 }
 
+async fn send_all_chain_states(
+    sender: &mut (impl Sink<Message, Error = axum::Error> + Unpin),
+    app_state: &AppState,
+    filter: &ChainFilter,
+) -> Result<()> {
+    for update in app_state.get_all_chain_states().await {
+        if !filter.allows(update.chain) {
+            continue;
+        }
+        let confidence = app_state.get_confidence(update.chain).await;
+        send_ws_message(
+            sender,
+            &WSMessage::Update(Box::new(update.into_ws_update(confidence))),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
 fn start_browser(url: String) {
     fn spawn(url: &str) -> Result<()> {
         let open_cmd = if cfg!(target_os = "windows") {
@@ -508,23 +2428,124 @@ fn start_browser(url: String) {
     });
 }
 
+/// Feed the updates of a journal through a fresh recorder, and print the
+/// resulting best states
+///
+/// Updates are recorded as if they came in right now, one after another.
+async fn replay(opts: &Opts, path: &std::path::Path) -> Result<()> {
+    let mut app_state = AppState::new(opts);
+
+    let updates: Vec<_> = journal::read(path)?
+        .into_iter()
+        .filter_map(|entry| {
+            match (
+                SourceId::from_short_name(&entry.source),
+                ChainId::from_ticker(&entry.ticker),
+            ) {
+                (Some(source), Some(chain)) => Some(ChainStateUpdate {
+                    source,
+                    chain,
+                    state: entry.state,
+                }),
+                _ => {
+                    tracing::warn!(
+                        "Skipping update of unknown source or chain: {} {}",
+                        entry.source,
+                        entry.ticker
+                    );
+                    None
+                }
+            }
+        })
+        .collect();
+
+    for update in &updates {
+        app_state.add_source(update.source);
+        app_state.add_chain(update.chain);
+    }
+
+    tracing::info!("Replaying {} updates", updates.len());
+    for update in updates {
+        app_state.update(update).await;
+    }
+
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&app_state.get_best_states(None).await)?
+    );
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let opts = opts::from_args();
 
     tracing_subscriber::registry()
         .with(tracing_subscriber::EnvFilter::new(
-            std::env::var("RUST_LOG")
-                .unwrap_or_else(|_| "chain_monitor=info,tower_http=debug".into()),
+            std::env::var("RUST_LOG").unwrap_or_else(|_| "chain_monitor=info".into()),
         ))
-        .with(tracing_subscriber::fmt::layer())
+        // keeping the output of the subcommands clean
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
         .init();
 
-    let mut app_state = AppState::new();
+    source::set_chain_metadata(&opts.chain_metadata)?;
+    #[cfg(feature = "test-util")]
+    if let Some(dir) = opts.record_fixtures.clone() {
+        source::fixtures::record_to(dir)?;
+    }
+
+    match opts.command.as_ref() {
+        Some(opts::Command::Replay { file }) => return replay(&opts, file).await,
+        Some(opts::Command::Export {
+            file,
+            chain,
+            format,
+        }) => {
+            print!(
+                "{}",
+                export::render(&journal::read_all(file)?, chain.as_deref(), *format)?
+            );
+            return Ok(());
+        }
+        Some(opts::Command::GenPromRules) => {
+            let (sources, _) = source::get_source(&opts)?;
+            let chains: Vec<_> = sources.get_supported_chains().into_iter().collect();
+            print!(
+                "{}",
+                prom_rules::render(
+                    &chains,
+                    |chain| opts.tuning(chain),
+                    opts.metrics_prefix.as_deref()
+                )
+            );
+            return Ok(());
+        }
+        None => {}
+    }
 
-    let source = source::get_source(&opts)?;
-    app_state.add_chains(source.get_supported_chains());
-    app_state.add_sources(source.get_supported_sources());
+    let mut app_state = AppState::new(&opts);
+
+    let (sources, skipped_sources) = source::get_source(&opts)?;
+    app_state.skipped_sources = skipped_sources;
+    app_state.webhooks = webhook::Webhooks::load(opts.webhooks_file.clone())?;
+    app_state.notifiers = notifier::get_notifiers(&opts)?;
+    if let Some(path) = opts.journal.clone() {
+        app_state.journal = Some(journal::Writer::spawn(
+            journal::Journal::open(path, opts.journal_max_bytes)?,
+            journal::RetentionPolicy::new(&opts),
+        )?);
+    }
+    app_state.add_chains(sources.get_supported_chains());
+    app_state.add_sources(sources.get_supported_sources());
+    app_state.add_urls(&sources);
+
+    if let Some(path) = opts.snapshot_file.as_ref() {
+        app_state
+            .restore_snapshot(snapshot::load(path).map_err(|e| {
+                anyhow::format_err!("can't load snapshot from {}: {e}", path.display())
+            })?)
+            .await;
+    }
 
     let app_state = Arc::new(app_state);
     let server = setup_server(&opts, app_state.clone())?;
@@ -540,10 +2561,77 @@ async fn main() -> Result<()> {
         start_browser(format!("http://{}", local_addr.to_string()));
     }
 
+    for source in sources {
+        tokio::spawn(supervisor::supervise(source, app_state.clone()));
+    }
+
+    if let Some(path) = opts.snapshot_file.clone() {
+        let app_state = app_state.clone();
+        let interval = Duration::from_secs(opts.snapshot_interval_secs);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                if let Err(e) = snapshot::save(&path, &app_state.snapshot_entries()) {
+                    tracing::warn!("Couldn't save snapshot to {}: {e}", path.display());
+                }
+            }
+        });
+    }
+
+    if let Some(journal) = app_state.journal.clone() {
+        let interval = Duration::from_secs(opts.journal_compact_interval_secs);
+        tokio::spawn(async move {
+            loop {
+                journal.compact();
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    if let Some(url) = opts.clock_check_url.clone() {
+        let app_state = app_state.clone();
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()?;
+        tokio::spawn(async move {
+            loop {
+                app_state.check_clock(&client, &url).await;
+                tokio::time::sleep(clock::CHECK_INTERVAL).await;
+            }
+        });
+    }
+
+    if let Some(url) = opts.influx_url.as_deref() {
+        let app_state = app_state.clone();
+        let writer = influx::Writer::new(url, &opts)?;
+        let interval = Duration::from_secs(opts.influx_interval_secs);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                if let Err(e) = writer.write(&app_state.measurements()).await {
+                    tracing::warn!("Couldn't write to InfluxDB: {e}");
+                }
+            }
+        });
+    }
+
+    if let Some(addr) = opts.graphite.as_deref() {
+        let app_state = app_state.clone();
+        let writer = graphite::Writer::new(addr, &opts.graphite_prefix);
+        let interval = Duration::from_secs(opts.graphite_interval_secs);
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                if let Err(e) = writer.write(&app_state.measurements()).await {
+                    tracing::warn!("Couldn't write to Graphite: {e}");
+                }
+            }
+        });
+    }
+
     loop {
-        if let Err(e) = timeout(Duration::from_secs(30), source.check_updates(&*app_state)).await {
-            tracing::warn!("Timeout waiting for updates: {e}");
-        }
+        app_state.check_schedule().await;
+        app_state.export_source_availability().await;
         tokio::time::sleep(Duration::from_secs(15)).await;
     }
 }
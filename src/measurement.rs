@@ -0,0 +1,37 @@
+//! Periodic snapshots of the chain states, for pushing to time-series databases
+use crate::source::{ChainId, SourceId};
+
+/// Values of a chain, or of a chain as reported by a single source
+#[derive(Debug, Clone)]
+pub struct Measurement {
+    pub ts: u64,
+    pub chain: ChainId,
+    /// `None` for values of the chain as a whole
+    pub source: Option<SourceId>,
+    pub fields: Vec<(&'static str, f64)>,
+}
+
+impl Measurement {
+    /// Name of the kind of measurement, for databases that keep them apart
+    pub fn name(&self) -> &'static str {
+        if self.source.is_some() {
+            "chain_monitor_source"
+        } else {
+            "chain_monitor_chain"
+        }
+    }
+
+    /// `chain`, `ticker`, `network_type` and `source` if any, like the
+    /// labels of the Prometheus metrics
+    pub fn tags(&self) -> Vec<(&'static str, String)> {
+        let mut tags = vec![
+            ("chain", self.chain.short_name().to_lowercase()),
+            ("ticker", self.chain.ticker().to_owned()),
+            ("network_type", self.chain.network_type().to_string()),
+        ];
+        if let Some(source) = self.source {
+            tags.push(("source", source.short_name().to_owned()));
+        }
+        tags
+    }
+}
@@ -0,0 +1,88 @@
+//! Countdowns to known protocol milestones (halvings and alike)
+use std::str::FromStr;
+
+use anyhow::{format_err, Error, Result};
+use serde::Serialize;
+
+use crate::{source::ChainId, ChainHeight};
+
+/// A milestone configured by the user
+#[derive(Debug, Clone)]
+pub struct CustomMilestone {
+    chain: ChainId,
+    height: ChainHeight,
+    name: String,
+}
+
+impl FromStr for CustomMilestone {
+    type Err = Error;
+
+    /// Parse `<ticker>=<height>[:<name>]`
+    fn from_str(s: &str) -> Result<Self> {
+        let (ticker, rest) = s
+            .split_once('=')
+            .ok_or_else(|| format_err!("expected `<ticker>=<height>[:<name>]`: {s}"))?;
+        let (height, name) = rest.split_once(':').unwrap_or((rest, "custom"));
+
+        Ok(Self {
+            chain: ChainId::from_ticker(ticker)
+                .ok_or_else(|| format_err!("unknown ticker: {ticker}"))?,
+            height: height.parse()?,
+            name: name.to_owned(),
+        })
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Milestone {
+    name: String,
+    height: ChainHeight,
+    remaining_blocks: u64,
+    /// Estimated based on the nominal block time of the chain
+    eta_ts: u64,
+}
+
+impl Milestone {
+    fn new(
+        chain: ChainId,
+        name: String,
+        height: ChainHeight,
+        best_height: ChainHeight,
+        now: u64,
+    ) -> Self {
+        let remaining_blocks = height - best_height;
+        Self {
+            name,
+            height,
+            remaining_blocks,
+            eta_ts: now + remaining_blocks * u64::from(chain.block_time_secs()),
+        }
+    }
+}
+
+/// All milestones of `chain` still ahead of `best_height`, soonest first
+pub fn upcoming(
+    chain: ChainId,
+    best_height: ChainHeight,
+    custom: &[CustomMilestone],
+    now: u64,
+) -> Vec<Milestone> {
+    let halving = chain.halving_interval().map(|interval| {
+        let next_halving = ChainHeight::new((best_height.as_u64() / interval + 1) * interval);
+        Milestone::new(chain, "halving".into(), next_halving, best_height, now)
+    });
+
+    let mut milestones: Vec<_> = halving
+        .into_iter()
+        .chain(
+            custom
+                .iter()
+                .filter(|m| m.chain == chain && best_height < m.height)
+                .map(|m| Milestone::new(chain, m.name.clone(), m.height, best_height, now)),
+        )
+        .collect();
+
+    milestones.sort_by_key(|m| m.height);
+    milestones
+}
@@ -0,0 +1,160 @@
+//! Destinations of alert notifications configured on the command line
+//!
+//! Unlike [`crate::webhook`]s, which are registered at runtime, these are set
+//! up once at startup.
+use std::sync::Arc;
+
+use anyhow::Result;
+use axum::async_trait;
+use tracing::{debug, warn};
+
+use crate::{
+    alert::{AlertEvent, AlertEventKind},
+    opts::Opts,
+};
+
+#[cfg(feature = "notifier-discord")]
+mod discord;
+#[cfg(feature = "notifier-matrix")]
+mod matrix;
+#[cfg(feature = "notifier-smtp")]
+mod smtp;
+
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// For telling notifiers apart in the logs
+    fn name(&self) -> &str;
+
+    async fn notify(&self, event: &AlertEvent) -> Result<()>;
+
+    /// Whether to be notified of `event`s of this kind at all
+    fn wants(&self, _event: AlertEventKind) -> bool {
+        true
+    }
+}
+
+/// A notifier only notified of some events, see [`Opts::notifier_events`]
+struct Subscribed {
+    notifier: Arc<dyn Notifier>,
+    events: Vec<AlertEventKind>,
+}
+
+#[async_trait]
+impl Notifier for Subscribed {
+    fn name(&self) -> &str {
+        self.notifier.name()
+    }
+
+    async fn notify(&self, event: &AlertEvent) -> Result<()> {
+        self.notifier.notify(event).await
+    }
+
+    fn wants(&self, event: AlertEventKind) -> bool {
+        self.events.contains(&event)
+    }
+}
+
+/// Restrict `notifier`, of the `kind` given in `--notifier-events`, to the
+/// events given there, if any
+#[cfg_attr(
+    not(any(
+        feature = "notifier-discord",
+        feature = "notifier-matrix",
+        feature = "notifier-smtp"
+    )),
+    allow(dead_code)
+)]
+fn subscribe(notifier: Arc<dyn Notifier>, kind: &str, opts: &Opts) -> Arc<dyn Notifier> {
+    let events: Vec<_> = opts
+        .notifier_events
+        .iter()
+        .filter(|entry| entry.notifier == kind)
+        .flat_map(|entry| entry.events.iter().copied())
+        .collect();
+    if events.is_empty() {
+        notifier
+    } else {
+        Arc::new(Subscribed { notifier, events })
+    }
+}
+
+/// Fill in the `{chain}`, `{ticker}`, `{event}`, `{kind}`, `{message}` and
+/// `{since_ts}` placeholders of `template` with the details of `event`
+#[cfg_attr(
+    not(any(
+        feature = "notifier-discord",
+        feature = "notifier-matrix",
+        feature = "notifier-smtp"
+    )),
+    allow(dead_code)
+)]
+pub fn render(template: &str, event: &AlertEvent) -> String {
+    let alert = &event.alert;
+    // serialized the same way as in the webhook payloads
+    let to_name = |value: serde_json::Value| value.as_str().unwrap_or_default().to_owned();
+
+    template
+        .replace("{chain}", alert.chain().short_name())
+        .replace("{ticker}", alert.chain().ticker())
+        .replace("{event}", &to_name(serde_json::json!(event.event)))
+        .replace("{kind}", &to_name(serde_json::json!(alert.kind())))
+        .replace("{message}", alert.message())
+        .replace("{since_ts}", &alert.since_ts().to_string())
+}
+
+pub fn get_notifiers(opts: &Opts) -> Result<Vec<Arc<dyn Notifier>>> {
+    #[allow(unused_mut)]
+    let mut notifiers: Vec<Arc<dyn Notifier>> = vec![];
+
+    if let Some(server) = opts.smtp_server.as_deref() {
+        #[cfg(feature = "notifier-smtp")]
+        notifiers.push(subscribe(
+            Arc::new(smtp::Smtp::new(server, opts)?),
+            "smtp",
+            opts,
+        ));
+        #[cfg(not(feature = "notifier-smtp"))]
+        anyhow::bail!("`--smtp-server {server}` requires the `notifier-smtp` feature");
+    }
+
+    if !opts.discord_webhooks.is_empty() {
+        #[cfg(feature = "notifier-discord")]
+        for url in &opts.discord_webhooks {
+            notifiers.push(subscribe(
+                Arc::new(discord::Discord::new(url, &opts.chat_message_template)?),
+                "discord",
+                opts,
+            ));
+        }
+        #[cfg(not(feature = "notifier-discord"))]
+        anyhow::bail!("`--discord-webhook` requires the `notifier-discord` feature");
+    }
+
+    if let Some(homeserver) = opts.matrix_homeserver.as_deref() {
+        #[cfg(feature = "notifier-matrix")]
+        notifiers.push(subscribe(
+            Arc::new(matrix::Matrix::new(homeserver, opts)?),
+            "matrix",
+            opts,
+        ));
+        #[cfg(not(feature = "notifier-matrix"))]
+        anyhow::bail!("`--matrix-homeserver {homeserver}` requires the `notifier-matrix` feature");
+    }
+
+    Ok(notifiers)
+}
+
+/// Send `events` to all the `notifiers` wanting them, in the background
+pub fn notify_all(notifiers: &[Arc<dyn Notifier>], events: &[AlertEvent]) {
+    for notifier in notifiers {
+        for event in events.iter().filter(|event| notifier.wants(event.event)) {
+            let (notifier, event) = (notifier.clone(), event.clone());
+            tokio::spawn(async move {
+                match notifier.notify(&event).await {
+                    Ok(()) => debug!("Notified {} of an alert", notifier.name()),
+                    Err(e) => warn!("Couldn't notify {} of an alert: {e}", notifier.name()),
+                }
+            });
+        }
+    }
+}
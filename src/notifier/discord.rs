@@ -0,0 +1,53 @@
+use std::time::Duration;
+
+use anyhow::Result;
+use axum::async_trait;
+use serde_json::json;
+
+use super::{render, Notifier};
+use crate::alert::AlertEvent;
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Alerts posted to a Discord channel through its webhook
+pub struct Discord {
+    name: String,
+    url: String,
+    template: String,
+    client: reqwest::Client,
+}
+
+impl Discord {
+    pub fn new(url: &str, template: &str) -> Result<Self> {
+        // the rest of the url is a secret
+        let id = url
+            .trim_end_matches('/')
+            .rsplit('/')
+            .nth(1)
+            .unwrap_or_default();
+
+        Ok(Self {
+            name: format!("Discord webhook {id}"),
+            url: url.to_owned(),
+            template: template.to_owned(),
+            client: reqwest::Client::builder().timeout(TIMEOUT).build()?,
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for Discord {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn notify(&self, event: &AlertEvent) -> Result<()> {
+        self.client
+            .post(&self.url)
+            .json(&json!({ "content": render(&self.template, event) }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
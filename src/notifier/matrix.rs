@@ -0,0 +1,96 @@
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+use anyhow::{format_err, Result};
+use axum::async_trait;
+use reqwest::Url;
+use serde_json::json;
+
+use super::{render, Notifier};
+use crate::{alert::AlertEvent, opts::Opts};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Alerts posted to a Matrix room, as the user of `access_token`
+pub struct Matrix {
+    name: String,
+    homeserver: Url,
+    room: String,
+    access_token: String,
+    template: String,
+    /// Makes transaction ids unique, along with the startup time
+    last_txn: AtomicU64,
+    started_ts: u64,
+    client: reqwest::Client,
+}
+
+impl Matrix {
+    /// Post to a room on `homeserver`, configured by the `--matrix-*` options
+    pub fn new(homeserver: &str, opts: &Opts) -> Result<Self> {
+        let room = opts
+            .matrix_room
+            .as_deref()
+            .ok_or_else(|| format_err!("`--matrix-homeserver` requires `--matrix-room`"))?;
+        let access_token = opts
+            .matrix_token
+            .as_deref()
+            .ok_or_else(|| format_err!("`--matrix-homeserver` requires `--matrix-token`"))?;
+
+        Ok(Self {
+            name: format!("Matrix room {room}"),
+            homeserver: homeserver.parse()?,
+            room: room.to_owned(),
+            access_token: crate::util::load_secret(access_token, "Matrix access token")?,
+            template: opts.chat_message_template.clone(),
+            last_txn: AtomicU64::new(0),
+            started_ts: crate::get_now_ts(),
+            client: reqwest::Client::builder().timeout(TIMEOUT).build()?,
+        })
+    }
+
+    fn send_url(&self) -> Result<Url> {
+        let txn_id = format!(
+            "chain-monitor-{}-{}",
+            self.started_ts,
+            self.last_txn.fetch_add(1, Ordering::Relaxed) + 1
+        );
+        let mut url = self.homeserver.clone();
+        url.path_segments_mut()
+            .map_err(|_| format_err!("not a homeserver url: {}", self.homeserver))?
+            .pop_if_empty()
+            .extend([
+                "_matrix",
+                "client",
+                "v3",
+                "rooms",
+                &self.room,
+                "send",
+                "m.room.message",
+                &txn_id,
+            ]);
+        Ok(url)
+    }
+}
+
+#[async_trait]
+impl Notifier for Matrix {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn notify(&self, event: &AlertEvent) -> Result<()> {
+        self.client
+            .put(self.send_url()?)
+            .bearer_auth(&self.access_token)
+            .json(&json!({
+                "msgtype": "m.text",
+                "body": render(&self.template, event),
+            }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
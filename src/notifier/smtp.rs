@@ -0,0 +1,99 @@
+use anyhow::{format_err, Result};
+use axum::async_trait;
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+
+use super::{render, Notifier};
+use crate::{
+    alert::AlertEvent,
+    opts::{Opts, SmtpTls},
+};
+
+/// Alerts sent as emails
+pub struct Smtp {
+    name: String,
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: Mailbox,
+    to: Vec<Mailbox>,
+    subject_template: String,
+    body_template: String,
+}
+
+impl Smtp {
+    /// Connect to `server` (`<host>[:<port>]`), configured by the `--smtp-*` options
+    pub fn new(server: &str, opts: &Opts) -> Result<Self> {
+        let (host, port) = match server.split_once(':') {
+            Some((host, port)) => (host, Some(port.parse()?)),
+            None => (server, None),
+        };
+        let builder = match opts.smtp_tls {
+            SmtpTls::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host)?,
+            SmtpTls::StartTls => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?,
+            SmtpTls::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+        };
+        let builder = match port {
+            Some(port) => builder.port(port),
+            None => builder,
+        };
+        let builder = match opts.smtp_username.as_ref() {
+            Some(username) => {
+                let password = opts
+                    .smtp_password
+                    .as_deref()
+                    .map(|password| crate::util::load_secret(password, "SMTP password"))
+                    .transpose()?
+                    .unwrap_or_default();
+                builder.credentials(Credentials::new(username.clone(), password))
+            }
+            None => builder,
+        };
+
+        let from = opts
+            .smtp_from
+            .as_deref()
+            .ok_or_else(|| format_err!("`--smtp-server` requires `--smtp-from`"))?
+            .parse()?;
+        if opts.smtp_to.is_empty() {
+            return Err(format_err!("`--smtp-server` requires `--smtp-to`"));
+        }
+        let to = opts
+            .smtp_to
+            .iter()
+            .map(|to| to.parse())
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            name: format!("SMTP {server}"),
+            transport: builder.build(),
+            from,
+            to,
+            subject_template: opts.smtp_subject_template.clone(),
+            body_template: opts.smtp_body_template.replace("\\n", "\n"),
+        })
+    }
+}
+
+#[async_trait]
+impl Notifier for Smtp {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn notify(&self, event: &AlertEvent) -> Result<()> {
+        let mut message = Message::builder()
+            .from(self.from.clone())
+            .subject(render(&self.subject_template, event))
+            .header(ContentType::TEXT_PLAIN);
+        for to in &self.to {
+            message = message.to(to.clone());
+        }
+
+        self.transport
+            .send(message.body(render(&self.body_template, event))?)
+            .await?;
+        Ok(())
+    }
+}
@@ -1,4 +1,18 @@
-use clap::Parser;
+use std::{collections::HashSet, net::IpAddr, str::FromStr};
+
+use anyhow::{format_err, Error, Result};
+use clap::{Parser, Subcommand};
+use ipnet::IpNet;
+use reqwest::header::HeaderName;
+use strum::{EnumString, IntoStaticStr};
+
+use crate::{
+    alert::AlertEventKind,
+    export::ExportFormat,
+    milestone::CustomMilestone,
+    silence::MaintenanceWindow,
+    source::{ChainId, SourceId},
+};
 
 #[derive(Parser, Debug, Clone)]
 #[clap(
@@ -6,6 +20,9 @@ use clap::Parser;
     about = "(block-)Chain (Height) Monitor Utility/Server"
 )]
 pub struct Opts {
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+
     /// Port to listen on
     #[clap(long = "listen", short = 'l', default_value = "0")]
     pub listen_port: u16,
@@ -13,17 +30,869 @@ pub struct Opts {
     #[clap(long = "daemon", short = 'd')]
     pub daemon: bool,
 
-    #[clap(long = "dynamic")]
+    /// Same as `--assets-dir assets`
+    #[clap(long = "dynamic", conflicts_with = "assets-dir")]
     pub dynamic: bool,
 
+    /// Directory with files to serve in place of the embedded web UI ones (e.g. a
+    /// custom `sound1.mp3`), falling back to the embedded ones for files not in it
+    #[clap(long = "assets-dir")]
+    pub assets_dir: Option<std::path::PathBuf>,
+
     #[clap(long = "enable-prometheus")]
     pub enable_prometheus: bool,
 
+    /// Bearer token required by `/metrics`
+    #[clap(long = "metrics-token")]
+    pub metrics_token: Option<String>,
+
+    /// Only serve `/metrics` to clients from these networks (e.g. `10.0.0.0/8`
+    /// or `192.0.2.7/32`); everyone is allowed if none are given
+    #[clap(long = "metrics-allow")]
+    pub metrics_allow: Vec<IpNet>,
+
+    /// Prefix of the names of all exported metrics, e.g. `eu` for `eu_http_requests_total`
+    #[clap(long = "metrics-prefix")]
+    pub metrics_prefix: Option<String>,
+
+    /// Label to add to all exported metrics (`<name>=<value>`), e.g. to tell
+    /// apart instances scraped by the same Prometheus
+    #[clap(long = "metrics-label")]
+    pub metrics_labels: Vec<MetricLabel>,
+
+    /// How many updates to buffer for slow websocket clients, before they
+    /// need to be resynced from scratch
+    #[clap(long = "broadcast-capacity", default_value = "1000")]
+    pub broadcast_capacity: usize,
+
     /// Mirror another instance of chainmonitor
     #[clap(long = "mirror")]
     pub mirror: Vec<String>,
+
+    /// Base URL to use for a built-in source (`<source>=<base-url>`), e.g. for a
+    /// self-hosted mempool.space or an API gateway; supported by AptosRpc, BitGo,
+    /// BitGoV1, Blockchain, Blockchair, BlockCypher, Blockfrost, Blockstream, CMC,
+    /// CryptoId, Glif, KaspaApi, Koios, MempoolSpace, NearRpc, Subscan, SuiRpc,
+    /// TonCenter and TronGrid, but not sources with an RPC endpoint per chain
+    /// (EvmRpc, SolanaRpc, Substrate, Other), nor those with their own options
+    #[clap(long = "source-url")]
+    pub source_urls: Vec<String>,
+
+    /// Monitor a bitcoind-compatible node, like zcashd or zebrad for ZCash
+    /// (`<ticker>=<rpc-url>`)
+    #[clap(long = "bitcoind-rpc")]
+    pub bitcoind_rpc: Vec<String>,
+
+    /// Monitor a self-hosted EVM node (Geth, Erigon, ...), subscribing to new blocks
+    /// over websocket (`<ticker>=<ws-url>[,<http-url>]`); the HTTP endpoint is
+    /// polled while the websocket is down, and defaults to the websocket one
+    #[clap(long = "evm-node")]
+    pub evm_nodes: Vec<String>,
+
+    /// Blockbook instance to use for a chain (`<ticker>=<url>`), instead of
+    /// the default public one
+    #[clap(long = "blockbook")]
+    pub blockbook: Vec<String>,
+
+    /// Blockscout instance to use for a chain (`<ticker>=<url>`), instead of
+    /// the default public one; any EVM chain with a Blockscout deployment works
+    #[clap(long = "blockscout")]
+    pub blockscout: Vec<String>,
+
+    /// Stacks Blockchain API to use for a chain (`<ticker>=<url>`), instead of
+    /// the one run by Hiro
+    #[clap(long = "stacks-api")]
+    pub stacks_api: Vec<String>,
+
+    /// Tezos node RPC to use for a chain (`<ticker>=<rpc-url>`), instead of the
+    /// default public ones; can be repeated, the nodes are tried in order
+    #[clap(long = "tezos-node")]
+    pub tezos_nodes: Vec<String>,
+
+    /// Tendermint/CometBFT RPC endpoint to use for a chain (`<ticker>=<rpc-url>`),
+    /// instead of the default public one
+    #[clap(long = "tendermint-rpc")]
+    pub tendermint_rpc: Vec<String>,
+
+    /// Monitor a custom signet through its Esplora API
+    /// (`<ticker>[:<name>[:<block-time-secs>]]=<esplora-api-url>`)
+    #[clap(long = "custom-signet")]
+    pub custom_signets: Vec<String>,
+
+    /// API key of a source (`<source>=<key>`), also read from an environment
+    /// variable (`<source>=env:<var>`) or a file (`<source>=file:<path>`);
+    /// Blockfrost and Subscan are skipped without one, CryptoID, toncenter and
+    /// TronGrid only rate limit anonymous requests more, and Blockchair uses its
+    /// documented per-chain stats with one
+    #[clap(long = "api-key")]
+    pub api_keys: Vec<String>,
+
+    /// Commitment level of the Solana state to fetch from Solana RPC
+    /// (`processed`, `confirmed` or `finalized`)
+    #[clap(long = "solana-commitment", default_value = "confirmed")]
+    pub solana_commitment: SolanaCommitment,
+
+    /// Also track mempool statistics of Bitcoin networks
+    #[clap(long = "track-mempool")]
+    pub track_mempool: bool,
+
+    /// Also track difficulty (and its next adjustment) of PoW chains
+    #[clap(long = "track-difficulty")]
+    pub track_difficulty: bool,
+
+    /// Additional milestone to count down to (`<ticker>=<height>[:<name>]`)
+    #[clap(long = "milestone")]
+    pub milestones: Vec<CustomMilestone>,
+
+    /// Alert when a chain is this many blocks behind the height expected
+    /// from its block time
+    #[clap(long = "behind-schedule-alert-blocks", default_value = "10")]
+    pub behind_schedule_alert_blocks: u64,
+
+    /// Only resolve alerts once their condition has been gone for this long, so
+    /// flapping ones aren't notified of over and over
+    #[clap(long = "alert-resolve-after-secs", default_value = "120")]
+    pub alert_resolve_after_secs: u64,
+
+    /// Notify of alerts still firing after this long again, as escalated, e.g.
+    /// to webhooks or notifiers subscribed only to escalations
+    #[clap(long = "alert-escalate-after-secs")]
+    pub alert_escalate_after_secs: Option<u64>,
+
+    /// Only notify a kind of notifier (`smtp`, `discord` or `matrix`) of some
+    /// alert events (`<notifier>=<event>[,...]`, with the events `fired`,
+    /// `escalated` and `resolved`), e.g. `smtp=escalated` to only email
+    /// alerts that went on for too long
+    #[clap(long = "notifier-events")]
+    pub notifier_events: Vec<NotifierEvents>,
+
+    /// Post alerts to a Discord channel through this webhook
+    #[clap(long = "discord-webhook")]
+    pub discord_webhooks: Vec<String>,
+
+    /// Post alerts to a Matrix room through this homeserver
+    #[clap(long = "matrix-homeserver")]
+    pub matrix_homeserver: Option<String>,
+
+    /// Id of the Matrix room to post alerts to, e.g. `!abcdef:matrix.org`
+    #[clap(long = "matrix-room")]
+    pub matrix_room: Option<String>,
+
+    /// Access token of the Matrix user posting alerts, also from an environment
+    /// variable (`env:<var>`) or a file (`file:<path>`)
+    #[clap(long = "matrix-token")]
+    pub matrix_token: Option<String>,
+
+    /// Alert messages posted to Discord and Matrix, with the same placeholders
+    /// as `--smtp-subject-template`
+    #[clap(
+        long = "chat-message-template",
+        default_value = "{chain} {kind} {event}: {message}"
+    )]
+    pub chat_message_template: String,
+
+    /// Email alerts through this SMTP server (`<host>[:<port>]`)
+    #[clap(long = "smtp-server")]
+    pub smtp_server: Option<String>,
+
+    /// How to secure the connection to the SMTP server
+    #[clap(long = "smtp-tls", default_value = "starttls")]
+    pub smtp_tls: SmtpTls,
+
+    #[clap(long = "smtp-username")]
+    pub smtp_username: Option<String>,
+
+    /// Also from an environment variable (`env:<var>`) or a file (`file:<path>`)
+    #[clap(long = "smtp-password")]
+    pub smtp_password: Option<String>,
+
+    /// Sender of the alert emails, e.g. `Chain Monitor <monitor@example.com>`
+    #[clap(long = "smtp-from")]
+    pub smtp_from: Option<String>,
+
+    /// Recipients of the alert emails
+    #[clap(long = "smtp-to")]
+    pub smtp_to: Vec<String>,
+
+    /// Subject of the alert emails, with `{chain}`, `{ticker}`, `{event}`,
+    /// `{kind}`, `{message}` and `{since_ts}` filled in
+    #[clap(
+        long = "smtp-subject-template",
+        default_value = "[chain-monitor] {ticker} {kind} {event}"
+    )]
+    pub smtp_subject_template: String,
+
+    /// Body of the alert emails, with the same placeholders as the subject
+    /// and `\n` for new lines
+    #[clap(
+        long = "smtp-body-template",
+        default_value = "{chain}: {message}\\n\\nAlert {event}, firing since {since_ts}."
+    )]
+    pub smtp_body_template: String,
+
+    /// How many blocks away from the best height can a source be, and still
+    /// count as agreeing with it in `chain_monitor_consensus_sources`
+    #[clap(long = "consensus-tolerance-blocks", default_value = "1")]
+    pub consensus_tolerance_blocks: u64,
+
+    /// File to periodically save the chain states to, and load them from
+    /// at startup, so they aren't empty until the first round of updates
+    #[clap(long = "snapshot-file")]
+    pub snapshot_file: Option<std::path::PathBuf>,
+
+    /// How often to save the snapshot
+    #[clap(long = "snapshot-interval-secs", default_value = "60")]
+    pub snapshot_interval_secs: u64,
+
+    /// File to log every accepted update to, as JSON lines, for `replay`
+    #[clap(long = "journal")]
+    pub journal: Option<std::path::PathBuf>,
+
+    /// Size of the journal at which to rotate it to `<journal>.1`
+    #[clap(long = "journal-max-bytes", default_value = "104857600")]
+    pub journal_max_bytes: u64,
+
+    /// Keep every entry of the journal for this long
+    #[clap(long = "journal-raw-secs", default_value = "172800")]
+    pub journal_raw_secs: u64,
+
+    /// After `--journal-raw-secs`, keep one entry of each source and chain
+    /// per 5 minutes for this long, and one per hour beyond
+    #[clap(long = "journal-downsampled-secs", default_value = "2592000")]
+    pub journal_downsampled_secs: u64,
+
+    /// Drop entries of the journal older than this altogether
+    #[clap(long = "journal-max-age-secs")]
+    pub journal_max_age_secs: Option<u64>,
+
+    /// How often to apply the retention of the journal
+    #[clap(long = "journal-compact-interval-secs", default_value = "3600")]
+    pub journal_compact_interval_secs: u64,
+
+    /// Directory to record the responses of the sources to, as fixtures for
+    /// the tests of their parsers
+    #[cfg(feature = "test-util")]
+    #[clap(long = "record-fixtures")]
+    pub record_fixtures: Option<std::path::PathBuf>,
+
+    /// Url to compare the local clock with, using the `Date` header of its
+    /// responses, at startup and every 10 minutes (e.g. `https://www.cloudflare.com`)
+    #[clap(long = "clock-check-url")]
+    pub clock_check_url: Option<String>,
+
+    /// InfluxDB to push heights, lags and latencies to, e.g. `http://localhost:8086`
+    #[clap(long = "influx-url")]
+    pub influx_url: Option<String>,
+
+    /// InfluxDB v1 database to write to
+    #[clap(long = "influx-db")]
+    pub influx_db: Option<String>,
+
+    /// InfluxDB v2 organization of `--influx-bucket`
+    #[clap(long = "influx-org")]
+    pub influx_org: Option<String>,
+
+    /// InfluxDB v2 bucket to write to
+    #[clap(long = "influx-bucket")]
+    pub influx_bucket: Option<String>,
+
+    /// InfluxDB v2 token, or `<user>:<password>` for v1, also from an environment
+    /// variable (`env:<var>`) or a file (`file:<path>`)
+    #[clap(long = "influx-token")]
+    pub influx_token: Option<String>,
+
+    /// How often to push to InfluxDB
+    #[clap(long = "influx-interval-secs", default_value = "60")]
+    pub influx_interval_secs: u64,
+
+    /// Carbon daemon to push heights, lags and latencies to (`<host>:<port>`,
+    /// usually port 2003)
+    #[clap(long = "graphite")]
+    pub graphite: Option<String>,
+
+    /// Prefix of the Graphite metric paths
+    #[clap(long = "graphite-prefix", default_value = "chain_monitor")]
+    pub graphite_prefix: String,
+
+    /// How often to push to Graphite
+    #[clap(long = "graphite-interval-secs", default_value = "60")]
+    pub graphite_interval_secs: u64,
+
+    /// Ignore updates with block hashes not matching the format of the chain,
+    /// instead of just reporting them
+    #[clap(long = "reject-malformed-hashes")]
+    pub reject_malformed_hashes: bool,
+
+    /// Bearer token required by the `/admin` endpoints, disabled without one
+    #[clap(long = "admin-token")]
+    pub admin_token: Option<String>,
+
+    /// Require websocket clients to present one of these tokens, as a `token`
+    /// query parameter or a bearer token (`<name>=<token>[:<ticker>,...]`);
+    /// clients of a token with tickers only get updates of those chains
+    #[clap(long = "ws-token")]
+    pub ws_tokens: Vec<WsToken>,
+
+    /// Leave testnets and signets out of `/chains`, `/state` and the websocket,
+    /// e.g. for a public instance; they're still monitored and exported
+    #[clap(long = "hide-testnets")]
+    pub hide_testnets: bool,
+
+    /// Don't notify of alerts on a chain, or the chains of a source, during a
+    /// planned maintenance (`<ticker|source>=<from>/<to>`, RFC 3339 times)
+    #[clap(long = "maintenance-window")]
+    pub maintenance_windows: Vec<MaintenanceWindow>,
+
+    /// File to keep the webhooks registered through `/api/v1/webhooks` in,
+    /// so they survive restarts
+    #[clap(long = "webhooks-file")]
+    pub webhooks_file: Option<std::path::PathBuf>,
+
+    /// Use fake sources with made up, but plausible, chains instead of the
+    /// real ones, for working on the UI and alerts
+    #[clap(long = "simulate")]
+    pub simulate: bool,
+
+    /// Response field a source should read the height of a chain from
+    /// (`<source>.<ticker>=<field>`), to work around upstream API changes
+    /// until a fixed release; only supported by Blockchair
+    #[clap(long = "height-field")]
+    pub height_fields: Vec<HeightField>,
+
+    /// Sources to prefer when several report the same best height
+    /// (comma-separated, most preferred first)
+    #[clap(long = "source-priority", use_value_delimiter = true)]
+    pub source_priority: Vec<SourceId>,
+
+    /// How much to trust a source (`<source>=<weight>`, 1 by default); a height
+    /// is only taken as the best one once sources with a total weight of at least
+    /// the heaviest reporting source agree on it
+    #[clap(long = "source-weight")]
+    pub source_weights: Vec<SourceWeight>,
+
+    /// Keep the previous state of a source whose height went backwards
+    /// for this many seconds, before accepting the lower one
+    #[clap(long = "regression-grace-secs", default_value = "0")]
+    pub regression_grace_secs: u64,
+
+    /// On PoW chains prefer the tip with the most chainwork over the highest one,
+    /// when any source reports chainwork
+    #[clap(long = "chainwork-best-state")]
+    pub chainwork_best_state: bool,
+
+    /// Tune how a chain is polled and alerted on
+    /// (`<ticker>=<setting>:<value>[,...]`), with the settings:
+    /// `recheck-secs`, how often to poll sources not behind, instead of
+    /// depending on the tip age; `acceptable-lag`, how many blocks can a
+    /// source be behind before being polled right away; `stall-multiplier`,
+    /// how many block times behind schedule to alert at, instead of
+    /// `--behind-schedule-alert-blocks`; `journal-raw-secs`,
+    /// `journal-downsampled-secs` and `journal-max-age-secs`, the retention
+    /// of its entries in the journal, instead of the `--journal-*` ones
+    #[clap(long = "chain-tuning")]
+    pub chain_tunings: Vec<ChainTuning>,
+
+    /// Override what's known of a built-in chain
+    /// (`<ticker>=<field>:<value>[,...]`), with the fields: `block-time-secs`,
+    /// the expected time between blocks; `short-name` and `full-name`, as shown
+    /// in the UI and alerts; `ticker`, as used in the API and metrics, while
+    /// options keep taking the built-in one
+    #[clap(long = "chain-metadata")]
+    pub chain_metadata: Vec<ChainMetadataOverride>,
+
+    /// Cache the addresses of the hosts of sources for this long, and keep
+    /// using them while looking them up again fails
+    #[clap(long = "dns-cache-secs")]
+    pub dns_cache_secs: Option<u64>,
+
+    /// Look up the hosts of sources with this DNS over HTTPS JSON API, e.g.
+    /// `https://cloudflare-dns.com/dns-query`, caching them as with `--dns-cache-secs`
+    #[clap(long = "dns-over-https")]
+    pub dns_over_https: Option<String>,
+
+    /// Connect to a host of a source at fixed addresses instead of looking it
+    /// up (`<source>.<host>=<ip>[,<ip>...]`)
+    #[clap(long = "pin-address")]
+    pub pinned_addresses: Vec<PinnedAddress>,
+
+    /// Close connections to sources idle for this long; polling slow chains
+    /// less often than that makes every poll handshake again
+    #[clap(long = "http-pool-idle-timeout-secs", default_value = "90")]
+    pub http_pool_idle_timeout_secs: u64,
+
+    /// Keep at most this many idle connections to each host of a source
+    #[clap(long = "http-pool-max-idle-per-host")]
+    pub http_pool_max_idle_per_host: Option<usize>,
+
+    /// Give up on a request to a source after this long, so a hung host fails
+    /// the poll (and gets retried) well before the round of updates times out
+    #[clap(long = "http-timeout-secs", default_value = "10")]
+    pub http_timeout_secs: u64,
+
+    /// Give up on connecting to a host of a source after this long
+    #[clap(long = "http-connect-timeout-secs", default_value = "5")]
+    pub http_connect_timeout_secs: u64,
+
+    /// Send TCP keepalives on connections to sources at this interval
+    #[clap(long = "http-tcp-keepalive-secs")]
+    pub http_tcp_keepalive_secs: Option<u64>,
+
+    /// Sources to talk HTTP/2 to right away, without negotiating it
+    /// (comma-separated); only for ones known to support it
+    #[clap(long = "http2-prior-knowledge", use_value_delimiter = true)]
+    pub http2_prior_knowledge: Vec<SourceId>,
+
+    /// User agent to send to sources
+    #[clap(long = "user-agent", default_value = "curl/7.79.1")]
+    pub user_agent: String,
+
+    /// User agent to send to a particular source (`<source>=<user-agent>`),
+    /// e.g. one blocking the default
+    #[clap(long = "source-user-agent")]
+    pub source_user_agents: Vec<SourceUserAgent>,
+
+    /// Header to add to all requests to a source (`<source>=<name>:<value>`),
+    /// e.g. an API key; the value can be read from `env:<var>` or `file:<path>`
+    #[clap(long = "source-header")]
+    pub source_headers: Vec<SourceHeader>,
+}
+
+impl Opts {
+    /// Settings of `chain` given with `--chain-tuning`, later ones taking precedence
+    pub fn tuning(&self, chain: ChainId) -> Tuning {
+        self.chain_tunings
+            .iter()
+            .filter(|tuning| tuning.chain == chain)
+            .fold(Tuning::default(), |acc, tuning| Tuning {
+                recheck_secs: tuning.tuning.recheck_secs.or(acc.recheck_secs),
+                acceptable_lag_blocks: tuning
+                    .tuning
+                    .acceptable_lag_blocks
+                    .or(acc.acceptable_lag_blocks),
+                stall_multiplier: tuning.tuning.stall_multiplier.or(acc.stall_multiplier),
+                journal_raw_secs: tuning.tuning.journal_raw_secs.or(acc.journal_raw_secs),
+                journal_downsampled_secs: tuning
+                    .tuning
+                    .journal_downsampled_secs
+                    .or(acc.journal_downsampled_secs),
+                journal_max_age_secs: tuning
+                    .tuning
+                    .journal_max_age_secs
+                    .or(acc.journal_max_age_secs),
+            })
+    }
+}
+
+/// Alternatives to running the monitor
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Feed the updates logged with `--journal` back through the recorder,
+    /// printing the resulting best states
+    Replay { file: std::path::PathBuf },
+    /// Print the heights logged with `--journal`, including its rotated part
+    Export {
+        file: std::path::PathBuf,
+        /// Ticker of the chain to export, all if not set
+        #[clap(long = "chain")]
+        chain: Option<String>,
+        /// `csv` or `jsonl`
+        #[clap(long = "format", default_value = "csv")]
+        format: ExportFormat,
+    },
+    /// Print Prometheus alerting rules for the chains of the configured sources,
+    /// with thresholds derived from their block times
+    GenPromRules,
+}
+
+/// Token of websocket clients, see [`Opts::ws_tokens`]
+#[derive(Debug, Clone)]
+pub struct WsToken {
+    /// For telling the clients apart in the logs
+    pub name: String,
+    pub token: String,
+    /// Chains the clients can see, all if `None`
+    pub chains: Option<HashSet<ChainId>>,
+}
+
+impl FromStr for WsToken {
+    type Err = Error;
+
+    /// Parse `<name>=<token>[:<ticker>,...]`
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, token) = s
+            .split_once('=')
+            .ok_or_else(|| format_err!("expected `<name>=<token>[:<ticker>,...]`: {s}"))?;
+        let (token, chains) = match token.split_once(':') {
+            Some((token, tickers)) => (
+                token,
+                Some(
+                    tickers
+                        .split(',')
+                        .map(|ticker| {
+                            ChainId::from_ticker(ticker)
+                                .ok_or_else(|| format_err!("unknown ticker: {ticker}"))
+                        })
+                        .collect::<Result<_>>()?,
+                ),
+            ),
+            None => (token, None),
+        };
+        if token.is_empty() {
+            return Err(format_err!("empty token: {s}"));
+        }
+
+        Ok(Self {
+            name: name.to_owned(),
+            token: token.to_owned(),
+            chains,
+        })
+    }
+}
+
+/// Static label of all metrics, see [`Opts::metrics_labels`]
+#[derive(Debug, Clone)]
+pub struct MetricLabel {
+    pub name: String,
+    pub value: String,
+}
+
+impl FromStr for MetricLabel {
+    type Err = Error;
+
+    /// Parse `<name>=<value>`
+    fn from_str(s: &str) -> Result<Self> {
+        let (name, value) = s
+            .split_once('=')
+            .ok_or_else(|| format_err!("expected `<name>=<value>`: {s}"))?;
+        let valid_name = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_name {
+            return Err(format_err!("invalid label name: {name}"));
+        }
+
+        Ok(Self {
+            name: name.to_owned(),
+            value: value.to_owned(),
+        })
+    }
+}
+
+/// Trust weight of a source, see [`Opts::source_weights`]
+#[derive(Debug, Clone, Copy)]
+pub struct SourceWeight {
+    pub source: SourceId,
+    pub weight: u64,
+}
+
+impl FromStr for SourceWeight {
+    type Err = Error;
+
+    /// Parse `<source>=<weight>`
+    fn from_str(s: &str) -> Result<Self> {
+        let (source, weight) = s
+            .split_once('=')
+            .ok_or_else(|| format_err!("expected `<source>=<weight>`: {s}"))?;
+
+        Ok(Self {
+            source: SourceId::from_str(source)
+                .map_err(|_| format_err!("unknown source: {source}"))?,
+            weight: weight.parse()?,
+        })
+    }
+}
+
+/// Per-chain settings, see [`Opts::chain_tunings`]; `None` for the defaults
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Tuning {
+    pub recheck_secs: Option<u64>,
+    pub acceptable_lag_blocks: Option<u64>,
+    pub stall_multiplier: Option<u64>,
+    pub journal_raw_secs: Option<u64>,
+    pub journal_downsampled_secs: Option<u64>,
+    pub journal_max_age_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChainTuning {
+    pub chain: ChainId,
+    pub tuning: Tuning,
+}
+
+impl FromStr for ChainTuning {
+    type Err = Error;
+
+    /// Parse `<ticker>=<setting>:<value>[,...]`
+    fn from_str(s: &str) -> Result<Self> {
+        let expected = || format_err!("expected `<ticker>=<setting>:<value>[,...]`: {s}");
+        let (ticker, settings) = s.split_once('=').ok_or_else(expected)?;
+        let chain =
+            ChainId::from_ticker(ticker).ok_or_else(|| format_err!("unknown chain: {ticker}"))?;
+
+        let mut tuning = Tuning::default();
+        for setting in settings.split(',') {
+            let (name, value) = setting.split_once(':').ok_or_else(expected)?;
+            let value = Some(value.parse()?);
+            match name {
+                "recheck-secs" => tuning.recheck_secs = value,
+                "acceptable-lag" => tuning.acceptable_lag_blocks = value,
+                "stall-multiplier" => tuning.stall_multiplier = value,
+                "journal-raw-secs" => tuning.journal_raw_secs = value,
+                "journal-downsampled-secs" => tuning.journal_downsampled_secs = value,
+                "journal-max-age-secs" => tuning.journal_max_age_secs = value,
+                _ => return Err(format_err!("unknown chain setting: {name}")),
+            }
+        }
+
+        Ok(Self { chain, tuning })
+    }
+}
+
+/// Metadata of a built-in chain, see [`Opts::chain_metadata`]; `None` for the
+/// built-in values
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChainMetadata {
+    pub block_time_secs: Option<u32>,
+    pub short_name: Option<String>,
+    pub full_name: Option<String>,
+    pub ticker: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChainMetadataOverride {
+    pub chain: ChainId,
+    pub metadata: ChainMetadata,
+}
+
+impl FromStr for ChainMetadataOverride {
+    type Err = Error;
+
+    /// Parse `<ticker>=<field>:<value>[,...]`
+    fn from_str(s: &str) -> Result<Self> {
+        let expected = || format_err!("expected `<ticker>=<field>:<value>[,...]`: {s}");
+        let (ticker, fields) = s.split_once('=').ok_or_else(expected)?;
+        let chain =
+            ChainId::from_ticker(ticker).ok_or_else(|| format_err!("unknown chain: {ticker}"))?;
+
+        let mut metadata = ChainMetadata::default();
+        for field in fields.split(',') {
+            let (name, value) = field.split_once(':').ok_or_else(expected)?;
+            if value.is_empty() {
+                return Err(format_err!("empty chain {name}: {s}"));
+            }
+            match name {
+                "block-time-secs" => match value.parse()? {
+                    0 => return Err(format_err!("block time can't be 0: {s}")),
+                    block_time_secs => metadata.block_time_secs = Some(block_time_secs),
+                },
+                "short-name" => metadata.short_name = Some(value.to_owned()),
+                "full-name" => metadata.full_name = Some(value.to_owned()),
+                "ticker" => metadata.ticker = Some(value.to_owned()),
+                _ => return Err(format_err!("unknown chain field: {name}")),
+            }
+        }
+
+        Ok(Self { chain, metadata })
+    }
+}
+
+/// User agent of a source, see [`Opts::source_user_agents`]
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "all-sources"), allow(dead_code))]
+pub struct SourceUserAgent {
+    pub source: SourceId,
+    pub user_agent: String,
+}
+
+impl FromStr for SourceUserAgent {
+    type Err = Error;
+
+    /// Parse `<source>=<user-agent>`
+    fn from_str(s: &str) -> Result<Self> {
+        let (source, user_agent) = s
+            .split_once('=')
+            .ok_or_else(|| format_err!("expected `<source>=<user-agent>`: {s}"))?;
+
+        Ok(Self {
+            source: SourceId::from_str(source)
+                .map_err(|_| format_err!("unknown source: {source}"))?,
+            user_agent: user_agent.to_owned(),
+        })
+    }
+}
+
+/// Extra header of a source, see [`Opts::source_headers`]
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "all-sources"), allow(dead_code))]
+pub struct SourceHeader {
+    pub source: SourceId,
+    pub name: HeaderName,
+    /// Possibly `env:<var>` or `file:<path>`, see [`crate::util::load_secret`]
+    pub value: String,
+}
+
+impl FromStr for SourceHeader {
+    type Err = Error;
+
+    /// Parse `<source>=<name>:<value>`
+    fn from_str(s: &str) -> Result<Self> {
+        let expected = || format_err!("expected `<source>=<name>:<value>`: {s}");
+        let (source, header) = s.split_once('=').ok_or_else(expected)?;
+        let (name, value) = header.split_once(':').ok_or_else(expected)?;
+
+        Ok(Self {
+            source: SourceId::from_str(source)
+                .map_err(|_| format_err!("unknown source: {source}"))?,
+            name: HeaderName::from_str(name.trim())
+                .map_err(|e| format_err!("invalid header name `{name}`: {e}"))?,
+            value: value.trim().to_owned(),
+        })
+    }
+}
+
+/// Fixed addresses of a host of a source, see [`Opts::pinned_addresses`]
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "all-sources"), allow(dead_code))]
+pub struct PinnedAddress {
+    pub source: SourceId,
+    pub host: String,
+    pub addrs: Vec<IpAddr>,
+}
+
+impl FromStr for PinnedAddress {
+    type Err = Error;
+
+    /// Parse `<source>.<host>=<ip>[,<ip>...]`
+    fn from_str(s: &str) -> Result<Self> {
+        let expected = || format_err!("expected `<source>.<host>=<ip>[,<ip>...]`: {s}");
+        let (target, addrs) = s.split_once('=').ok_or_else(expected)?;
+        let (source, host) = target.split_once('.').ok_or_else(expected)?;
+
+        Ok(Self {
+            source: SourceId::from_str(source)
+                .map_err(|_| format_err!("unknown source: {source}"))?,
+            host: host.to_owned(),
+            addrs: addrs
+                .split(',')
+                .map(|addr| {
+                    addr.parse()
+                        .map_err(|e| format_err!("invalid address `{addr}`: {e}"))
+                })
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+/// Override of where a source reads the height of a chain from, see [`Opts::height_fields`]
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "source-blockchair"), allow(dead_code))]
+pub struct HeightField {
+    pub source: SourceId,
+    pub chain: ChainId,
+    pub field: String,
+}
+
+impl FromStr for HeightField {
+    type Err = Error;
+
+    /// Parse `<source>.<ticker>=<field>`
+    fn from_str(s: &str) -> Result<Self> {
+        let expected = || format_err!("expected `<source>.<ticker>=<field>`: {s}");
+        let (target, field) = s.split_once('=').ok_or_else(expected)?;
+        let (source, ticker) = target.split_once('.').ok_or_else(expected)?;
+
+        Ok(Self {
+            source: SourceId::BUILTIN
+                .iter()
+                .copied()
+                .find(|id| id.short_name().eq_ignore_ascii_case(source))
+                .ok_or_else(|| format_err!("unknown source: {source}"))?,
+            chain: ChainId::from_ticker(ticker)
+                .ok_or_else(|| format_err!("unknown ticker: {ticker}"))?,
+            field: field.to_owned(),
+        })
+    }
+}
+
+/// Alert events a kind of notifier is notified of, see [`Opts::notifier_events`]
+#[derive(Debug, Clone)]
+pub struct NotifierEvents {
+    pub notifier: String,
+    pub events: Vec<AlertEventKind>,
+}
+
+impl FromStr for NotifierEvents {
+    type Err = Error;
+
+    /// Parse `<notifier>=<event>[,...]`
+    fn from_str(s: &str) -> Result<Self> {
+        let (notifier, events) = s
+            .split_once('=')
+            .ok_or_else(|| format_err!("expected `<notifier>=<event>[,...]`: {s}"))?;
+        if !["smtp", "discord", "matrix"].contains(&notifier) {
+            return Err(format_err!("unknown notifier: {notifier}"));
+        }
+
+        Ok(Self {
+            notifier: notifier.to_owned(),
+            events: events
+                .split(',')
+                .map(|event| match event {
+                    "fired" => Ok(AlertEventKind::Fired),
+                    "escalated" => Ok(AlertEventKind::Escalated),
+                    "resolved" => Ok(AlertEventKind::Resolved),
+                    _ => Err(format_err!("unknown alert event: {event}")),
+                })
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+/// Security of the connection to an SMTP server, see [`Opts::smtp_tls`]
+#[derive(Debug, Clone, Copy, EnumString)]
+#[strum(serialize_all = "lowercase")]
+#[cfg_attr(not(feature = "notifier-smtp"), allow(dead_code))]
+pub enum SmtpTls {
+    /// Plain text, only for servers on the local network
+    None,
+    /// Upgraded to TLS, usually on port 587
+    StartTls,
+    /// TLS right away, usually on port 465
+    Tls,
+}
+
+/// How settled should Solana blocks be to be reported
+#[derive(Debug, Clone, Copy, EnumString, IntoStaticStr)]
+#[strum(serialize_all = "lowercase")]
+pub enum SolanaCommitment {
+    /// Voted on by the leader only
+    Processed,
+    /// Voted on by a supermajority of the cluster
+    Confirmed,
+    /// Rooted, with 31+ confirmed blocks built on top
+    Finalized,
 }
 
 pub fn from_args() -> Opts {
+    // other options name custom signets by ticker, so they have to be known first
+    let custom_signets =
+        custom_signet_args(std::env::args_os().map(|arg| arg.to_string_lossy().into_owned()));
+    if let Err(e) = crate::source::register_custom_signets(&custom_signets) {
+        clap::Error::raw(clap::ErrorKind::ValueValidation, format!("{e}\n")).exit();
+    }
+
     Opts::parse()
 }
+
+/// Values of `--custom-signet` in `args`, ahead of parsing them all
+fn custom_signet_args(args: impl Iterator<Item = String>) -> Vec<String> {
+    let mut args = args.take_while(|arg| arg != "--");
+    let mut values = vec![];
+    while let Some(arg) = args.next() {
+        if arg == "--custom-signet" {
+            values.extend(args.next());
+        } else if let Some(value) = arg.strip_prefix("--custom-signet=") {
+            values.push(value.to_owned());
+        }
+    }
+    values
+}
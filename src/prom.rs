@@ -1,21 +1,51 @@
-use anyhow::Result;
-use axum::{extract::MatchedPath, middleware::Next, response::IntoResponse};
+use anyhow::{format_err, Result};
+use axum::{
+    extract::{ConnectInfo, MatchedPath},
+    http::StatusCode,
+    middleware::{self, Next},
+    response::IntoResponse,
+    Router,
+};
 use hyper::Request;
+use ipnet::IpNet;
 use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
-use std::time::Instant;
-pub fn setup_metrics_recorder() -> Result<PrometheusHandle> {
+use metrics_util::layers::{Layer, PrefixLayer};
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    time::Instant,
+};
+
+use crate::opts::MetricLabel;
+/// Install the Prometheus recorder, with all metric names prefixed with
+/// `prefix` and all metrics labeled with `labels`
+pub fn setup_metrics_recorder(
+    prefix: Option<&str>,
+    labels: &[MetricLabel],
+) -> Result<PrometheusHandle> {
     const EXPONENTIAL_SECONDS: &[f64] = &[
         0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
     ];
 
-    let handle = PrometheusBuilder::new()
-        .set_buckets_for_metric(
-            Matcher::Full("http_requests_duration_seconds".to_string()),
+    let builder = labels.iter().fold(
+        PrometheusBuilder::new().set_buckets_for_metric(
+            Matcher::Suffix("http_requests_duration_seconds".to_string()),
             EXPONENTIAL_SECONDS,
-        )?
-        .install_recorder();
+        )?,
+        |builder, label| builder.add_global_label(&label.name, &label.value),
+    );
+
+    let recorder = builder.build_recorder();
+    let handle = recorder.handle();
+    // the `.` separating the prefix is exported as `_`
+    let res = match prefix {
+        Some(prefix) => {
+            metrics::set_boxed_recorder(Box::new(PrefixLayer::new(prefix).layer(recorder)))
+        }
+        None => metrics::set_boxed_recorder(Box::new(recorder)),
+    };
+    res.map_err(|e| format_err!("couldn't install the metrics recorder: {e}"))?;
 
-    Ok(handle?)
+    Ok(handle)
 }
 
 pub async fn track_metrics<B>(req: Request<B>, next: Next<B>) -> impl IntoResponse {
@@ -43,3 +73,40 @@ pub async fn track_metrics<B>(req: Request<B>, next: Next<B>) -> impl IntoRespon
 
     response
 }
+
+/// Address of a client, with IPv4 ones connected to a dual-stack socket
+/// showing up as mapped IPv6 ones unmapped
+fn client_ip(addr: &SocketAddr) -> IpAddr {
+    match addr.ip() {
+        IpAddr::V6(ip) => match ip.octets() {
+            [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, a, b, c, d] => {
+                IpAddr::V4(Ipv4Addr::new(a, b, c, d))
+            }
+            _ => IpAddr::V6(ip),
+        },
+        ip => ip,
+    }
+}
+
+/// Reject requests coming from outside of `networks`
+pub fn require_allowed_ip(router: Router, networks: Vec<IpNet>) -> Router {
+    router.route_layer(middleware::from_fn(
+        move |req: Request<_>, next: Next<_>| {
+            let allowed =
+                req.extensions()
+                    .get::<ConnectInfo<SocketAddr>>()
+                    .map(|ConnectInfo(addr)| {
+                        let ip = client_ip(addr);
+                        networks.iter().any(|network| network.contains(&ip))
+                    })
+                    == Some(true);
+            async move {
+                if allowed {
+                    Ok(next.run(req).await)
+                } else {
+                    Err(StatusCode::FORBIDDEN)
+                }
+            }
+        },
+    ))
+}
@@ -0,0 +1,159 @@
+//! Prometheus alerting rules on the exported metrics, see `gen-prom-rules`
+use std::fmt::Write;
+
+use crate::{opts::Tuning, source::ChainId};
+
+/// Shortest tip age considered a stall, for chains with a short block time,
+/// whose tips only get to be this old when polled
+const MIN_STALL_SECS: u64 = 300;
+/// Block times without a new block to consider a chain stalled, unless tuned
+///
+/// Gaps of 10 block times are rare enough with proof of work (about once
+/// every 20,000 blocks), and should never happen otherwise.
+const STALL_MULTIPLIER: u64 = 10;
+/// Time a source can fall behind the best one before alerting, in seconds,
+/// unless the acceptable lag is tuned
+const LAG_SECS: u64 = 300;
+/// Time a chain can fall behind its schedule before alerting, in seconds,
+/// unless the stall multiplier is tuned
+const BEHIND_SCHEDULE_SECS: u64 = 1800;
+/// Success rate in the last hour below which a source is considered down
+const SOURCE_DOWN_SUCCESS_RATE: f64 = 0.5;
+
+struct Rule {
+    alert: &'static str,
+    expr: String,
+    for_: &'static str,
+    severity: &'static str,
+    summary: String,
+}
+
+/// Single-quoted YAML scalar
+fn yaml_str(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+/// Label matcher selecting `chain`
+fn ticker_matcher(chain: ChainId) -> String {
+    format!(
+        "ticker=\"{}\"",
+        chain.ticker().replace('\\', "\\\\").replace('"', "\\\"")
+    )
+}
+
+fn chain_rules(chain: ChainId, tuning: Tuning, metric: &impl Fn(&str) -> String) -> Vec<Rule> {
+    let block_time_secs = u64::from(chain.block_time_secs().max(1));
+    let ticker = ticker_matcher(chain);
+    let stall_secs =
+        (block_time_secs * tuning.stall_multiplier.unwrap_or(STALL_MULTIPLIER)).max(MIN_STALL_SECS);
+    let lag_blocks = tuning
+        .acceptable_lag_blocks
+        .unwrap_or_else(|| (LAG_SECS / block_time_secs).max(2));
+    let behind_schedule_blocks = tuning
+        .stall_multiplier
+        .unwrap_or_else(|| (BEHIND_SCHEDULE_SECS / block_time_secs).max(3));
+    let height = metric("chain_monitor_chain_height");
+
+    vec![
+        Rule {
+            alert: "ChainStalled",
+            expr: format!(
+                "min by (ticker) ({}{{{ticker}}}) > {stall_secs}",
+                metric("chain_monitor_chain_tip_age_seconds")
+            ),
+            for_: "5m",
+            severity: "critical",
+            summary: format!(
+                "No new {} block in {{{{ $value | humanizeDuration }}}}",
+                chain.full_name()
+            ),
+        },
+        Rule {
+            alert: "ChainBehindSchedule",
+            expr: format!(
+                "{}{{{ticker}}} > {behind_schedule_blocks}",
+                metric("chain_monitor_chain_behind_schedule_blocks")
+            ),
+            for_: "10m",
+            severity: "warning",
+            summary: format!(
+                "{} is {{{{ $value }}}} blocks behind its schedule",
+                chain.full_name()
+            ),
+        },
+        Rule {
+            alert: "SourceLagging",
+            expr: format!(
+                "max by (ticker, height_kind) ({height}{{{ticker}}}) \
+                 - on (ticker, height_kind) group_right {height}{{{ticker}}} > {lag_blocks}"
+            ),
+            for_: "10m",
+            severity: "warning",
+            summary: format!(
+                "{{{{ $labels.source_full_name }}}} is {{{{ $value }}}} {} blocks behind",
+                chain.full_name()
+            ),
+        },
+    ]
+}
+
+fn source_rules(metric: &impl Fn(&str) -> String) -> Vec<Rule> {
+    vec![Rule {
+        alert: "SourceDown",
+        expr: format!(
+            "{}{{window=\"1h\"}} < {SOURCE_DOWN_SUCCESS_RATE}",
+            metric("chain_monitor_source_success_rate")
+        ),
+        for_: "15m",
+        severity: "warning",
+        summary: "Only {{ $value | humanizePercentage }} of the updates of {{ $labels.source }} \
+                  succeeded in the last hour"
+            .to_owned(),
+    }]
+}
+
+fn write_group(out: &mut String, name: &str, rules: &[Rule]) {
+    writeln!(out, "  - name: {}", yaml_str(name)).expect("can't fail");
+    out.push_str("    rules:\n");
+    for rule in rules {
+        writeln!(
+            out,
+            "      - alert: {}\n        expr: {}\n        for: {}\n        labels:\n          severity: {}\n        annotations:\n          summary: {}",
+            rule.alert,
+            yaml_str(&rule.expr),
+            rule.for_,
+            rule.severity,
+            yaml_str(&rule.summary),
+        )
+        .expect("can't fail");
+    }
+}
+
+/// Rules file with a group for each of `chains`, and one for the sources
+///
+/// The thresholds are derived from the block time of each chain, and its
+/// `tuning`. Metric names get `prefix` as with `--metrics-prefix`.
+pub fn render(
+    chains: &[ChainId],
+    tuning: impl Fn(ChainId) -> Tuning,
+    prefix: Option<&str>,
+) -> String {
+    let metric = |name: &str| match prefix {
+        Some(prefix) => format!("{prefix}_{name}"),
+        None => name.to_owned(),
+    };
+
+    let mut chains = chains.to_vec();
+    chains.sort_by_key(|chain| chain.ticker());
+
+    let mut out = String::from("groups:\n");
+    for chain in chains {
+        write_group(
+            &mut out,
+            &format!("chain-monitor-{}", chain.ticker().to_lowercase()),
+            &chain_rules(chain, tuning(chain), &metric),
+        );
+    }
+    write_group(&mut out, "chain-monitor-sources", &source_rules(&metric));
+    out
+}
@@ -0,0 +1,169 @@
+//! Maintenance windows and silences, keeping alerts from being notified
+use std::str::FromStr;
+
+use anyhow::{format_err, Error, Result};
+use serde::Serialize;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use crate::source::{ChainId, SourceId};
+
+/// What a silence applies to
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SilenceTarget {
+    Chain(ChainId),
+    /// Every chain the source reports, e.g. a node being upgraded
+    Source(SourceId),
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SilenceOrigin {
+    /// `--maintenance-window`
+    Maintenance,
+    /// `/admin/silence`
+    Admin,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Silence {
+    id: u64,
+    target: SilenceTarget,
+    origin: SilenceOrigin,
+    reason: String,
+    start_ts: u64,
+    end_ts: u64,
+}
+
+impl Silence {
+    fn is_active(&self, now: u64) -> bool {
+        self.start_ts <= now && now < self.end_ts
+    }
+}
+
+/// A planned maintenance (`<ticker|source>=<from>/<to>`, RFC 3339 times)
+#[derive(Debug, Clone)]
+pub struct MaintenanceWindow {
+    target: SilenceTarget,
+    start_ts: u64,
+    end_ts: u64,
+}
+
+fn parse_ts(s: &str) -> Result<u64> {
+    let datetime = OffsetDateTime::parse(s, &Rfc3339)
+        .map_err(|e| format_err!("invalid RFC 3339 time `{s}`: {e}"))?;
+    Ok(u64::try_from(datetime.unix_timestamp())?)
+}
+
+/// Chain by ticker, or source by short name
+pub fn parse_target(s: &str) -> Result<SilenceTarget> {
+    ChainId::from_ticker(s)
+        .map(SilenceTarget::Chain)
+        .or_else(|| SourceId::from_str(s).ok().map(SilenceTarget::Source))
+        .ok_or_else(|| format_err!("neither a ticker nor a source: {s}"))
+}
+
+impl FromStr for MaintenanceWindow {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let expected = || format_err!("expected `<ticker|source>=<from>/<to>`: {s}");
+        let (target, window) = s.split_once('=').ok_or_else(expected)?;
+        let (start, end) = window.split_once('/').ok_or_else(expected)?;
+        let (start_ts, end_ts) = (parse_ts(start)?, parse_ts(end)?);
+        if end_ts <= start_ts {
+            return Err(format_err!("maintenance window ends before it starts: {s}"));
+        }
+
+        Ok(Self {
+            target: parse_target(target)?,
+            start_ts,
+            end_ts,
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct Silences {
+    last_id: u64,
+    silences: Vec<Silence>,
+}
+
+impl Silences {
+    pub fn new(windows: &[MaintenanceWindow]) -> Self {
+        let mut silences = Self::default();
+        for window in windows {
+            silences.push(
+                window.target,
+                SilenceOrigin::Maintenance,
+                "maintenance window".into(),
+                window.start_ts,
+                window.end_ts,
+            );
+        }
+        silences
+    }
+
+    fn push(
+        &mut self,
+        target: SilenceTarget,
+        origin: SilenceOrigin,
+        reason: String,
+        start_ts: u64,
+        end_ts: u64,
+    ) -> Silence {
+        self.last_id += 1;
+        let silence = Silence {
+            id: self.last_id,
+            target,
+            origin,
+            reason,
+            start_ts,
+            end_ts,
+        };
+        self.silences.push(silence.clone());
+        silence
+    }
+
+    /// Silence `target` for `duration_secs` starting `now`
+    pub fn add(
+        &mut self,
+        target: SilenceTarget,
+        duration_secs: u64,
+        reason: String,
+        now: u64,
+    ) -> Silence {
+        self.push(
+            target,
+            SilenceOrigin::Admin,
+            reason,
+            now,
+            now.saturating_add(duration_secs),
+        )
+    }
+
+    /// Lift a silence early; `false` if there's none with `id`
+    pub fn remove(&mut self, id: u64) -> bool {
+        let len = self.silences.len();
+        self.silences.retain(|silence| silence.id != id);
+        self.silences.len() != len
+    }
+
+    /// Silences in effect or yet to come, forgetting the ones that ended
+    pub fn current(&mut self, now: u64) -> Vec<Silence> {
+        self.silences.retain(|silence| now < silence.end_ts);
+        self.silences.clone()
+    }
+
+    /// Whether alerts on `chain`, reported by `sources`, are silenced
+    pub fn is_silenced(&self, chain: ChainId, sources: &[SourceId], now: u64) -> bool {
+        self.silences
+            .iter()
+            .filter(|silence| silence.is_active(now))
+            .any(|silence| match silence.target {
+                SilenceTarget::Chain(silenced) => silenced == chain,
+                SilenceTarget::Source(silenced) => sources.contains(&silenced),
+            })
+    }
+}
@@ -0,0 +1,34 @@
+//! Chain states saved to disk, so a restarted instance doesn't start empty
+use std::path::Path;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::ChainStateTs;
+
+/// State of a chain as reported by a source, at the time of the snapshot
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+    /// Short name of the source
+    pub source: String,
+    pub ticker: String,
+    #[serde(flatten)]
+    pub state: ChainStateTs,
+}
+
+pub fn save(path: &Path, entries: &[Entry]) -> Result<()> {
+    // write the whole file at once, so a crash can't leave it half-written
+    let tmp_path = path.with_extension("tmp");
+    std::fs::write(&tmp_path, serde_json::to_vec(entries)?)?;
+    std::fs::rename(tmp_path, path)?;
+    Ok(())
+}
+
+/// Entries saved at `path`, none if there's no snapshot yet
+pub fn load(path: &Path) -> Result<Vec<Entry>> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+}
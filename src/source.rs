@@ -1,42 +1,148 @@
-use crate::{opts::Opts, ChainUpdateRecorder};
-use anyhow::Result;
+use crate::{
+    opts::{ChainMetadata, ChainMetadataOverride, Opts},
+    ChainUpdateRecorder,
+};
+use anyhow::{bail, format_err, Result};
 use axum::async_trait;
 use futures::future::join_all;
-use serde::Serialize;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize, Serializer};
 use std::{
-    cmp,
     collections::{HashMap, HashSet},
     fmt::Display,
+    str::FromStr,
+    time::Duration,
 };
-use strum::IntoStaticStr;
-use tokio::sync::Mutex;
-use tracing::debug;
+use strum::{EnumString, IntoStaticStr};
+
+pub use error::{SourceError, SourceErrorKind};
+#[cfg_attr(not(feature = "all-sources"), allow(unused_imports))]
+pub use poller::{ChainStateFetcher, Poller, PollingPolicy};
 
+#[cfg(feature = "source-aptos")]
+mod aptos;
+#[cfg(feature = "source-bitcoind")]
+mod bitcoind;
+#[cfg(feature = "source-bitgo")]
 mod bitgo;
+#[cfg(feature = "source-bitgov1")]
 mod bitgov1;
+#[cfg(feature = "source-blockbook")]
+mod blockbook;
+#[cfg(feature = "source-blockchain")]
 mod blockchain;
+#[cfg(feature = "source-blockchair")]
 mod blockchair;
+#[cfg(feature = "source-blockcypher")]
 mod blockcypher;
+#[cfg(feature = "source-blockfrost")]
+mod blockfrost;
+#[cfg(feature = "source-blockscout")]
+mod blockscout;
+#[cfg(feature = "source-blockstream")]
+mod blockstream;
+#[cfg(feature = "source-chainmonitor")]
 mod chainmonitor;
+#[cfg(feature = "source-cmc")]
 mod cmc;
+mod conditional;
+#[cfg(feature = "source-cryptoid")]
+mod cryptoid;
+mod dns;
+mod error;
+#[cfg(any(
+    feature = "source-blockstream",
+    feature = "source-custom-signet",
+    feature = "source-mempoolspace"
+))]
+mod esplora;
+#[cfg(feature = "source-evm")]
+mod evm;
+#[cfg(any(test, feature = "test-util"))]
+pub mod fixtures;
+#[cfg(feature = "source-glif")]
+mod glif;
+mod http;
+#[cfg(feature = "source-kaspa")]
+mod kaspa;
+#[cfg(feature = "source-koios")]
+mod koios;
+#[cfg(feature = "source-mempoolspace")]
 mod mempoolspace;
+#[cfg(any(test, feature = "test-util"))]
+pub mod mock;
+#[cfg(feature = "source-near")]
+mod near;
+#[cfg(feature = "source-newheads")]
+mod newheads;
+#[cfg(feature = "source-other")]
 mod other;
+mod pacing;
+mod poller;
+#[cfg(feature = "source-custom-signet")]
+mod signet;
+#[cfg(feature = "source-simulated")]
+mod simulated;
+#[cfg(feature = "source-solana")]
+mod solana;
+#[cfg(feature = "source-stacks")]
+mod stacks;
+#[cfg(feature = "source-subscan")]
+mod subscan;
+#[cfg(feature = "source-substrate")]
+mod substrate;
+#[cfg(feature = "source-sui")]
+mod sui;
+#[cfg(feature = "source-tendermint")]
+mod tendermint;
+#[cfg(feature = "source-tezos")]
+mod tezos;
+#[cfg(feature = "source-toncenter")]
+mod toncenter;
+#[cfg(feature = "source-trongrid")]
+mod trongrid;
+#[cfg(any(feature = "source-newheads", feature = "source-xrpl"))]
+mod websocket;
+#[cfg(feature = "source-xrpl")]
+mod xrpl;
 
 #[async_trait]
-pub trait Source: Sync {
+pub trait Source: Sync + Send {
     fn get_supported_chains(&self) -> HashSet<ChainId>;
     fn get_supported_sources(&self) -> HashSet<SourceId>;
 
+    /// Explorer page of `chain` as provided by `source`
+    ///
+    /// Might contain `{height}` and `{hash}` placeholders, to be filled
+    /// with the data of a particular block.
+    fn url_for_chain(&self, source: SourceId, chain: ChainId) -> Option<String>;
+
+    /// Time to wait after `check_updates` before calling it again
+    ///
+    /// Sources listening for pushed updates can keep listening in
+    /// `check_updates` for a while instead, and use a shorter one.
+    fn update_interval(&self) -> Duration {
+        Duration::from_secs(15)
+    }
+
     async fn check_updates(&self, recorder: &dyn ChainUpdateRecorder);
 }
 
 /// Like `Source`, but doesn't do anything fancy,
 /// so can use const fields
+///
+/// Meant for sources that fetch many chains at once; ones that go
+/// chain by chain should be a [`ChainStateFetcher`] instead.
 #[async_trait]
-pub trait StaticSource: Sync {
+pub trait StaticSource: Sync + Send {
     const ID: SourceId;
     const SUPPORTED_CHAINS: &'static [ChainId];
 
+    /// See [`Source::url_for_chain`]
+    fn url_for_chain(&self, _chain: ChainId) -> Option<String> {
+        None
+    }
+
     async fn check_updates(&self, recorder: &dyn ChainUpdateRecorder);
 }
 
@@ -54,13 +160,22 @@ where
         HashSet::from_iter(vec![Self::ID])
     }
 
+    fn url_for_chain(&self, source: SourceId, chain: ChainId) -> Option<String> {
+        if source == Self::ID && Self::SUPPORTED_CHAINS.contains(&chain) {
+            <S as StaticSource>::url_for_chain(self, chain)
+        } else {
+            None
+        }
+    }
+
     async fn check_updates(&self, recorder: &dyn ChainUpdateRecorder) {
         S::check_updates(&self, recorder).await
     }
 }
 
-#[derive(Debug, Clone, Copy, IntoStaticStr, Serialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[derive(Debug, Clone, Copy, IntoStaticStr, EnumString, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum SourceId {
+    Bitcoind,
     BitGo,
     Blockchain,
     Blockchair,
@@ -68,13 +183,137 @@ pub enum SourceId {
     CMC,
     MempoolSpace,
     BitGoV1,
+    EvmRpc,
     Other,
+    Blockstream,
+    /// User's own Esplora instances of custom signets
+    CustomEsplora,
+    /// Self-hosted EVM nodes, pushing new blocks over websocket
+    EvmNode,
+    TronGrid,
+    NearRpc,
+    TonCenter,
+    AptosRpc,
+    SuiRpc,
+    Glif,
+    KaspaApi,
+    Tendermint,
+    Substrate,
+    Subscan,
+    XrplCluster,
+    Blockfrost,
+    Koios,
+    SolanaRpc,
+    Blockbook,
+    CryptoId,
+    Blockscout,
+    StacksApi,
+    TezosNode,
+    /// Mirrored data the remote instance didn't attribute to any known source
     ChainMonitor,
+    /// A source as reported by a mirrored chain-monitor instance
+    #[strum(disabled)]
+    Mirrored(MirroredSourceId),
+    /// A fake source of `--simulate`
+    #[strum(disabled)]
+    Simulated(SimulatedSourceId),
+}
+
+/// Identity of a source seen through a mirror
+///
+/// Names are formatted once, when the mirror is set up, so they can be
+/// handed out as `&'static str` like the ones of built-in sources.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MirroredSourceId {
+    short_name: &'static str,
+    full_name: &'static str,
+    url: &'static str,
+}
+
+impl MirroredSourceId {
+    pub fn new(source: SourceId, mirror_idx: usize, mirror_name: &str, url: &str) -> Self {
+        Self {
+            url: Box::leak(url.to_owned().into_boxed_str()),
+            short_name: Box::leak(
+                format!("{}@{}", source.short_name(), mirror_idx).into_boxed_str(),
+            ),
+            full_name: Box::leak(
+                format!("{} (via mirror {mirror_name})", source.full_name()).into_boxed_str(),
+            ),
+        }
+    }
+}
+
+/// Identity of a fake source of `--simulate`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SimulatedSourceId {
+    short_name: &'static str,
+    full_name: &'static str,
+}
+
+#[cfg_attr(not(feature = "source-simulated"), allow(dead_code))]
+impl SimulatedSourceId {
+    pub const fn new(short_name: &'static str, full_name: &'static str) -> Self {
+        Self {
+            short_name,
+            full_name,
+        }
+    }
+}
+
+impl Serialize for SourceId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.short_name())
+    }
 }
 
 impl SourceId {
+    /// All sources that can be reported by a remote instance
+    pub const BUILTIN: &'static [SourceId] = &[
+        SourceId::Bitcoind,
+        SourceId::BitGo,
+        SourceId::Blockchain,
+        SourceId::Blockchair,
+        SourceId::BlockCypher,
+        SourceId::CMC,
+        SourceId::MempoolSpace,
+        SourceId::BitGoV1,
+        SourceId::EvmRpc,
+        SourceId::Other,
+        SourceId::Blockstream,
+        SourceId::CustomEsplora,
+        SourceId::EvmNode,
+        SourceId::TronGrid,
+        SourceId::NearRpc,
+        SourceId::TonCenter,
+        SourceId::AptosRpc,
+        SourceId::SuiRpc,
+        SourceId::Glif,
+        SourceId::KaspaApi,
+        SourceId::Tendermint,
+        SourceId::Substrate,
+        SourceId::Subscan,
+        SourceId::XrplCluster,
+        SourceId::Blockfrost,
+        SourceId::Koios,
+        SourceId::SolanaRpc,
+        SourceId::Blockbook,
+        SourceId::CryptoId,
+        SourceId::Blockscout,
+        SourceId::StacksApi,
+        SourceId::TezosNode,
+    ];
+
+    pub fn from_short_name(short_name: &str) -> Option<Self> {
+        SourceId::from_str(short_name).ok()
+    }
+
     pub fn full_name(self) -> &'static str {
         match self {
+            SourceId::Bitcoind => "bitcoind",
             SourceId::BitGo => "BitGo",
             SourceId::BitGoV1 => "BitGo (v1)",
             SourceId::Blockchain => "Blockchain.com",
@@ -82,16 +321,86 @@ impl SourceId {
             SourceId::BlockCypher => "BlockCypher",
             SourceId::MempoolSpace => "mempool.space",
             SourceId::CMC => "CoinMarketCap",
+            SourceId::EvmRpc => "EVM JSON-RPC",
             SourceId::Other => "Other",
+            SourceId::Blockstream => "Blockstream",
+            SourceId::CustomEsplora => "Esplora (custom)",
+            SourceId::EvmNode => "EVM node",
+            SourceId::TronGrid => "TronGrid",
+            SourceId::NearRpc => "NEAR RPC",
+            SourceId::TonCenter => "toncenter",
+            SourceId::AptosRpc => "Aptos fullnode API",
+            SourceId::SuiRpc => "Sui JSON-RPC",
+            SourceId::Glif => "Glif",
+            SourceId::KaspaApi => "Kaspa REST API",
+            SourceId::Tendermint => "Tendermint RPC",
+            SourceId::Substrate => "Substrate RPC",
+            SourceId::Subscan => "Subscan",
+            SourceId::XrplCluster => "XRPL public servers",
+            SourceId::Blockfrost => "Blockfrost",
+            SourceId::Koios => "Koios",
+            SourceId::SolanaRpc => "Solana RPC",
+            SourceId::Blockbook => "Blockbook",
+            SourceId::CryptoId => "CryptoID",
+            SourceId::Blockscout => "Blockscout",
+            SourceId::StacksApi => "Stacks API",
+            SourceId::TezosNode => "Tezos node RPC",
             SourceId::ChainMonitor => "ChainMonitor",
+            SourceId::Mirrored(id) => id.full_name,
+            SourceId::Simulated(id) => id.full_name,
         }
     }
     pub fn short_name(self) -> &'static str {
-        self.into()
+        match self {
+            SourceId::Mirrored(id) => id.short_name,
+            SourceId::Simulated(id) => id.short_name,
+            _ => self.into(),
+        }
+    }
+
+    /// Homepage of the source
+    pub fn url(self) -> Option<&'static str> {
+        Some(match self {
+            SourceId::BitGo | SourceId::BitGoV1 => "https://www.bitgo.com",
+            SourceId::Blockchain => "https://www.blockchain.com/explorer",
+            SourceId::Blockchair => "https://blockchair.com",
+            SourceId::BlockCypher => "https://live.blockcypher.com",
+            SourceId::MempoolSpace => "https://mempool.space",
+            SourceId::Blockstream => "https://blockstream.info",
+            SourceId::TronGrid => "https://www.trongrid.io",
+            SourceId::TonCenter => "https://toncenter.com",
+            SourceId::Glif => "https://glif.io",
+            SourceId::Subscan => "https://www.subscan.io",
+            SourceId::Blockfrost => "https://blockfrost.io",
+            SourceId::Koios => "https://koios.rest",
+            SourceId::CryptoId => "https://chainz.cryptoid.info",
+            SourceId::KaspaApi => "https://api.kaspa.org",
+            SourceId::CMC => "https://blockchain.coinmarketcap.com",
+            SourceId::Mirrored(id) => id.url,
+            SourceId::Bitcoind
+            | SourceId::EvmRpc
+            | SourceId::Other
+            | SourceId::ChainMonitor
+            | SourceId::CustomEsplora
+            | SourceId::EvmNode
+            | SourceId::NearRpc
+            | SourceId::AptosRpc
+            | SourceId::SuiRpc
+            | SourceId::Tendermint
+            | SourceId::Substrate
+            | SourceId::XrplCluster
+            | SourceId::SolanaRpc
+            | SourceId::Blockbook
+            | SourceId::Blockscout
+            | SourceId::StacksApi
+            | SourceId::TezosNode
+            | SourceId::Simulated(_) => return None,
+        })
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum NetworkType {
     Mainnet,
     Testnet,
@@ -108,7 +417,198 @@ impl Display for NetworkType {
     }
 }
 
-#[derive(Debug, Clone, Copy, IntoStaticStr, Hash, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+/// What does a reported height of a chain count
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HeightKind {
+    /// Blocks actually produced
+    Block,
+    /// Slots (block production opportunities), including the skipped ones
+    Slot,
+    /// Blocks of the chain this one is anchored to, e.g. Bitcoin for Stacks
+    #[serde(rename = "burn_block")]
+    BurnBlock,
+}
+
+impl Display for HeightKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            HeightKind::Block => "block",
+            HeightKind::Slot => "slot",
+            HeightKind::BurnBlock => "burn_block",
+        })
+    }
+}
+
+/// How are block hashes of a chain encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashFormat {
+    /// Hex-encoded bytes, optionally `0x`-prefixed
+    Hex { bytes: usize },
+    /// Base58, of a given length range
+    Base58 { min_len: usize, max_len: usize },
+    /// Not validated
+    Any,
+}
+
+impl HashFormat {
+    pub fn validate(self, hash: &str) -> Result<()> {
+        match self {
+            HashFormat::Hex { bytes } => {
+                let hex = hash.strip_prefix("0x").unwrap_or(hash);
+                if hex.len() != bytes * 2 {
+                    bail!("expected {} hex digits, got {}", bytes * 2, hex.len());
+                }
+                if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                    bail!("invalid hex");
+                }
+            }
+            HashFormat::Base58 { min_len, max_len } => {
+                if hash.len() < min_len || max_len < hash.len() {
+                    bail!(
+                        "expected {min_len}-{max_len} base58 characters, got {}",
+                        hash.len()
+                    );
+                }
+                if !hash
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() && !"0OIl".contains(c))
+                {
+                    bail!("invalid base58");
+                }
+            }
+            HashFormat::Any => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct CustomSignetInfo {
+    short_name: String,
+    full_name: String,
+    ticker: String,
+    block_time_secs: u32,
+}
+
+/// Identity of a signet network defined by the user
+///
+/// Like with [`MirroredSourceId`], the details are leaked once on startup,
+/// so names can be handed out as `&'static str`, and the id stays small.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CustomSignetId(&'static CustomSignetInfo);
+
+impl CustomSignetId {
+    fn new(ticker: &str, name: &str, block_time_secs: u32) -> Self {
+        Self(Box::leak(Box::new(CustomSignetInfo {
+            short_name: name.replace(' ', ""),
+            full_name: format!("{name} (signet)"),
+            ticker: ticker.to_owned(),
+            block_time_secs,
+        })))
+    }
+}
+
+/// Nominal block time of custom signets, unless configured otherwise
+const CUSTOM_SIGNET_BLOCK_TIME_SECS: u32 = 600;
+
+/// Custom signets, registered once on startup so [`ChainId::from_ticker`]
+/// knows them, see [`register_custom_signets`]
+static CUSTOM_SIGNETS: OnceCell<Vec<CustomSignetId>> = OnceCell::new();
+
+/// Register the signets of `--custom-signet` `entries`
+/// (`<ticker>[:<name>[:<block-time-secs>]]=<esplora-api-url>`), before
+/// anything naming chains by ticker is parsed
+pub fn register_custom_signets(entries: &[String]) -> Result<()> {
+    let mut signets: Vec<CustomSignetId> = vec![];
+    for entry in entries {
+        let (id, _url) = entry.split_once('=').ok_or_else(|| {
+            format_err!(
+                "expected `<ticker>[:<name>[:<block-time-secs>]]=<esplora-api-url>`: {entry}"
+            )
+        })?;
+        let mut parts = id.splitn(3, ':');
+        let ticker = parts.next().unwrap_or_default();
+        let name = parts.next().unwrap_or(ticker);
+        let block_time_secs = parts
+            .next()
+            .map(str::parse)
+            .transpose()?
+            .unwrap_or(CUSTOM_SIGNET_BLOCK_TIME_SECS);
+
+        if ChainId::from_ticker(ticker).is_some()
+            || signets.iter().any(|signet| signet.0.ticker == ticker)
+        {
+            bail!("ticker already taken: {ticker}");
+        }
+        signets.push(CustomSignetId::new(ticker, name, block_time_secs));
+    }
+
+    CUSTOM_SIGNETS
+        .set(signets)
+        .map_err(|_| format_err!("custom signets already registered"))
+}
+
+/// Metadata of built-in chains overridden with `--chain-metadata`, set once
+/// on startup, so it can be handed out as `&'static str` like the built-in one
+static CHAIN_METADATA: OnceCell<HashMap<ChainId, ChainMetadata>> = OnceCell::new();
+
+/// Apply the `--chain-metadata` `overrides`, later ones taking precedence
+pub fn set_chain_metadata(overrides: &[ChainMetadataOverride]) -> Result<()> {
+    let mut all: HashMap<ChainId, ChainMetadata> = HashMap::new();
+    for entry in overrides {
+        let metadata = all.entry(entry.chain).or_default();
+        *metadata = ChainMetadata {
+            block_time_secs: entry.metadata.block_time_secs.or(metadata.block_time_secs),
+            short_name: entry
+                .metadata
+                .short_name
+                .clone()
+                .or_else(|| metadata.short_name.take()),
+            full_name: entry
+                .metadata
+                .full_name
+                .clone()
+                .or_else(|| metadata.full_name.take()),
+            ticker: entry
+                .metadata
+                .ticker
+                .clone()
+                .or_else(|| metadata.ticker.take()),
+        };
+    }
+
+    // a ticker has to keep telling a single chain
+    for (chain, metadata) in &all {
+        let ticker = match metadata.ticker.as_deref() {
+            Some(ticker) => ticker,
+            None => continue,
+        };
+        let other = ChainId::from_ticker(ticker)
+            .filter(|other| other != chain)
+            .or_else(|| {
+                all.iter()
+                    .find(|(other, metadata)| {
+                        *other != chain && metadata.ticker.as_deref() == Some(ticker)
+                    })
+                    .map(|(other, _)| *other)
+            });
+        if let Some(other) = other {
+            bail!("can't use the ticker {ticker} of {other:?} for {chain:?}");
+        }
+    }
+
+    CHAIN_METADATA
+        .set(all)
+        .map_err(|_| format_err!("chain metadata already set"))
+}
+
+/// Overridden metadata of `chain`, if any
+fn chain_metadata(chain: ChainId) -> Option<&'static ChainMetadata> {
+    CHAIN_METADATA.get()?.get(&chain)
+}
+
+#[derive(Debug, Clone, Copy, IntoStaticStr, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ChainId {
     Algorand,
     Avalanche,
@@ -140,6 +640,28 @@ pub enum ChainId {
     Stellar,
     Tezos,
     ZCash,
+    Liquid,
+    Arbitrum,
+    Optimism,
+    Base,
+    BnbSmartChain,
+    Gnosis,
+    Fantom,
+    Tron,
+    Near,
+    Ton,
+    Aptos,
+    Sui,
+    Filecoin,
+    Kaspa,
+    CosmosHub,
+    Osmosis,
+    Celestia,
+    Moonbeam,
+    Astar,
+    AvalanchePChain,
+    InternetComputer,
+    Mina,
 
     AlgorandTestnet,
     BitcoinCashTestnet,
@@ -160,10 +682,32 @@ pub enum ChainId {
     StellarTestnet,
     TezosTestnet,
     ZCashTestnet,
+    EthereumSepoliaTestnet,
+    EthereumHoleskyTestnet,
+    TronShastaTestnet,
+    SolanaDevnet,
+
+    /// A signet defined by the user, see `--custom-signet`
+    #[strum(disabled)]
+    CustomSignet(CustomSignetId),
+}
+
+impl Serialize for ChainId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.short_name())
+    }
 }
 
 impl ChainId {
     pub fn block_time_secs(self) -> u32 {
+        if let Some(block_time_secs) =
+            chain_metadata(self).and_then(|metadata| metadata.block_time_secs)
+        {
+            return block_time_secs;
+        }
         match self {
             ChainId::Bitcoin
             | ChainId::BitcoinCash
@@ -176,23 +720,165 @@ impl ChainId {
             | ChainId::Stacks
             | ChainId::StacksTestnet => 600,
             ChainId::Monero => 120,
+            ChainId::Mina => 180,
+            ChainId::Liquid | ChainId::Groestlcoin => 60,
+            ChainId::CustomSignet(id) => id.0.block_time_secs,
             ChainId::ZCash | ChainId::ZCashTestnet => 75,
             ChainId::Litecoin | ChainId::LitecoinTestnet | ChainId::Dash | ChainId::DashTestnet => {
                 150
             }
-            ChainId::Ethereum | ChainId::EthereumClassic | ChainId::EthereumGoerliTestnet => 15,
+            ChainId::Ethereum
+            | ChainId::EthereumClassic
+            | ChainId::EthereumGoerliTestnet
+            | ChainId::EthereumSepoliaTestnet
+            | ChainId::EthereumHoleskyTestnet => 15,
             ChainId::Eos | Self::EosTestnet => 1, // actually 0.5, but we use integers, so whatever
+            ChainId::Solana | ChainId::SolanaTestnet | ChainId::SolanaDevnet => 1, // slots of 0.4
+            // actually well below, or around a second, see above
+            ChainId::Arbitrum
+            | ChainId::Fantom
+            | ChainId::Near
+            | ChainId::Aptos
+            | ChainId::Sui
+            | ChainId::Kaspa
+            | ChainId::InternetComputer => 1,
+            ChainId::Optimism | ChainId::Base | ChainId::Osmosis | ChainId::Avalanche => 2,
+            ChainId::HederaHashgraph | ChainId::HederaHashgraphTestnet => 2, // record files closed every ~2s
+            ChainId::BnbSmartChain | ChainId::Tron | ChainId::TronShastaTestnet => 3,
+            ChainId::Ripple | ChainId::RippleTestnet => 4,
+            ChainId::Gnosis | ChainId::Ton => 5,
+            ChainId::CosmosHub
+            | ChainId::Celestia
+            | ChainId::Polkadot
+            | ChainId::Kusama
+            | ChainId::Moonbeam
+            | ChainId::Astar => 6,
             ChainId::Algorand | Self::AlgorandTestnet => 5, // actually 4.5
-            ChainId::Tezos | Self::TezosTestnet => 30,
+            ChainId::Cardano => 20,
+            ChainId::Tezos | Self::TezosTestnet | ChainId::Filecoin => 30,
             // I'm kind of lazy RN, so default to some sanity value for now
             _ => 120,
         }
     }
+    /// What does the `height` reported for this chain count
+    ///
+    /// All sources must report the same kind, so they can be compared.
+    pub fn height_kind(self) -> HeightKind {
+        match self {
+            ChainId::Solana | ChainId::SolanaTestnet | ChainId::SolanaDevnet => HeightKind::Slot,
+            _ => HeightKind::Block,
+        }
+    }
+
+    /// Format of block hashes, used to catch sources reporting garbage
+    pub fn hash_format(self) -> HashFormat {
+        match self {
+            ChainId::Solana
+            | ChainId::SolanaTestnet
+            | ChainId::SolanaDevnet
+            | ChainId::Near
+            | ChainId::Sui => HashFormat::Base58 {
+                min_len: 32,
+                max_len: 44,
+            },
+            ChainId::Tezos | ChainId::TezosTestnet => HashFormat::Base58 {
+                min_len: 51,
+                max_len: 51,
+            },
+            ChainId::Mina => HashFormat::Base58 {
+                min_len: 52,
+                max_len: 52,
+            },
+            // block hashes of these are either not hex, or not reported at all
+            ChainId::Algorand
+            | ChainId::AlgorandTestnet
+            | ChainId::HederaHashgraph
+            | ChainId::HederaHashgraphTestnet
+            | ChainId::Ton
+            | ChainId::Filecoin => HashFormat::Any,
+            _ => HashFormat::Hex { bytes: 32 },
+        }
+    }
+
+    /// Is the chain a Bitcoin-like PoW chain, where the valid tip is the one
+    /// with most cumulative work
+    pub fn has_chain_work(self) -> bool {
+        matches!(
+            self,
+            ChainId::Bitcoin
+                | ChainId::BitcoinCash
+                | ChainId::BitcoinGold
+                | ChainId::BitcoinSV
+                | ChainId::Dash
+                | ChainId::Doge
+                | ChainId::ECash
+                | ChainId::Groestlcoin
+                | ChainId::Litecoin
+                | ChainId::ZCash
+                | ChainId::BitcoinCashTestnet
+                | ChainId::BitcoinSVTestnet
+                | ChainId::BitcoinTestnet
+                | ChainId::BitcoinSignet
+                | ChainId::DashTestnet
+                | ChainId::LitecoinTestnet
+                | ChainId::ZCashTestnet
+        )
+    }
+
+    /// Number of blocks between block subsidy halvings
+    pub fn halving_interval(self) -> Option<u64> {
+        match self {
+            ChainId::Bitcoin
+            | ChainId::BitcoinCash
+            | ChainId::BitcoinGold
+            | ChainId::BitcoinSV
+            | ChainId::ECash
+            | ChainId::BitcoinTestnet
+            | ChainId::BitcoinCashTestnet
+            | ChainId::BitcoinSVTestnet
+            | ChainId::BitcoinSignet
+            | ChainId::CustomSignet(_) => Some(210_000),
+            ChainId::Litecoin | ChainId::LitecoinTestnet => Some(840_000),
+            _ => None,
+        }
+    }
+
+    /// Number of blocks between difficulty adjustments, on chains that
+    /// retarget periodically (as opposed to every block)
+    pub fn difficulty_adjustment_interval(self) -> Option<u64> {
+        match self {
+            ChainId::Bitcoin
+            | ChainId::BitcoinTestnet
+            | ChainId::BitcoinSignet
+            | ChainId::CustomSignet(_)
+            | ChainId::Litecoin
+            | ChainId::LitecoinTestnet => Some(2016),
+            _ => None,
+        }
+    }
+
+    /// What does the `secondaryHeight` reported for this chain count, if any
+    pub fn secondary_height_kind(self) -> Option<HeightKind> {
+        match self {
+            ChainId::Solana | ChainId::SolanaTestnet | ChainId::SolanaDevnet => {
+                Some(HeightKind::Block)
+            }
+            ChainId::Cardano => Some(HeightKind::Slot),
+            ChainId::Stacks | ChainId::StacksTestnet => Some(HeightKind::BurnBlock),
+            _ => None,
+        }
+    }
+
     pub fn full_name(self) -> &'static str {
+        if let Some(full_name) =
+            chain_metadata(self).and_then(|metadata| metadata.full_name.as_deref())
+        {
+            return full_name;
+        }
         match self {
             ChainId::Algorand => "Algorand",
             ChainId::Avalanche => "Avalanche",
-            ChainId::BinanceCoin => "Binance Coin",
+            ChainId::BinanceCoin => "BNB Beacon Chain",
             ChainId::Bitcoin => "Bitcoin",
             ChainId::BitcoinCash => "Bitcoin Cash",
             ChainId::BitcoinGold => "Bitcoin Gold",
@@ -228,7 +914,7 @@ impl ChainId {
             ChainId::CeloTestnet => "Celo Testnet",
             ChainId::DashTestnet => "Dash Testnet",
             ChainId::EosTestnet => "Eos Testnet",
-            ChainId::EthereumGoerliTestnet => "Ethereum Testnet (Goerli) ",
+            ChainId::EthereumGoerliTestnet => "Ethereum Testnet (Goerli)",
             ChainId::HederaHashgraphTestnet => "Hedera Hashgraph Testnet",
             ChainId::LitecoinTestnet => "Litecoin Testnet",
             ChainId::RippleTestnet => "Ripple Testnet",
@@ -239,13 +925,63 @@ impl ChainId {
             ChainId::TezosTestnet => "Tezos Testnet",
             ChainId::ZCashTestnet => "ZCash Testnet",
             ChainId::BitcoinSignet => "Bitcoin Signet",
+            ChainId::Mina => "Mina",
+            ChainId::InternetComputer => "Internet Computer",
+            ChainId::AvalanchePChain => "Avalanche P-Chain",
+            ChainId::SolanaDevnet => "Solana Devnet",
+            ChainId::Astar => "Astar",
+            ChainId::Moonbeam => "Moonbeam",
+            ChainId::Celestia => "Celestia",
+            ChainId::Osmosis => "Osmosis",
+            ChainId::CosmosHub => "Cosmos Hub",
+            ChainId::Kaspa => "Kaspa",
+            ChainId::Filecoin => "Filecoin",
+            ChainId::Sui => "Sui",
+            ChainId::Aptos => "Aptos",
+            ChainId::Ton => "TON",
+            ChainId::Near => "NEAR Protocol",
+            ChainId::TronShastaTestnet => "Tron Testnet (Shasta)",
+            ChainId::Tron => "Tron",
+            ChainId::Fantom => "Fantom",
+            ChainId::Gnosis => "Gnosis Chain",
+            ChainId::BnbSmartChain => "BNB Smart Chain",
+            ChainId::Base => "Base",
+            ChainId::Optimism => "Optimism",
+            ChainId::Arbitrum => "Arbitrum One",
+            ChainId::EthereumHoleskyTestnet => "Ethereum Testnet (Holesky)",
+            ChainId::EthereumSepoliaTestnet => "Ethereum Testnet (Sepolia)",
+            ChainId::Liquid => "Liquid",
+            ChainId::CustomSignet(id) => &id.0.full_name,
         }
     }
     pub fn short_name(self) -> &'static str {
-        self.into()
+        if let Some(short_name) =
+            chain_metadata(self).and_then(|metadata| metadata.short_name.as_deref())
+        {
+            return short_name;
+        }
+        match self {
+            ChainId::CustomSignet(id) => &id.0.short_name,
+            _ => self.into(),
+        }
     }
 
+    /// Chain of an overridden, custom signet or built-in `ticker`
     pub fn from_ticker(ticker: &str) -> Option<Self> {
+        let overridden = CHAIN_METADATA.get().and_then(|all| {
+            all.iter()
+                .find(|(_, metadata)| metadata.ticker.as_deref() == Some(ticker))
+        });
+        if let Some((chain, _)) = overridden {
+            return Some(*chain);
+        }
+        let custom_signet = CUSTOM_SIGNETS
+            .get()
+            .and_then(|signets| signets.iter().find(|signet| signet.0.ticker == ticker));
+        if let Some(signet) = custom_signet {
+            return Some(ChainId::CustomSignet(*signet));
+        }
+
         Some(match ticker {
             "algo" => ChainId::Algorand,
             "avax" => ChainId::Avalanche,
@@ -285,6 +1021,8 @@ impl ChainId {
             "celo-testnet" => ChainId::CeloTestnet,
             "dash-testnet" => ChainId::DashTestnet,
             "eos-testnet" => ChainId::EosTestnet,
+            "eth-goerli" => ChainId::EthereumGoerliTestnet,
+            // the ticker of Goerli, from before other Ethereum testnets were monitored
             "eth-testnet" => ChainId::EthereumGoerliTestnet,
             "thbar" => ChainId::HederaHashgraphTestnet,
             "ltc-testnet" => ChainId::LitecoinTestnet,
@@ -296,11 +1034,40 @@ impl ChainId {
             "xtz-testnet" => ChainId::TezosTestnet,
             "zec-testnet" => ChainId::ZCashTestnet,
             "btc-signet" => ChainId::BitcoinSignet,
+            "mina" => ChainId::Mina,
+            "icp" => ChainId::InternetComputer,
+            "avax-p" => ChainId::AvalanchePChain,
+            "sol-devnet" => ChainId::SolanaDevnet,
+            "astr" => ChainId::Astar,
+            "glmr" => ChainId::Moonbeam,
+            "tia" => ChainId::Celestia,
+            "osmo" => ChainId::Osmosis,
+            "atom" => ChainId::CosmosHub,
+            "kas" => ChainId::Kaspa,
+            "fil" => ChainId::Filecoin,
+            "sui" => ChainId::Sui,
+            "apt" => ChainId::Aptos,
+            "ton" => ChainId::Ton,
+            "near" => ChainId::Near,
+            "trx-shasta" => ChainId::TronShastaTestnet,
+            "trx" => ChainId::Tron,
+            "ftm" => ChainId::Fantom,
+            "gno" => ChainId::Gnosis,
+            "bsc" => ChainId::BnbSmartChain,
+            "base" => ChainId::Base,
+            "op" => ChainId::Optimism,
+            "arb" => ChainId::Arbitrum,
+            "eth-holesky" => ChainId::EthereumHoleskyTestnet,
+            "eth-sepolia" => ChainId::EthereumSepoliaTestnet,
+            "lbtc" => ChainId::Liquid,
             _ => return None,
         })
     }
 
     pub fn ticker(self) -> &'static str {
+        if let Some(ticker) = chain_metadata(self).and_then(|metadata| metadata.ticker.as_deref()) {
+            return ticker;
+        }
         match self {
             ChainId::Algorand => "algo",
             ChainId::Avalanche => "avax",
@@ -340,7 +1107,7 @@ impl ChainId {
             ChainId::CeloTestnet => "celo-testnet",
             ChainId::DashTestnet => "dash-testnet",
             ChainId::EosTestnet => "eos-testnet",
-            ChainId::EthereumGoerliTestnet => "eth-testnet",
+            ChainId::EthereumGoerliTestnet => "eth-goerli",
             ChainId::HederaHashgraphTestnet => "thbar",
             ChainId::LitecoinTestnet => "ltc-testnet",
             ChainId::RippleTestnet => "xrp-testnet",
@@ -351,6 +1118,33 @@ impl ChainId {
             ChainId::TezosTestnet => "xtz-testnet",
             ChainId::ZCashTestnet => "zec-testnet",
             ChainId::BitcoinSignet => "btc-signet",
+            ChainId::Mina => "mina",
+            ChainId::InternetComputer => "icp",
+            ChainId::AvalanchePChain => "avax-p",
+            ChainId::SolanaDevnet => "sol-devnet",
+            ChainId::Astar => "astr",
+            ChainId::Moonbeam => "glmr",
+            ChainId::Celestia => "tia",
+            ChainId::Osmosis => "osmo",
+            ChainId::CosmosHub => "atom",
+            ChainId::Kaspa => "kas",
+            ChainId::Filecoin => "fil",
+            ChainId::Sui => "sui",
+            ChainId::Aptos => "apt",
+            ChainId::Ton => "ton",
+            ChainId::Near => "near",
+            ChainId::TronShastaTestnet => "trx-shasta",
+            ChainId::Tron => "trx",
+            ChainId::Fantom => "ftm",
+            ChainId::Gnosis => "gno",
+            ChainId::BnbSmartChain => "bsc",
+            ChainId::Base => "base",
+            ChainId::Optimism => "op",
+            ChainId::Arbitrum => "arb",
+            ChainId::EthereumHoleskyTestnet => "eth-holesky",
+            ChainId::EthereumSepoliaTestnet => "eth-sepolia",
+            ChainId::Liquid => "lbtc",
+            ChainId::CustomSignet(id) => &id.0.ticker,
         }
     }
 
@@ -405,26 +1199,381 @@ impl ChainId {
             ChainId::TezosTestnet => NetworkType::Testnet,
             ChainId::ZCashTestnet => NetworkType::Testnet,
             ChainId::BitcoinSignet => NetworkType::Signet,
+            ChainId::Mina => NetworkType::Mainnet,
+            ChainId::InternetComputer => NetworkType::Mainnet,
+            ChainId::AvalanchePChain => NetworkType::Mainnet,
+            ChainId::SolanaDevnet => NetworkType::Testnet,
+            ChainId::Astar => NetworkType::Mainnet,
+            ChainId::Moonbeam => NetworkType::Mainnet,
+            ChainId::Celestia => NetworkType::Mainnet,
+            ChainId::Osmosis => NetworkType::Mainnet,
+            ChainId::CosmosHub => NetworkType::Mainnet,
+            ChainId::Kaspa => NetworkType::Mainnet,
+            ChainId::Filecoin => NetworkType::Mainnet,
+            ChainId::Sui => NetworkType::Mainnet,
+            ChainId::Aptos => NetworkType::Mainnet,
+            ChainId::Ton => NetworkType::Mainnet,
+            ChainId::Near => NetworkType::Mainnet,
+            ChainId::TronShastaTestnet => NetworkType::Testnet,
+            ChainId::Tron => NetworkType::Mainnet,
+            ChainId::Fantom => NetworkType::Mainnet,
+            ChainId::Gnosis => NetworkType::Mainnet,
+            ChainId::BnbSmartChain => NetworkType::Mainnet,
+            ChainId::Base => NetworkType::Mainnet,
+            ChainId::Optimism => NetworkType::Mainnet,
+            ChainId::Arbitrum => NetworkType::Mainnet,
+            ChainId::EthereumHoleskyTestnet => NetworkType::Testnet,
+            ChainId::EthereumSepoliaTestnet => NetworkType::Testnet,
+            ChainId::Liquid => NetworkType::Mainnet,
+            ChainId::CustomSignet(_) => NetworkType::Signet,
         }
     }
 }
 
-pub(crate) fn get_source(opts: &Opts) -> Result<Vec<Box<dyn Source>>> {
-    let mut sources = vec![
-        Box::new(bitgo::BitGo::new()?) as Box<dyn Source>,
-        Box::new(bitgov1::BitGoV1::new()?),
-        Box::new(blockchain::Blockchain::new()?),
-        Box::new(blockchair::Blockchair::new()?),
-        Box::new(blockcypher::BlockCypher::new()?),
-        Box::new(mempoolspace::MempoolSpace::new()?),
-        Box::new(cmc::CoinMarketCap::new()?),
-        Box::new(other::Other::new()?),
+/// Base URLs of built-in sources, as overridden with `--source-url`
+struct BaseUrls(HashMap<SourceId, String>);
+
+#[cfg_attr(not(feature = "all-sources"), allow(dead_code))]
+impl BaseUrls {
+    /// Sources whose base URL can be overridden
+    ///
+    /// Not those with an RPC endpoint per chain (e.g. `EvmRpc`), or their own
+    /// options (e.g. `--stacks-api`).
+    const SUPPORTED: &'static [SourceId] = &[
+        SourceId::AptosRpc,
+        SourceId::BitGo,
+        SourceId::BitGoV1,
+        SourceId::Blockchain,
+        SourceId::Blockchair,
+        SourceId::BlockCypher,
+        SourceId::Blockfrost,
+        SourceId::Blockstream,
+        SourceId::CMC,
+        SourceId::CryptoId,
+        SourceId::Glif,
+        SourceId::KaspaApi,
+        SourceId::Koios,
+        SourceId::MempoolSpace,
+        SourceId::NearRpc,
+        SourceId::Subscan,
+        SourceId::SuiRpc,
+        SourceId::TonCenter,
+        SourceId::TronGrid,
     ];
 
-    for mirror in &opts.mirror {
-        sources.push(Box::new(chainmonitor::ChainMonitor::new(mirror.clone())?) as Box<dyn Source>)
+    /// Parse `<source>=<base-url>` entries
+    fn new(entries: &[String]) -> Result<Self> {
+        entries
+            .iter()
+            .map(|entry| {
+                let (source, url) = entry
+                    .split_once('=')
+                    .ok_or_else(|| format_err!("expected `<source>=<base-url>`: {entry}"))?;
+                let source = SourceId::from_str(source)
+                    .map_err(|_| format_err!("unknown source: {source}"))?;
+                if !Self::SUPPORTED.contains(&source) {
+                    bail!("the base URL of {source:?} can't be changed");
+                }
+                Ok((source, url.trim_end_matches('/').to_owned()))
+            })
+            .collect::<Result<_>>()
+            .map(Self)
+    }
+
+    /// Custom base URL of `source`, if any
+    fn get(&self, source: SourceId) -> Option<String> {
+        self.0.get(&source).cloned()
     }
-    Ok(sources)
+}
+
+/// API keys of sources, as given with `--api-key`
+struct ApiKeys(HashMap<SourceId, String>);
+
+#[cfg_attr(not(feature = "all-sources"), allow(dead_code))]
+impl ApiKeys {
+    /// Sources that use an API key
+    const SUPPORTED: &'static [SourceId] = &[
+        SourceId::Blockchair,
+        SourceId::Blockfrost,
+        SourceId::CryptoId,
+        SourceId::Subscan,
+        SourceId::TonCenter,
+        SourceId::TronGrid,
+    ];
+
+    /// Parse `<source>=<key>` entries, loading `env:` and `file:` secrets
+    fn new(entries: &[String]) -> Result<Self> {
+        entries
+            .iter()
+            .map(|entry| {
+                let (source, key) = entry
+                    .split_once('=')
+                    .ok_or_else(|| format_err!("expected `<source>=<key>`: {}", entry))?;
+                let source = SourceId::from_str(source)
+                    .map_err(|_| format_err!("unknown source: {source}"))?;
+                if !Self::SUPPORTED.contains(&source) {
+                    bail!("{source:?} doesn't use an API key");
+                }
+                Ok((source, crate::util::load_secret(key, "API key")?))
+            })
+            .collect::<Result<_>>()
+            .map(Self)
+    }
+
+    fn get(&self, source: SourceId) -> Option<String> {
+        self.0.get(&source).cloned()
+    }
+}
+
+/// Why a source is skipped when its API key is missing
+#[cfg_attr(not(feature = "all-sources"), allow(dead_code))]
+const MISSING_API_KEY: &str = "no API key configured";
+
+/// Built-in sources left out, with the reason why
+pub(crate) type SkippedSources = Vec<(SourceId, &'static str)>;
+
+// every push is conditional on a feature
+#[allow(clippy::vec_init_then_push, unused_mut, unused_variables)]
+pub(crate) fn get_source(opts: &Opts) -> Result<(Vec<Box<dyn Source>>, SkippedSources)> {
+    let mut sources: Vec<Box<dyn Source>> = vec![];
+    let mut skipped = SkippedSources::new();
+
+    if opts.simulate {
+        #[cfg(feature = "source-simulated")]
+        {
+            tracing::info!("Simulating; not using any real sources");
+            sources.push(Box::new(simulated::Simulated::new()));
+            return Ok((sources, skipped));
+        }
+        #[cfg(not(feature = "source-simulated"))]
+        bail!("`--simulate` requires the `source-simulated` feature");
+    }
+
+    let base_urls = BaseUrls::new(&opts.source_urls)?;
+    if let Some(height_field) = opts
+        .height_fields
+        .iter()
+        .find(|height_field| height_field.source != SourceId::Blockchair)
+    {
+        bail!(
+            "{:?} doesn't support overriding its height field",
+            height_field.source
+        );
+    }
+    let api_keys = ApiKeys::new(&opts.api_keys)?;
+    let clients = http::Clients::new(opts)?;
+
+    #[cfg(feature = "source-aptos")]
+    sources.push(Box::new(Poller::new(aptos::AptosRpc::new(
+        clients.get(SourceId::AptosRpc)?,
+        base_urls.get(SourceId::AptosRpc),
+    )?)));
+    #[cfg(feature = "source-bitgo")]
+    sources.push(Box::new(Poller::new(bitgo::BitGo::new(
+        clients.get(SourceId::BitGo)?,
+        base_urls.get(SourceId::BitGo),
+    )?)));
+    #[cfg(feature = "source-bitgov1")]
+    sources.push(Box::new(Poller::new(bitgov1::BitGoV1::new(
+        clients.get(SourceId::BitGoV1)?,
+        base_urls.get(SourceId::BitGoV1),
+    )?)));
+    #[cfg(feature = "source-blockbook")]
+    sources.push(Box::new(Poller::new(blockbook::Blockbook::new(
+        clients.get(SourceId::Blockbook)?,
+        &opts.blockbook,
+    )?)));
+    #[cfg(feature = "source-blockchain")]
+    sources.push(Box::new(Poller::new(blockchain::Blockchain::new(
+        clients.get(SourceId::Blockchain)?,
+        base_urls.get(SourceId::Blockchain),
+    )?)));
+    #[cfg(feature = "source-blockchair")]
+    sources.push(Box::new(blockchair::Blockchair::new(
+        clients.get(SourceId::Blockchair)?,
+        base_urls.get(SourceId::Blockchair),
+        api_keys.get(SourceId::Blockchair),
+        opts.height_fields
+            .iter()
+            .filter(|height_field| height_field.source == SourceId::Blockchair)
+            .map(|height_field| (height_field.chain, height_field.field.clone()))
+            .collect(),
+    )?));
+    #[cfg(feature = "source-blockcypher")]
+    sources.push(Box::new(Poller::new(blockcypher::BlockCypher::new(
+        clients.get(SourceId::BlockCypher)?,
+        base_urls.get(SourceId::BlockCypher),
+    )?)));
+    // Blockfrost doesn't serve anonymous requests
+    #[cfg(feature = "source-blockfrost")]
+    match api_keys.get(SourceId::Blockfrost) {
+        Some(api_key) => sources.push(Box::new(Poller::new(blockfrost::Blockfrost::new(
+            clients.get(SourceId::Blockfrost)?,
+            base_urls.get(SourceId::Blockfrost),
+            api_key,
+        )?))),
+        None => skipped.push((SourceId::Blockfrost, MISSING_API_KEY)),
+    }
+    #[cfg(feature = "source-blockscout")]
+    sources.push(Box::new(Poller::new(blockscout::Blockscout::new(
+        clients.get(SourceId::Blockscout)?,
+        &opts.blockscout,
+    )?)));
+    #[cfg(feature = "source-blockstream")]
+    sources.push(Box::new(Poller::new(blockstream::Blockstream::new(
+        clients.get(SourceId::Blockstream)?,
+        base_urls.get(SourceId::Blockstream),
+    )?)));
+    #[cfg(feature = "source-glif")]
+    sources.push(Box::new(Poller::new(glif::Glif::new(
+        clients.get(SourceId::Glif)?,
+        base_urls.get(SourceId::Glif),
+    )?)));
+    #[cfg(feature = "source-kaspa")]
+    sources.push(Box::new(Poller::new(kaspa::KaspaApi::new(
+        clients.get(SourceId::KaspaApi)?,
+        base_urls.get(SourceId::KaspaApi),
+    )?)));
+    #[cfg(feature = "source-koios")]
+    sources.push(Box::new(Poller::new(koios::Koios::new(
+        clients.get(SourceId::Koios)?,
+        base_urls.get(SourceId::Koios),
+    )?)));
+    #[cfg(feature = "source-mempoolspace")]
+    sources.push(Box::new(Poller::new(mempoolspace::MempoolSpace::new(
+        clients.get(SourceId::MempoolSpace)?,
+        base_urls.get(SourceId::MempoolSpace),
+        opts.track_mempool,
+        opts.track_difficulty,
+    )?)));
+    #[cfg(feature = "source-cmc")]
+    sources.push(Box::new(Poller::new(cmc::CoinMarketCap::new(
+        clients.get(SourceId::CMC)?,
+        base_urls.get(SourceId::CMC),
+    )?)));
+    #[cfg(feature = "source-cryptoid")]
+    sources.push(Box::new(Poller::new(cryptoid::CryptoId::new(
+        clients.get(SourceId::CryptoId)?,
+        base_urls.get(SourceId::CryptoId),
+        api_keys.get(SourceId::CryptoId),
+    )?)));
+    #[cfg(feature = "source-evm")]
+    sources.push(Box::new(Poller::new(evm::EvmRpc::new(
+        clients.get(SourceId::EvmRpc)?,
+    )?)));
+    #[cfg(feature = "source-near")]
+    sources.push(Box::new(Poller::new(near::NearRpc::new(
+        clients.get(SourceId::NearRpc)?,
+        base_urls.get(SourceId::NearRpc),
+    )?)));
+    #[cfg(feature = "source-other")]
+    sources.push(Box::new(Poller::new(other::Other::new(
+        clients.get(SourceId::Other)?,
+    )?)));
+    #[cfg(feature = "source-solana")]
+    sources.push(Box::new(Poller::new(solana::SolanaRpc::new(
+        clients.get(SourceId::SolanaRpc)?,
+        opts.solana_commitment,
+    )?)));
+    #[cfg(feature = "source-stacks")]
+    sources.push(Box::new(Poller::new(stacks::StacksApi::new(
+        clients.get(SourceId::StacksApi)?,
+        &opts.stacks_api,
+    )?)));
+    // Subscan doesn't serve anonymous requests
+    #[cfg(feature = "source-subscan")]
+    match api_keys.get(SourceId::Subscan) {
+        Some(api_key) => sources.push(Box::new(Poller::new(subscan::Subscan::new(
+            clients.get(SourceId::Subscan)?,
+            base_urls.get(SourceId::Subscan),
+            api_key,
+        )?))),
+        None => skipped.push((SourceId::Subscan, MISSING_API_KEY)),
+    }
+    #[cfg(feature = "source-substrate")]
+    sources.push(Box::new(Poller::new(substrate::Substrate::new(
+        clients.get(SourceId::Substrate)?,
+    )?)));
+    #[cfg(feature = "source-sui")]
+    sources.push(Box::new(Poller::new(sui::SuiRpc::new(
+        clients.get(SourceId::SuiRpc)?,
+        base_urls.get(SourceId::SuiRpc),
+    )?)));
+    #[cfg(feature = "source-tendermint")]
+    sources.push(Box::new(Poller::new(tendermint::Tendermint::new(
+        clients.get(SourceId::Tendermint)?,
+        &opts.tendermint_rpc,
+    )?)));
+    #[cfg(feature = "source-tezos")]
+    sources.push(Box::new(Poller::new(tezos::TezosNode::new(
+        clients.get(SourceId::TezosNode)?,
+        &opts.tezos_nodes,
+    )?)));
+    #[cfg(feature = "source-toncenter")]
+    sources.push(Box::new(Poller::new(toncenter::TonCenter::new(
+        clients.get(SourceId::TonCenter)?,
+        base_urls.get(SourceId::TonCenter),
+        api_keys.get(SourceId::TonCenter),
+    )?)));
+    #[cfg(feature = "source-trongrid")]
+    sources.push(Box::new(Poller::new(trongrid::TronGrid::new(
+        clients.get(SourceId::TronGrid)?,
+        base_urls.get(SourceId::TronGrid),
+        api_keys.get(SourceId::TronGrid),
+    )?)));
+    #[cfg(feature = "source-xrpl")]
+    sources.push(Box::new(xrpl::XrplCluster::new()));
+
+    if !opts.bitcoind_rpc.is_empty() {
+        #[cfg(feature = "source-bitcoind")]
+        sources.push(Box::new(Poller::new(bitcoind::Bitcoind::new(
+            clients.get(SourceId::Bitcoind)?,
+            &opts.bitcoind_rpc,
+            opts.track_mempool,
+            opts.track_difficulty,
+        )?)));
+        #[cfg(not(feature = "source-bitcoind"))]
+        bail!("`--bitcoind-rpc` requires the `source-bitcoind` feature");
+    }
+
+    if !opts.custom_signets.is_empty() {
+        #[cfg(feature = "source-custom-signet")]
+        sources.push(Box::new(Poller::new(signet::CustomSignets::new(
+            clients.get(SourceId::CustomEsplora)?,
+            &opts.custom_signets,
+        )?)));
+        #[cfg(not(feature = "source-custom-signet"))]
+        bail!("`--custom-signet` requires the `source-custom-signet` feature");
+    }
+
+    if !opts.evm_nodes.is_empty() {
+        #[cfg(feature = "source-newheads")]
+        sources.push(Box::new(newheads::NewHeads::new(
+            clients.get(SourceId::EvmNode)?,
+            &opts.evm_nodes,
+        )?));
+        #[cfg(not(feature = "source-newheads"))]
+        bail!("`--evm-node` requires the `source-newheads` feature");
+    }
+
+    #[cfg(feature = "source-chainmonitor")]
+    for (i, mirror) in opts.mirror.iter().enumerate() {
+        sources.push(Box::new(chainmonitor::ChainMonitor::new(
+            clients.get(SourceId::ChainMonitor)?,
+            mirror.clone(),
+            i + 1,
+        )?));
+    }
+    #[cfg(not(feature = "source-chainmonitor"))]
+    if !opts.mirror.is_empty() {
+        bail!("`--mirror` requires the `source-chainmonitor` feature");
+    }
+
+    for (source, reason) in &skipped {
+        tracing::info!("Skipping {source:?}: {reason}");
+    }
+
+    Ok((sources, skipped))
 }
 
 #[async_trait]
@@ -443,71 +1592,11 @@ impl Source for Vec<Box<dyn Source>> {
         })
     }
 
-    async fn check_updates(&self, recorder: &dyn ChainUpdateRecorder) {
-        join_all(self.iter().map(|source| source.check_updates(recorder))).await;
-    }
-}
-
-struct UpdateRateLimiter {
-    source: SourceId,
-    last_checked: Mutex<HashMap<ChainId, u64>>,
-    enable_periodic_check: bool,
-}
-
-impl UpdateRateLimiter {
-    fn new(source: SourceId) -> Self {
-        Self {
-            source,
-            last_checked: Mutex::new(HashMap::default()),
-            enable_periodic_check: true,
-        }
-    }
-
-    pub fn disable_periodic_check(self) -> Self {
-        Self {
-            enable_periodic_check: false,
-            ..self
-        }
+    fn url_for_chain(&self, source: SourceId, chain: ChainId) -> Option<String> {
+        self.iter().find_map(|s| s.url_for_chain(source, chain))
     }
 
-    async fn should_check(
-        &self,
-        chain: ChainId,
-        update_recorder: &dyn ChainUpdateRecorder,
-    ) -> bool {
-        let now = super::get_now_ts();
-        let mut last_checked = self.last_checked.lock().await;
-
-        let since_last_check_secs = now - *last_checked.entry(chain).or_insert(0);
-        let recheck_threashold_secs = cmp::max(u64::from(chain.block_time_secs()) / 2, 45);
-        let how_far_behind = update_recorder.how_far_behind(self.source, chain).await;
-
-        let is_behind = if how_far_behind > 0 {
-            debug!(
-                "{:?} {:?} is {} behind; updating",
-                self.source, chain, how_far_behind
-            );
-            true
-        } else {
-            false
-        };
-
-        let is_stale =
-            if (since_last_check_secs > recheck_threashold_secs) && self.enable_periodic_check {
-                debug!(
-                    "{:?} {:?} is {}s since last updated; updating",
-                    self.source, chain, since_last_check_secs
-                );
-                true
-            } else {
-                false
-            };
-
-        if is_behind || is_stale {
-            last_checked.insert(chain, now);
-            true
-        } else {
-            false
-        }
+    async fn check_updates(&self, recorder: &dyn ChainUpdateRecorder) {
+        join_all(self.iter().map(|source| source.check_updates(recorder))).await;
     }
 }
@@ -0,0 +1,64 @@
+use super::{pacing::SendPaced, ChainId, ChainId::*, ChainStateFetcher, SourceId};
+use crate::{BlockHash, ChainHeight, ChainState};
+use anyhow::Result;
+use axum::async_trait;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct LedgerInfo {
+    #[serde(deserialize_with = "crate::util::deserialize_number_from_string")]
+    block_height: u64,
+    /// In microseconds
+    #[serde(deserialize_with = "crate::util::deserialize_number_from_string")]
+    ledger_timestamp: u64,
+}
+
+const DEFAULT_BASE_URL: &str = "https://fullnode.mainnet.aptoslabs.com";
+
+/// Public Aptos fullnode REST API
+pub struct AptosRpc {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl AptosRpc {
+    pub fn new(client: reqwest::Client, base_url: Option<String>) -> Result<Self> {
+        Ok(Self {
+            client,
+            base_url: base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_owned()),
+        })
+    }
+}
+
+#[async_trait]
+impl ChainStateFetcher for AptosRpc {
+    const ID: SourceId = SourceId::AptosRpc;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &[Aptos]
+    }
+
+    fn url_for_chain(&self, _chain: ChainId) -> Option<String> {
+        Some("https://explorer.aptoslabs.com/block/{height}?network=mainnet".to_owned())
+    }
+
+    async fn fetch(&self, _chain: ChainId) -> Result<ChainState> {
+        let info = self
+            .client
+            .get(format!("{}/v1", self.base_url))
+            .send_paced()
+            .await?
+            .error_for_status()?
+            .json::<LedgerInfo>()
+            .await?;
+
+        let height = ChainHeight::new(info.block_height);
+        Ok(ChainState {
+            // ledger info doesn't include the hash of the block
+            hash: BlockHash::from_height(height),
+            height,
+            block_time: Some(info.ledger_timestamp / 1_000_000),
+            ..Default::default()
+        })
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+
+use super::{pacing::SendPaced, ChainId, ChainStateFetcher, PollingPolicy, SourceError, SourceId};
+use crate::{ChainHeight, ChainState, DifficultyStats, MempoolStats};
+use anyhow::{bail, format_err, Result};
+use axum::async_trait;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::json;
+
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+struct BlockchainInfo {
+    blocks: u64,
+    bestblockhash: String,
+    chainwork: Option<String>,
+    time: Option<u64>,
+    /// Not reported by zebrad
+    difficulty: Option<f64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+struct MempoolInfo {
+    size: u64,
+    bytes: u64,
+    /// In BTC/kvB
+    mempoolminfee: Option<f64>,
+}
+
+/// A bitcoind-compatible node JSON-RPC endpoint
+struct Node {
+    url: reqwest::Url,
+    user: Option<String>,
+    password: Option<String>,
+}
+
+impl Node {
+    fn from_url(url: &str) -> Result<Self> {
+        let mut url = reqwest::Url::parse(url)?;
+        let user = Some(url.username().to_owned()).filter(|user| !user.is_empty());
+        let password = url.password().map(ToOwned::to_owned);
+        url.set_username("")
+            .map_err(|_| format_err!("invalid url: {url}"))?;
+        url.set_password(None)
+            .map_err(|_| format_err!("invalid url: {url}"))?;
+
+        Ok(Self {
+            url,
+            user,
+            password,
+        })
+    }
+}
+
+/// Self-hosted bitcoind (and alike, e.g. zcashd or zebrad) nodes
+pub struct Bitcoind {
+    client: reqwest::Client,
+    chains: Vec<ChainId>,
+    nodes: HashMap<ChainId, Node>,
+    track_mempool: bool,
+    track_difficulty: bool,
+}
+
+impl Bitcoind {
+    /// Create from `<ticker>=<url>` entries
+    pub fn new(
+        client: reqwest::Client,
+        nodes: &[String],
+        track_mempool: bool,
+        track_difficulty: bool,
+    ) -> Result<Self> {
+        let nodes: HashMap<_, _> = nodes
+            .iter()
+            .map(|entry| {
+                let (ticker, url) = entry
+                    .split_once('=')
+                    .ok_or_else(|| format_err!("expected `<ticker>=<url>`: {entry}"))?;
+                let chain = ChainId::from_ticker(ticker)
+                    .ok_or_else(|| format_err!("unknown ticker: {ticker}"))?;
+                Ok((chain, Node::from_url(url)?))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            client,
+            chains: nodes.keys().copied().collect(),
+            nodes,
+            track_mempool,
+            track_difficulty,
+        })
+    }
+
+    async fn call<T: DeserializeOwned>(&self, node: &Node, method: &str) -> Result<T> {
+        let mut req = self.client.post(node.url.clone()).json(&json!({
+            "jsonrpc": "1.0",
+            "id": "chain-monitor",
+            "method": method,
+            "params": [],
+        }));
+
+        if let Some(user) = node.user.as_ref() {
+            req = req.basic_auth(user, node.password.as_ref());
+        }
+
+        let resp = req
+            .send_paced()
+            .await?
+            .error_for_status()?
+            .json::<RpcResponse<T>>()
+            .await?;
+
+        if let Some(error) = resp.error {
+            bail!("rpc error {}: {}", error.code, error.message);
+        }
+
+        Ok(resp.result.ok_or_else(|| {
+            SourceError::schema_change(format!("no result returned for `{method}`"))
+        })?)
+    }
+
+    fn node(&self, chain: ChainId) -> &Node {
+        &self.nodes[&chain]
+    }
+}
+
+#[async_trait]
+impl ChainStateFetcher for Bitcoind {
+    const ID: SourceId = SourceId::Bitcoind;
+    // our own nodes, no need to go easy on them
+    const POLLING: PollingPolicy = PollingPolicy::Always;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &self.chains
+    }
+
+    async fn fetch(&self, chain: ChainId) -> Result<ChainState> {
+        let info: BlockchainInfo = self.call(self.node(chain), "getblockchaininfo").await?;
+
+        Ok(ChainState {
+            hash: info.bestblockhash.into(),
+            height: ChainHeight::new(info.blocks),
+            block_time: info.time,
+            chain_work: info.chainwork,
+            ..Default::default()
+        })
+    }
+
+    async fn fetch_mempool_stats(&self, chain: ChainId) -> Option<Result<MempoolStats>> {
+        if !self.track_mempool {
+            return None;
+        }
+
+        Some(
+            self.call::<MempoolInfo>(self.node(chain), "getmempoolinfo")
+                .await
+                .map(|info| MempoolStats {
+                    tx_count: info.size,
+                    vsize: info.bytes,
+                    // BTC/kvB -> sat/vB
+                    min_fee_rate: info.mempoolminfee.map(|fee| fee * 100_000.),
+                }),
+        )
+    }
+
+    async fn fetch_difficulty_stats(&self, chain: ChainId) -> Option<Result<DifficultyStats>> {
+        if !self.track_difficulty {
+            return None;
+        }
+
+        Some(
+            self.call::<BlockchainInfo>(self.node(chain), "getblockchaininfo")
+                .await
+                .and_then(|info| {
+                    let difficulty = info
+                        .difficulty
+                        .ok_or_else(|| format_err!("node doesn't report difficulty"))?;
+                    let remaining_blocks = chain
+                        .difficulty_adjustment_interval()
+                        .map(|interval| interval - info.blocks % interval);
+
+                    Ok(DifficultyStats {
+                        difficulty,
+                        next_adjustment_height: remaining_blocks.map(|remaining_blocks| {
+                            ChainHeight::new(info.blocks + remaining_blocks)
+                        }),
+                        remaining_blocks,
+                        expected_change_pct: None,
+                    })
+                }),
+        )
+    }
+}
@@ -1,22 +1,24 @@
 use std::fmt::Display;
 
-use super::{ChainId, ChainId::*, SourceId};
-use crate::{ChainState, ChainStateUpdate, ChainUpdateRecorder};
+use super::{pacing::SendPaced, ChainId, ChainId::*, ChainStateFetcher, SourceId};
+use crate::{ChainHeight, ChainState};
 use anyhow::Result;
 use axum::async_trait;
-use rand::{seq::SliceRandom, thread_rng};
 use serde::Deserialize;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 
 #[derive(Deserialize)]
 struct BlockLatestBody {
     id: String,
     height: u64,
+    /// Timestamp of the block, in RFC 3339
+    date: Option<String>,
 }
 
 pub(crate) async fn get_chain_state(
     client: &reqwest::Client,
     api: BitgoAPI,
-    host: &str,
+    base_url: &str,
     chain_api_symbol: &str,
 ) -> Result<ChainState> {
     let path = match api {
@@ -24,43 +26,25 @@ pub(crate) async fn get_chain_state(
         BitgoAPI::V2 => format!("/api/{api}/{chain_api_symbol}/public/block/latest"),
     };
     let resp = client
-        .get(format!("https://{host}{path}"))
-        .send()
+        .get(format!("{base_url}{path}"))
+        .send_paced()
         .await?
         .error_for_status()?
         .json::<BlockLatestBody>()
         .await?;
 
     Ok(ChainState {
-        hash: resp.id,
-        height: resp.height,
+        hash: resp.id.into(),
+        height: ChainHeight::new(resp.height),
+        // tells a lagging BitGo apart from a slow chain, so an odd one is no reason to fail
+        block_time: resp
+            .date
+            .and_then(|date| OffsetDateTime::parse(&date, &Rfc3339).ok())
+            .and_then(|date| u64::try_from(date.unix_timestamp()).ok()),
+        ..Default::default()
     })
 }
 
-pub async fn get_updates(
-    client: &reqwest::Client,
-    chain: ChainId,
-    api: BitgoAPI,
-    host: &str,
-    chain_api_symbol: &str,
-) -> Option<ChainStateUpdate> {
-    match get_chain_state(client, api, host, chain_api_symbol).await {
-        Ok(state) => Some(ChainStateUpdate {
-            source: match api {
-                BitgoAPI::V1 => SourceId::BitGoV1,
-                BitgoAPI::V2 => SourceId::BitGo,
-            },
-            chain: chain.into(),
-            state,
-        }),
-        Err(e) => {
-            let chain_name: &str = chain.into();
-            tracing::warn!("Couldn't update BitGo {chain_name}: {e}");
-            None
-        }
-    }
-}
-
 #[derive(Copy, Clone, Debug)]
 pub enum BitgoAPI {
     V1,
@@ -78,17 +62,13 @@ impl Display for BitgoAPI {
 }
 pub struct BitGo {
     client: reqwest::Client,
-    rate_limiter: super::UpdateRateLimiter,
+    /// Used for all the chains instead of their own host, if set
+    base_url: Option<String>,
 }
 
 impl BitGo {
-    pub fn new() -> Result<Self> {
-        Ok(Self {
-            client: reqwest::Client::builder()
-                .user_agent("curl/7.79.1")
-                .build()?,
-            rate_limiter: super::UpdateRateLimiter::new(<Self as super::StaticSource>::ID),
-        })
+    pub fn new(client: reqwest::Client, base_url: Option<String>) -> Result<Self> {
+        Ok(Self { client, base_url })
     }
 
     fn host_for_chain(chain: ChainId) -> &'static str {
@@ -100,6 +80,7 @@ impl BitGo {
             | BitcoinCashTestnet
             | LitecoinTestnet
             | EthereumGoerliTestnet
+            | EthereumHoleskyTestnet
             | DashTestnet
             | BitcoinSVTestnet
             | SolanaTestnet
@@ -114,8 +95,41 @@ impl BitGo {
             | StacksTestnet
             | HederaHashgraphTestnet
             | TezosTestnet => "test.bitgo.com",
-            Doge | Cardano | Monero | Kusama | ECash | Mixin | Groestlcoin | BinanceCoin
-            | BitcoinSignet => {
+            Doge
+            | Cardano
+            | Monero
+            | Kusama
+            | ECash
+            | Mixin
+            | Groestlcoin
+            | BinanceCoin
+            | BitcoinSignet
+            | Liquid
+            | CustomSignet(_)
+            | EthereumSepoliaTestnet
+            | Arbitrum
+            | Optimism
+            | Base
+            | BnbSmartChain
+            | Gnosis
+            | Fantom
+            | Tron
+            | TronShastaTestnet
+            | Near
+            | Ton
+            | Aptos
+            | Sui
+            | Filecoin
+            | Kaspa
+            | CosmosHub
+            | Osmosis
+            | Celestia
+            | Moonbeam
+            | Astar
+            | SolanaDevnet
+            | AvalanchePChain
+            | InternetComputer
+            | Mina => {
                 unreachable!()
             }
         }
@@ -133,7 +147,34 @@ impl BitGo {
             BitcoinCashTestnet => "tbch",
             BitcoinTestnet => "tbtc",
             BitcoinSignet => unreachable!(),
+            Mina => unreachable!(),
+            InternetComputer => unreachable!(),
+            AvalanchePChain => unreachable!(),
+            SolanaDevnet => unreachable!(),
+            Astar => unreachable!(),
+            Moonbeam => unreachable!(),
+            Celestia => unreachable!(),
+            Osmosis => unreachable!(),
+            CosmosHub => unreachable!(),
+            Kaspa => unreachable!(),
+            Filecoin => unreachable!(),
+            Sui => unreachable!(),
+            Aptos => unreachable!(),
+            Ton => unreachable!(),
+            Near => unreachable!(),
+            TronShastaTestnet => unreachable!(),
+            Tron => unreachable!(),
+            Fantom => unreachable!(),
+            Gnosis => unreachable!(),
+            BnbSmartChain => unreachable!(),
+            Base => unreachable!(),
+            Optimism => unreachable!(),
+            Arbitrum => unreachable!(),
+            EthereumSepoliaTestnet => unreachable!(),
+            Liquid => unreachable!(),
+            CustomSignet(_) => unreachable!(),
             EthereumGoerliTestnet => "gteth",
+            EthereumHoleskyTestnet => "hteth",
             HederaHashgraph => "hbar",
             HederaHashgraphTestnet => "thbar",
             LitecoinTestnet => "tltc",
@@ -176,9 +217,7 @@ impl BitGo {
     }
 }
 
-#[async_trait]
-impl super::StaticSource for BitGo {
-    const ID: SourceId = SourceId::BitGo;
+impl BitGo {
     const SUPPORTED_CHAINS: &'static [ChainId] = &[
         Bitcoin,
         Litecoin,
@@ -208,6 +247,7 @@ impl super::StaticSource for BitGo {
         ZCashTestnet,
         BitcoinSVTestnet,
         EthereumGoerliTestnet,
+        EthereumHoleskyTestnet,
         HederaHashgraphTestnet,
         RippleTestnet,
         StellarTestnet,
@@ -220,27 +260,47 @@ impl super::StaticSource for BitGo {
         StacksTestnet,
         TezosTestnet,
     ];
+}
 
-    async fn check_updates(&self, recorder: &dyn ChainUpdateRecorder) {
-        // randomize the order to give all chains a chance, even in the presence
-        // of rate limiting
-        let mut supported_chains = Self::SUPPORTED_CHAINS.to_vec();
-        supported_chains.shuffle(&mut thread_rng());
-
-        for chain_id in supported_chains {
-            if self.rate_limiter.should_check(chain_id, recorder).await {
-                if let Some(update) = get_updates(
-                    &self.client,
-                    chain_id,
-                    BitgoAPI::V2,
-                    Self::host_for_chain(chain_id),
-                    Self::coin_symbol_for_chain(chain_id),
-                )
-                .await
-                {
-                    recorder.update(update).await;
-                }
-            }
-        }
+#[async_trait]
+impl ChainStateFetcher for BitGo {
+    const ID: SourceId = SourceId::BitGo;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        Self::SUPPORTED_CHAINS
+    }
+
+    async fn fetch(&self, chain: ChainId) -> Result<ChainState> {
+        let base_url = self
+            .base_url
+            .clone()
+            .unwrap_or_else(|| format!("https://{}", Self::host_for_chain(chain)));
+        get_chain_state(
+            &self.client,
+            BitgoAPI::V2,
+            &base_url,
+            Self::coin_symbol_for_chain(chain),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::fixtures;
+
+    #[tokio::test]
+    async fn reads_the_latest_block_and_its_date() {
+        let client = reqwest::Client::new();
+        let state = fixtures::replay(
+            fixtures::test_dir(),
+            get_chain_state(&client, BitgoAPI::V2, "https://app.bitgo.com", "btc"),
+        )
+        .await
+        .expect("state");
+
+        assert_eq!(state.height, ChainHeight::new(866_000));
+        assert_eq!(state.block_time, Some(1_729_157_000));
     }
 }
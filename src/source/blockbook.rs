@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+
+use super::{pacing::SendPaced, ChainId, ChainId::*, ChainStateFetcher, SourceId};
+use crate::{ChainHeight, ChainState};
+use anyhow::{format_err, Result};
+use axum::async_trait;
+use serde::Deserialize;
+
+/// Public instances run by Trezor, used unless overridden with `--blockbook`
+const DEFAULT_URLS: &[(ChainId, &str)] = &[
+    (Bitcoin, "https://btc1.trezor.io"),
+    (Litecoin, "https://ltc1.trezor.io"),
+    (Doge, "https://doge1.trezor.io"),
+    (Dash, "https://dash1.trezor.io"),
+    (ZCash, "https://zec1.trezor.io"),
+    (BitcoinCash, "https://bch1.trezor.io"),
+    (Ethereum, "https://eth1.trezor.io"),
+];
+
+#[derive(Deserialize)]
+struct Status {
+    blockbook: BlockbookStatus,
+    backend: BackendStatus,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlockbookStatus {
+    in_sync: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BackendStatus {
+    blocks: u64,
+    best_block_hash: String,
+}
+
+/// Trezor's Blockbook indexer, serving many chains through the same API
+pub struct Blockbook {
+    client: reqwest::Client,
+    chains: Vec<ChainId>,
+    urls: HashMap<ChainId, String>,
+}
+
+impl Blockbook {
+    /// Create from `<ticker>=<url>` entries, on top of [`DEFAULT_URLS`]
+    pub fn new(client: reqwest::Client, overrides: &[String]) -> Result<Self> {
+        let mut urls: HashMap<_, _> = DEFAULT_URLS
+            .iter()
+            .map(|&(chain, url)| (chain, url.to_owned()))
+            .collect();
+
+        for entry in overrides {
+            let (ticker, url) = entry
+                .split_once('=')
+                .ok_or_else(|| format_err!("expected `<ticker>=<url>`: {entry}"))?;
+            let chain = ChainId::from_ticker(ticker)
+                .ok_or_else(|| format_err!("unknown ticker: {ticker}"))?;
+            urls.insert(chain, url.trim_end_matches('/').to_owned());
+        }
+
+        Ok(Self {
+            client,
+            chains: urls.keys().copied().collect(),
+            urls,
+        })
+    }
+}
+
+#[async_trait]
+impl ChainStateFetcher for Blockbook {
+    const ID: SourceId = SourceId::Blockbook;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &self.chains
+    }
+
+    fn url_for_chain(&self, chain: ChainId) -> Option<String> {
+        Some(format!("{}/block/{{height}}", self.urls[&chain]))
+    }
+
+    async fn fetch(&self, chain: ChainId) -> Result<ChainState> {
+        let status = self
+            .client
+            .get(format!("{}/api/v2", self.urls[&chain]))
+            .send_paced()
+            .await?
+            .error_for_status()?
+            .json::<Status>()
+            .await?;
+
+        if !status.blockbook.in_sync {
+            return Err(format_err!("blockbook is still syncing"));
+        }
+
+        Ok(ChainState {
+            hash: status.backend.best_block_hash.into(),
+            height: ChainHeight::new(status.backend.blocks),
+            ..Default::default()
+        })
+    }
+}
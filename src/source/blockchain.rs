@@ -1,5 +1,5 @@
-use super::{ChainId, ChainId::*, SourceId};
-use crate::{ChainState, ChainStateUpdate, ChainUpdateRecorder};
+use super::{pacing::SendPaced, ChainId, ChainId::*, ChainStateFetcher, PollingPolicy, SourceId};
+use crate::{ChainHeight, ChainState};
 use anyhow::{bail, Result};
 use axum::async_trait;
 use serde::Deserialize;
@@ -8,6 +8,7 @@ use serde::Deserialize;
 struct BlockLatestBody {
     hash: String,
     height: u64,
+    time: Option<u64>,
 }
 
 #[derive(Deserialize)]
@@ -22,16 +23,24 @@ struct BlockV2 {
     hash: String,
     #[serde(deserialize_with = "crate::util::deserialize_number_from_string")]
     number: u64,
+    #[serde(
+        default,
+        deserialize_with = "crate::util::deserialize_opt_number_from_string"
+    )]
+    timestamp: Option<u64>,
 }
+const DEFAULT_BASE_URL: &str = "https://api.blockchain.info";
+
 pub(crate) async fn get_chain_state_v2(
     client: &reqwest::Client,
+    base_url: &str,
     chain_api_symbol: &str,
 ) -> Result<ChainState> {
     let resp = client
         .get(format!(
-            "https://api.blockchain.info/v2/{chain_api_symbol}/data/blocks?size=1"
+            "{base_url}/v2/{chain_api_symbol}/data/blocks?size=1"
         ))
-        .send()
+        .send_paced()
         .await?
         .error_for_status()?
         .json::<BlocksV2Body>()
@@ -45,69 +54,46 @@ pub(crate) async fn get_chain_state_v2(
     }
 
     Ok(ChainState {
-        hash: resp.block_headers[0].hash.clone(),
-        height: resp.block_headers[0].number,
+        hash: resp.block_headers[0].hash.clone().into(),
+        height: ChainHeight::new(resp.block_headers[0].number),
+        block_time: resp.block_headers[0].timestamp,
+        ..Default::default()
     })
 }
 
 pub(crate) async fn get_chain_state_v1(
     client: &reqwest::Client,
+    base_url: &str,
     chain_api_symbol: &str,
 ) -> Result<ChainState> {
     let resp = client
         .get(format!(
-            "https://api.blockchain.info/haskoin-store/{chain_api_symbol}/block/best?notx=true"
+            "{base_url}/haskoin-store/{chain_api_symbol}/block/best?notx=true"
         ))
-        .send()
+        .send_paced()
         .await?
         .error_for_status()?
         .json::<BlockLatestBody>()
         .await?;
 
     Ok(ChainState {
-        hash: resp.hash,
-        height: resp.height,
+        hash: resp.hash.into(),
+        height: ChainHeight::new(resp.height),
+        block_time: resp.time,
+        ..Default::default()
     })
 }
-async fn check_chain_update(
-    recorer: &dyn ChainUpdateRecorder,
-    client: &reqwest::Client,
-    chain: ChainId,
-    chain_api_symbol: &str,
-) {
-    let res = if chain == Ethereum {
-        get_chain_state_v2(client, chain_api_symbol).await
-    } else {
-        get_chain_state_v1(client, chain_api_symbol).await
-    };
-
-    match res {
-        Ok(state) => {
-            recorer
-                .update(ChainStateUpdate {
-                    source: SourceId::Blockchain,
-                    chain: chain.into(),
-                    state,
-                })
-                .await
-        }
-        Err(e) => {
-            let chain_name: &str = chain.into();
-            tracing::warn!("Couldn't update Blockchain {chain_name}: {e}");
-        }
-    }
-}
 
 pub struct Blockchain {
     client: reqwest::Client,
+    base_url: String,
 }
 
 impl Blockchain {
-    pub fn new() -> Result<Self> {
+    pub fn new(client: reqwest::Client, base_url: Option<String>) -> Result<Self> {
         Ok(Self {
-            client: reqwest::Client::builder()
-                .user_agent("curl/7.79.1")
-                .build()?,
+            client,
+            base_url: base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_owned()),
         })
     }
 
@@ -165,25 +151,34 @@ impl Blockchain {
 }
 
 #[async_trait]
-impl super::StaticSource for Blockchain {
+impl ChainStateFetcher for Blockchain {
     const ID: SourceId = SourceId::Blockchain;
-    const SUPPORTED_CHAINS: &'static [ChainId] = &[
-        Bitcoin,
-        BitcoinCash,
-        Ethereum,
-        BitcoinTestnet,
-        BitcoinCashTestnet,
-    ];
-
-    async fn check_updates(&self, recorder: &dyn ChainUpdateRecorder) {
-        for &chain_id in Self::SUPPORTED_CHAINS {
-            check_chain_update(
-                recorder,
-                &self.client,
-                chain_id,
-                Self::coin_symbol_for_chain(chain_id),
-            )
-            .await
+    const POLLING: PollingPolicy = PollingPolicy::Always;
+    const CONCURRENCY: usize = 4;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &[
+            Bitcoin,
+            BitcoinCash,
+            Ethereum,
+            BitcoinTestnet,
+            BitcoinCashTestnet,
+        ]
+    }
+
+    fn url_for_chain(&self, chain: ChainId) -> Option<String> {
+        Some(format!(
+            "https://www.blockchain.com/explorer/blocks/{}/{{height}}",
+            Self::coin_symbol_for_chain(chain)
+        ))
+    }
+
+    async fn fetch(&self, chain: ChainId) -> Result<ChainState> {
+        let chain_api_symbol = Self::coin_symbol_for_chain(chain);
+        if chain == Ethereum {
+            get_chain_state_v2(&self.client, &self.base_url, chain_api_symbol).await
+        } else {
+            get_chain_state_v1(&self.client, &self.base_url, chain_api_symbol).await
         }
     }
 }
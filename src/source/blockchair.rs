@@ -1,12 +1,16 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use super::{
+    error::without_url,
+    pacing::SendPaced,
     ChainId::{self, *},
+    HeightKind, SourceError,
     SourceId::{self, *},
 };
-use crate::{ChainState, ChainStateUpdate, ChainUpdateRecorder};
+use crate::{BlockHash, ChainHeight, ChainState, ChainStateUpdate, ChainUpdateRecorder};
 use anyhow::Result;
 use axum::async_trait;
+use futures::stream::{self, StreamExt};
 use serde::Deserialize;
 
 // TODO: find a nicer way; whoever made this API scheme, really love nesting shit and the word "data"
@@ -31,7 +35,8 @@ struct HomepageEnStats {
 #[derive(Deserialize)]
 #[serde(rename_all = "lowercase")]
 struct HomepageEnCoin {
-    data: Option<HomepageEnCoinData>,
+    /// Kept raw, for `--height-field` to pick any field
+    data: Option<serde_json::Value>,
 }
 
 #[derive(Deserialize)]
@@ -42,32 +47,133 @@ struct HomepageEnCoinData {
     #[serde(alias = "best_ledger_hash", alias = "best_snapshot_hash")]
     best_block_hash: Option<String>,
 
+    /// Only in the stats of Solana, which tell it apart from its block height
+    #[serde(alias = "best_slot")]
     best_slot_absolute: Option<u64>,
 }
 
-async fn get_homepage_en(client: &reqwest::Client) -> Result<HomepageEnBody> {
+impl HomepageEnCoinData {
+    fn height_of_kind(&self, kind: HeightKind) -> Option<ChainHeight> {
+        match kind {
+            HeightKind::Block => self.best_block_height,
+            HeightKind::Slot => self.best_slot_absolute,
+            HeightKind::BurnBlock => None,
+        }
+        .map(ChainHeight::new)
+    }
+}
+
+const DEFAULT_BASE_URL: &str = "https://api.blockchair.com";
+/// Chains to fetch the stats of at once, to stay within the rate limits of a key
+const STATS_CONCURRENCY: usize = 4;
+
+async fn get_homepage_en(client: &reqwest::Client, base_url: &str) -> Result<HomepageEnBody> {
     Ok(client
-        .get("https://api.blockchair.com/internal/homepage/en")
-        .send()
+        .get(format!("{base_url}/internal/homepage/en"))
+        .send_paced()
         .await?
         .error_for_status()?
         .json::<HomepageEnBody>()
         .await?)
 }
 
+/// Response of the documented `/<coin>/stats` endpoints
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+struct StatsBody {
+    data: serde_json::Value,
+}
+
+async fn get_stats(
+    client: &reqwest::Client,
+    base_url: &str,
+    symbol: &str,
+    api_key: &str,
+) -> Result<StatsBody> {
+    // the URL has the key in it
+    Ok(client
+        .get(format!("{base_url}/{symbol}/stats"))
+        .query(&[("key", api_key)])
+        .send_paced()
+        .await
+        .map_err(without_url)?
+        .error_for_status()
+        .map_err(reqwest::Error::without_url)?
+        .json::<StatsBody>()
+        .await
+        .map_err(reqwest::Error::without_url)?)
+}
+
+/// Read a height from a field that might be either a number or a string
+fn height_from_field(data: &serde_json::Value, field: &str) -> Option<ChainHeight> {
+    let value = data.get(field)?;
+    value
+        .as_u64()
+        .or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+        .map(ChainHeight::new)
+}
+
 pub struct Blockchair {
     client: reqwest::Client,
+    base_url: String,
+    /// With one, the documented stats of each chain are fetched instead of
+    /// those of all chains from the homepage
+    api_key: Option<String>,
+    /// Fields to read heights from instead of the usual ones
+    height_fields: HashMap<ChainId, String>,
 }
 
 impl Blockchair {
-    pub fn new() -> Result<Self> {
+    pub fn new(
+        client: reqwest::Client,
+        base_url: Option<String>,
+        api_key: Option<String>,
+        height_fields: HashMap<ChainId, String>,
+    ) -> Result<Self> {
         Ok(Self {
-            client: reqwest::Client::builder()
-                .user_agent("curl/7.79.1")
-                .build()?,
+            client,
+            base_url: base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_owned()),
+            api_key,
+            height_fields,
         })
     }
 
+    /// Chains on the homepage, and in the stats of each chain
+    const MAINNETS: &'static [ChainId] = &[
+        Bitcoin,
+        Ethereum,
+        Litecoin,
+        Cardano,
+        Ripple,
+        Ripple,
+        Polkadot,
+        Doge,
+        Solana,
+        BitcoinCash,
+        Stellar,
+        Monero,
+        Eos,
+        Kusama,
+        BitcoinSV,
+        ECash,
+        Dash,
+        Mixin,
+        Groestlcoin,
+        ZCash,
+        BnbSmartChain,
+    ];
+
+    /// Chains only in the stats of each chain, so polled with an API key only
+    const TESTNETS: &'static [ChainId] = &[BitcoinTestnet, EthereumSepoliaTestnet];
+
+    fn chains(&self) -> impl Iterator<Item = ChainId> {
+        let testnets = match self.api_key {
+            Some(_) => Self::TESTNETS,
+            None => &[],
+        };
+        Self::MAINNETS.iter().chain(testnets).copied()
+    }
+
     fn coin_symbol_for_chain(chain: ChainId) -> &'static str {
         match chain {
             Bitcoin => "bitcoin",
@@ -89,101 +195,227 @@ impl Blockchair {
             Polkadot => "polkadot",
             Solana => "solana",
             Kusama => "kusama",
+            BnbSmartChain => "bnb",
+
+            // testnets go by the path of their stats
+            BitcoinTestnet => "bitcoin/testnet",
+            EthereumSepoliaTestnet => "ethereum/testnet",
 
             _ => unreachable!(),
         }
     }
+
+    /// Record the state of `chain` from its `raw` coin data
+    async fn record(
+        &self,
+        recorder: &dyn ChainUpdateRecorder,
+        chain: ChainId,
+        raw: Option<serde_json::Value>,
+    ) {
+        let symbol = Self::coin_symbol_for_chain(chain);
+        let data = match raw
+            .as_ref()
+            .and_then(|raw| serde_json::from_value::<HomepageEnCoinData>(raw.clone()).ok())
+        {
+            Some(data) => data,
+            None => {
+                recorder
+                    .report_error(
+                        Blockchair,
+                        Some(chain),
+                        SourceError::schema_change(format!(
+                            "Malformed data for blockchair coin data: {symbol}"
+                        )),
+                    )
+                    .await;
+                return;
+            }
+        };
+
+        let height = match (self.height_fields.get(&chain), raw.as_ref()) {
+            (Some(field), Some(raw)) => height_from_field(raw, field),
+            _ => data.height_of_kind(chain.height_kind()),
+        };
+        match height {
+            Some(height) => {
+                recorder
+                    .update(ChainStateUpdate {
+                        source: Blockchair,
+                        chain,
+                        state: ChainState {
+                            hash: data
+                                .best_block_hash
+                                .as_deref()
+                                .map_or_else(|| BlockHash::from_height(height), BlockHash::from),
+                            height,
+                            secondary_height: chain
+                                .secondary_height_kind()
+                                .and_then(|kind| data.height_of_kind(kind)),
+                            ..Default::default()
+                        },
+                    })
+                    .await
+            }
+            None => {
+                recorder
+                    .report_error(
+                        Blockchair,
+                        Some(chain),
+                        SourceError::schema_change(format!(
+                            "Missing chain data for blockchair coin data: {symbol}"
+                        )),
+                    )
+                    .await
+            }
+        }
+    }
 }
 
 #[async_trait]
-impl super::StaticSource for Blockchair {
-    const ID: SourceId = SourceId::Blockchair;
-    const SUPPORTED_CHAINS: &'static [ChainId] = &[
-        Bitcoin,
-        Ethereum,
-        Litecoin,
-        Cardano,
-        Ripple,
-        Ripple,
-        Polkadot,
-        Doge,
-        Solana,
-        BitcoinCash,
-        Stellar,
-        Monero,
-        Eos,
-        Kusama,
-        BitcoinSV,
-        ECash,
-        Dash,
-        Mixin,
-        Groestlcoin,
-        ZCash,
-    ];
+impl super::Source for Blockchair {
+    fn get_supported_chains(&self) -> HashSet<ChainId> {
+        self.chains().collect()
+    }
+
+    fn get_supported_sources(&self) -> HashSet<SourceId> {
+        HashSet::from_iter([Blockchair])
+    }
+
+    fn url_for_chain(&self, source: SourceId, chain: ChainId) -> Option<String> {
+        if source != Blockchair || !self.chains().any(|c| c == chain) {
+            return None;
+        }
+        Some(format!(
+            "https://blockchair.com/{}/block/{{height}}",
+            Self::coin_symbol_for_chain(chain)
+        ))
+    }
 
     async fn check_updates(&self, recorder: &dyn ChainUpdateRecorder) {
-        match get_homepage_en(&self.client).await {
-            Ok(state) => {
-                let data = state.data.stats.data;
-
-                for &chain in Self::SUPPORTED_CHAINS {
-                    let symbol = Self::coin_symbol_for_chain(chain);
-
-                    if let Some(data) = data.get(symbol) {
-                        if let Some(data) = data.data.as_ref() {
-                            if chain == ChainId::Solana || chain == ChainId::SolanaTestnet {
-                                // report slots instead of block height for Solana
-                                if let Some(height) = data.best_slot_absolute {
-                                    recorder
-                                        .update(ChainStateUpdate {
-                                            source: Blockchair,
-                                            chain: chain,
-                                            state: ChainState {
-                                                hash: data
-                                                    .best_block_hash
-                                                    .clone()
-                                                    .unwrap_or_else(|| height.to_string()),
-                                                height,
-                                            },
-                                        })
-                                        .await;
-                                } else {
-                                    tracing::warn!(
-                                        "Missing chain data for blockchair coin data: {symbol}"
-                                    );
-                                }
-                            } else {
-                                if let Some(height) = data.best_block_height {
-                                    recorder
-                                        .update(ChainStateUpdate {
-                                            source: Blockchair,
-                                            chain: chain,
-                                            state: ChainState {
-                                                hash: data
-                                                    .best_block_hash
-                                                    .clone()
-                                                    .unwrap_or_else(|| height.to_string()),
-                                                height,
-                                            },
-                                        })
-                                        .await;
-                                } else {
-                                    tracing::warn!(
-                                        "Missing chain data for blockchair coin data: {symbol}"
-                                    );
-                                }
-                            }
-                        } else {
-                            tracing::warn!("Malformed data for blockchair coin data: {symbol}");
+        match self.api_key.as_deref() {
+            Some(api_key) => {
+                let stats: Vec<_> = stream::iter(self.chains())
+                    .map(|chain| async move {
+                        (
+                            chain,
+                            get_stats(
+                                &self.client,
+                                &self.base_url,
+                                Self::coin_symbol_for_chain(chain),
+                                api_key,
+                            )
+                            .await,
+                        )
+                    })
+                    .buffer_unordered(STATS_CONCURRENCY)
+                    .collect()
+                    .await;
+                for (chain, stats) in stats {
+                    match stats {
+                        Ok(stats) => self.record(recorder, chain, Some(stats.data)).await,
+                        Err(e) => {
+                            recorder
+                                .report_error(Blockchair, Some(chain), e.into())
+                                .await
                         }
-                    } else {
-                        tracing::warn!("Couldn't find blockchair coin data: {symbol}");
                     }
                 }
             }
-            Err(e) => {
-                tracing::warn!("Couldn't update Blockchair: {e}");
-            }
+            None => match get_homepage_en(&self.client, &self.base_url).await {
+                Ok(state) => {
+                    let mut data = state.data.stats.data;
+
+                    for &chain in Self::MAINNETS {
+                        let symbol = Self::coin_symbol_for_chain(chain);
+
+                        match data.remove(symbol) {
+                            Some(data) => self.record(recorder, chain, data.data).await,
+                            None => {
+                                recorder
+                                    .report_error(
+                                        Blockchair,
+                                        Some(chain),
+                                        SourceError::schema_change(format!(
+                                            "Couldn't find blockchair coin data: {symbol}"
+                                        )),
+                                    )
+                                    .await
+                            }
+                        }
+                    }
+                }
+                Err(e) => recorder.report_error(Blockchair, None, e.into()).await,
+            },
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::{fixtures, Source};
+
+    async fn homepage_coin(symbol: &str) -> Option<HomepageEnCoinData> {
+        let client = reqwest::Client::new();
+        let mut body = fixtures::replay(
+            fixtures::test_dir(),
+            get_homepage_en(&client, DEFAULT_BASE_URL),
+        )
+        .await
+        .expect("homepage");
+        let raw = body.data.stats.data.remove(symbol)?.data?;
+        Some(serde_json::from_value(raw).expect("coin data"))
+    }
+
+    #[tokio::test]
+    async fn reads_block_heights_from_the_homepage() {
+        let bitcoin = homepage_coin("bitcoin").await.expect("bitcoin");
+        assert_eq!(
+            bitcoin.height_of_kind(HeightKind::Block),
+            Some(ChainHeight::new(866_000))
+        );
+        assert_eq!(bitcoin.height_of_kind(HeightKind::Slot), None);
+
+        let ripple = homepage_coin("ripple").await.expect("ripple");
+        assert_eq!(
+            ripple.height_of_kind(HeightKind::Block),
+            Some(ChainHeight::new(91_379_005))
+        );
+    }
+
+    #[tokio::test]
+    async fn reads_solana_slots_apart_from_block_heights() {
+        let solana = homepage_coin("solana").await.expect("solana");
+        assert_eq!(
+            solana.height_of_kind(HeightKind::Block),
+            Some(ChainHeight::new(275_163_212))
+        );
+        assert_eq!(
+            solana.height_of_kind(HeightKind::Slot),
+            Some(ChainHeight::new(296_913_330))
+        );
+    }
+
+    #[tokio::test]
+    async fn coins_without_data_are_told_apart() {
+        assert!(homepage_coin("monero").await.is_none());
+    }
+
+    #[test]
+    fn testnets_need_an_api_key() {
+        let blockchair = |api_key: Option<&str>| {
+            Blockchair::new(
+                reqwest::Client::new(),
+                None,
+                api_key.map(str::to_owned),
+                HashMap::new(),
+            )
+            .expect("source")
+        };
+        let testnet =
+            |blockchair: &Blockchair| blockchair.get_supported_chains().contains(&BitcoinTestnet);
+
+        assert!(!testnet(&blockchair(None)));
+        assert!(testnet(&blockchair(Some("key"))));
+    }
+}
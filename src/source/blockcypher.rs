@@ -1,8 +1,7 @@
-use super::{ChainId, ChainId::*, SourceId};
-use crate::{ChainState, ChainStateUpdate, ChainUpdateRecorder};
+use super::{pacing::SendPaced, ChainId, ChainId::*, ChainStateFetcher, PollingPolicy, SourceId};
+use crate::{ChainHeight, ChainState};
 use anyhow::Result;
 use axum::async_trait;
-use rand::{seq::SliceRandom, thread_rng};
 use serde::Deserialize;
 
 #[derive(Deserialize)]
@@ -11,57 +10,38 @@ struct BlockLatestBody {
     height: u64,
 }
 
+const DEFAULT_BASE_URL: &str = "https://api.blockcypher.com";
+
 pub(crate) async fn get_chain_state(
     client: &reqwest::Client,
+    base_url: &str,
     chain_api_symbol: &str,
 ) -> Result<ChainState> {
     let resp = client
-        .get(format!("https://api.blockcypher.com/v1/{chain_api_symbol}"))
-        .send()
+        .get(format!("{base_url}/v1/{chain_api_symbol}"))
+        .send_paced()
         .await?
         .error_for_status()?
         .json::<BlockLatestBody>()
         .await?;
 
     Ok(ChainState {
-        hash: resp.hash,
-        height: resp.height,
+        hash: resp.hash.into(),
+        height: ChainHeight::new(resp.height),
+        ..Default::default()
     })
 }
 
-async fn get_updates(
-    client: &reqwest::Client,
-    chain: ChainId,
-    chain_api_symbol: &str,
-) -> Option<ChainStateUpdate> {
-    match get_chain_state(client, chain_api_symbol).await {
-        Ok(state) => Some(ChainStateUpdate {
-            source: SourceId::BlockCypher,
-            chain: chain.into(),
-            state,
-        }),
-        Err(e) => {
-            let chain_name: &str = chain.into();
-            tracing::warn!("Couldn't update BlockCypher {chain_name}: {e}");
-            None
-        }
-    }
-}
-
 pub struct BlockCypher {
     client: reqwest::Client,
-    rate_limiter: super::UpdateRateLimiter,
+    base_url: String,
 }
 
 impl BlockCypher {
-    pub fn new() -> Result<Self> {
+    pub fn new(client: reqwest::Client, base_url: Option<String>) -> Result<Self> {
         Ok(Self {
-            client: reqwest::Client::builder()
-                .user_agent("curl/7.79.1")
-                .build()?,
-            rate_limiter: super::UpdateRateLimiter::new(<Self as super::StaticSource>::ID)
-                // tight rate limits
-                .disable_periodic_check(),
+            client,
+            base_url: base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_owned()),
         })
     }
 
@@ -78,28 +58,35 @@ impl BlockCypher {
 }
 
 #[async_trait]
-impl super::StaticSource for BlockCypher {
+impl ChainStateFetcher for BlockCypher {
     const ID: SourceId = SourceId::BlockCypher;
-    const SUPPORTED_CHAINS: &'static [ChainId] = &[Bitcoin, Litecoin, Dash, Doge, BitcoinTestnet];
+    // tight rate limits
+    const POLLING: PollingPolicy = PollingPolicy::WhenBehind;
 
-    async fn check_updates(&self, recorder: &dyn ChainUpdateRecorder) {
-        // randomize the order to give all chains a chance, even in the presence
-        // of rate limiting
-        let mut supported_chains = Self::SUPPORTED_CHAINS.to_vec();
-        supported_chains.shuffle(&mut thread_rng());
+    fn supported_chains(&self) -> &[ChainId] {
+        &[Bitcoin, Litecoin, Dash, Doge, BitcoinTestnet]
+    }
 
-        for chain_id in supported_chains {
-            if self.rate_limiter.should_check(chain_id, recorder).await {
-                if let Some(update) = get_updates(
-                    &self.client,
-                    chain_id,
-                    Self::coin_symbol_for_chain(chain_id),
-                )
-                .await
-                {
-                    recorder.update(update).await;
-                }
-            }
-        }
+    fn url_for_chain(&self, chain: ChainId) -> Option<String> {
+        let explorer_symbol = match chain {
+            Bitcoin => "btc",
+            Litecoin => "ltc",
+            Dash => "dash",
+            Doge => "doge",
+            BitcoinTestnet => "btc-testnet",
+            _ => return None,
+        };
+        Some(format!(
+            "https://live.blockcypher.com/{explorer_symbol}/block/{{hash}}/"
+        ))
+    }
+
+    async fn fetch(&self, chain: ChainId) -> Result<ChainState> {
+        get_chain_state(
+            &self.client,
+            &self.base_url,
+            Self::coin_symbol_for_chain(chain),
+        )
+        .await
     }
 }
@@ -0,0 +1,65 @@
+use super::{pacing::SendPaced, ChainId, ChainId::*, ChainStateFetcher, SourceId};
+use crate::{ChainHeight, ChainState};
+use anyhow::Result;
+use axum::async_trait;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct Block {
+    hash: String,
+    height: u64,
+    slot: u64,
+    time: u64,
+}
+
+const DEFAULT_BASE_URL: &str = "https://cardano-mainnet.blockfrost.io";
+
+/// Blockfrost, the hosted Cardano API
+pub struct Blockfrost {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: String,
+}
+
+impl Blockfrost {
+    pub fn new(client: reqwest::Client, base_url: Option<String>, api_key: String) -> Result<Self> {
+        Ok(Self {
+            client,
+            base_url: base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_owned()),
+            api_key,
+        })
+    }
+}
+
+#[async_trait]
+impl ChainStateFetcher for Blockfrost {
+    const ID: SourceId = SourceId::Blockfrost;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &[Cardano]
+    }
+
+    fn url_for_chain(&self, _chain: ChainId) -> Option<String> {
+        Some("https://cardanoscan.io/block/{height}".to_owned())
+    }
+
+    async fn fetch(&self, _chain: ChainId) -> Result<ChainState> {
+        let block = self
+            .client
+            .get(format!("{}/api/v0/blocks/latest", self.base_url))
+            .header("project_id", &self.api_key)
+            .send_paced()
+            .await?
+            .error_for_status()?
+            .json::<Block>()
+            .await?;
+
+        Ok(ChainState {
+            hash: block.hash.into(),
+            height: ChainHeight::new(block.height),
+            block_time: Some(block.time),
+            secondary_height: Some(ChainHeight::new(block.slot)),
+            ..Default::default()
+        })
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+
+use super::{pacing::SendPaced, ChainId, ChainId::*, ChainStateFetcher, SourceError, SourceId};
+use crate::{ChainHeight, ChainState};
+use anyhow::{format_err, Result};
+use axum::async_trait;
+use serde::Deserialize;
+
+/// Public instances, used unless overridden with `--blockscout`
+const DEFAULT_URLS: &[(ChainId, &str)] = &[
+    (EthereumClassic, "https://etc.blockscout.com"),
+    (Celo, "https://celo.blockscout.com"),
+];
+
+#[derive(Deserialize)]
+struct Blocks {
+    items: Vec<Block>,
+}
+
+#[derive(Deserialize)]
+struct Block {
+    hash: String,
+    height: u64,
+}
+
+/// Blockscout, the open-source explorer of many EVM chains
+pub struct Blockscout {
+    client: reqwest::Client,
+    chains: Vec<ChainId>,
+    urls: HashMap<ChainId, String>,
+}
+
+impl Blockscout {
+    /// Create from `<ticker>=<url>` entries, on top of [`DEFAULT_URLS`]
+    pub fn new(client: reqwest::Client, overrides: &[String]) -> Result<Self> {
+        let mut urls: HashMap<_, _> = DEFAULT_URLS
+            .iter()
+            .map(|&(chain, url)| (chain, url.to_owned()))
+            .collect();
+
+        for entry in overrides {
+            let (ticker, url) = entry
+                .split_once('=')
+                .ok_or_else(|| format_err!("expected `<ticker>=<url>`: {entry}"))?;
+            let chain = ChainId::from_ticker(ticker)
+                .ok_or_else(|| format_err!("unknown ticker: {ticker}"))?;
+            urls.insert(chain, url.trim_end_matches('/').to_owned());
+        }
+
+        Ok(Self {
+            client,
+            chains: urls.keys().copied().collect(),
+            urls,
+        })
+    }
+}
+
+#[async_trait]
+impl ChainStateFetcher for Blockscout {
+    const ID: SourceId = SourceId::Blockscout;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &self.chains
+    }
+
+    fn url_for_chain(&self, chain: ChainId) -> Option<String> {
+        Some(format!("{}/block/{{height}}", self.urls[&chain]))
+    }
+
+    async fn fetch(&self, chain: ChainId) -> Result<ChainState> {
+        let block = self
+            .client
+            .get(format!("{}/api/v2/blocks?type=block", self.urls[&chain]))
+            .send_paced()
+            .await?
+            .error_for_status()?
+            .json::<Blocks>()
+            .await?
+            .items
+            .into_iter()
+            .next()
+            .ok_or_else(|| SourceError::schema_change("no blocks returned"))?;
+
+        Ok(ChainState {
+            hash: block.hash.into(),
+            height: ChainHeight::new(block.height),
+            ..Default::default()
+        })
+    }
+}
@@ -0,0 +1,56 @@
+use super::{esplora, ChainId, ChainId::*, ChainStateFetcher, SourceId};
+use crate::ChainState;
+use anyhow::Result;
+use axum::async_trait;
+
+const DEFAULT_BASE_URL: &str = "https://blockstream.info";
+
+/// Blockstream's Esplora, an independent view next to mempool.space
+pub struct Blockstream {
+    client: reqwest::Client,
+    /// Of both the API and the explorer
+    base_url: String,
+}
+
+impl Blockstream {
+    pub fn new(client: reqwest::Client, base_url: Option<String>) -> Result<Self> {
+        Ok(Self {
+            client,
+            base_url: base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_owned()),
+        })
+    }
+
+    fn get_prefix_for_chain(chain: ChainId) -> &'static str {
+        match chain {
+            Bitcoin => "",
+            BitcoinTestnet => "testnet/",
+            Liquid => "liquid/",
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[async_trait]
+impl ChainStateFetcher for Blockstream {
+    const ID: SourceId = SourceId::Blockstream;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &[Bitcoin, BitcoinTestnet, Liquid]
+    }
+
+    fn url_for_chain(&self, chain: ChainId) -> Option<String> {
+        Some(format!(
+            "{}/{}block/{{hash}}",
+            self.base_url,
+            Self::get_prefix_for_chain(chain)
+        ))
+    }
+
+    async fn fetch(&self, chain: ChainId) -> Result<ChainState> {
+        esplora::get_chain_state(
+            &self.client,
+            &format!("{}/{}api", self.base_url, Self::get_prefix_for_chain(chain)),
+        )
+        .await
+    }
+}
@@ -1,76 +1,203 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
 
 use super::{
+    pacing::SendPaced,
     ChainId::{self, *},
-    SourceId::{self, *},
+    MirroredSourceId, SourceId,
 };
 use crate::{ChainStateTs, ChainStateUpdate, ChainUpdateRecorder};
 use anyhow::Result;
 use axum::async_trait;
-use tracing::{debug, log::warn};
+use serde::Deserialize;
+use tracing::debug;
+
+/// Best state of a chain, as reported by `/state` of another instance
+#[derive(Deserialize)]
+struct MirroredChainState {
+    /// Missing when talking to older instances
+    source: Option<String>,
+    #[serde(flatten)]
+    state: ChainStateTs,
+}
 
-/// A catch-all of single-chain explorers and alikes
+/// Another instance of chain-monitor
 pub struct ChainMonitor {
     client: reqwest::Client,
     url: String,
+    mirror_idx: usize,
+    mirror_name: String,
+    /// Our ids of the sources reported by the remote, made up the first time
+    /// each of them is reported
+    sources: Mutex<HashMap<SourceId, SourceId>>,
+    /// Source the state of each chain was attributed to in the last poll
+    reported: Mutex<HashMap<ChainId, SourceId>>,
 }
 
 impl ChainMonitor {
-    pub fn new(url: String) -> Result<Self> {
+    const SUPPORTED_CHAINS: &'static [ChainId] = &[
+        Algorand,
+        Avalanche,
+        Stacks,
+        EthereumClassic,
+        Casper,
+        Celo,
+        Tezos,
+    ];
+
+    pub fn new(client: reqwest::Client, url: String, mirror_idx: usize) -> Result<Self> {
+        let mirror_name = url
+            .trim_start_matches("https://")
+            .trim_start_matches("http://")
+            .trim_end_matches('/')
+            .to_owned();
+
         Ok(Self {
-            client: reqwest::Client::builder()
-                .user_agent("curl/7.79.1")
-                .build()?,
+            client,
             url,
+            mirror_idx,
+            mirror_name,
+            sources: Default::default(),
+            reported: Default::default(),
         })
     }
 
-    pub async fn get_json(&self) -> Result<HashMap<String, ChainStateTs>> {
+    async fn get_json(&self) -> Result<HashMap<String, MirroredChainState>> {
         Ok(self
             .client
             .get(format!("{}/state", self.url))
-            .send()
+            .send_paced()
             .await?
             .error_for_status()?
-            .json::<HashMap<String, ChainStateTs>>()
+            .json::<HashMap<String, MirroredChainState>>()
             .await?)
     }
+
+    /// Map the source reported by the remote to our own id for it
+    fn local_source_id(&self, remote_source: Option<&str>) -> SourceId {
+        let source = match remote_source.and_then(SourceId::from_short_name) {
+            Some(source) if SourceId::BUILTIN.contains(&source) => source,
+            _ => return SourceId::ChainMonitor,
+        };
+        *self
+            .sources
+            .lock()
+            .expect("not poisoned")
+            .entry(source)
+            .or_insert_with(|| {
+                SourceId::Mirrored(MirroredSourceId::new(
+                    source,
+                    self.mirror_idx,
+                    &self.mirror_name,
+                    &self.url,
+                ))
+            })
+    }
+
+    /// Remember which source the state of each chain was attributed to,
+    /// returning the ones the remote no longer attributes a chain to
+    fn replace_reported(&self, reported: HashMap<ChainId, SourceId>) -> Vec<(ChainId, SourceId)> {
+        let previous = std::mem::replace(
+            &mut *self.reported.lock().expect("not poisoned"),
+            reported.clone(),
+        );
+        previous
+            .into_iter()
+            .filter(|(chain, source)| reported.get(chain) != Some(source))
+            .collect()
+    }
 }
 
 #[async_trait]
-impl super::StaticSource for ChainMonitor {
-    const ID: SourceId = SourceId::ChainMonitor;
-    const SUPPORTED_CHAINS: &'static [ChainId] = &[
-        Algorand,
-        Avalanche,
-        Stacks,
-        EthereumClassic,
-        Casper,
-        Celo,
-        Tezos,
-    ];
+impl super::Source for ChainMonitor {
+    fn get_supported_chains(&self) -> HashSet<ChainId> {
+        HashSet::from_iter(Self::SUPPORTED_CHAINS.iter().copied())
+    }
+
+    /// Mirrored sources are registered by the recorder once they report
+    /// anything, see [`Self::local_source_id`]
+    fn get_supported_sources(&self) -> HashSet<SourceId> {
+        HashSet::from_iter([SourceId::ChainMonitor])
+    }
+
+    /// The remote doesn't tell the explorer pages of its sources
+    fn url_for_chain(&self, _source: SourceId, _chain: ChainId) -> Option<String> {
+        None
+    }
 
     async fn check_updates(&self, recorder: &dyn ChainUpdateRecorder) {
         match self.get_json().await {
-            Err(e) => warn!(
-                "Could not get chain state from {}: {e}",
-                Self::ID.short_name(),
-            ),
+            Err(e) => {
+                recorder
+                    .report_error(SourceId::ChainMonitor, None, e.into())
+                    .await
+            }
             Ok(states) => {
+                let mut reported = HashMap::new();
                 for (ticker, state) in states {
                     if let Some(chain) = ChainId::from_ticker(&ticker) {
+                        let source = self.local_source_id(state.source.as_deref());
+                        reported.insert(chain, source);
                         recorder
                             .update(ChainStateUpdate {
-                                source: ChainMonitor,
+                                source,
                                 chain,
-                                state: state.state,
+                                state: state.state.state,
                             })
                             .await;
                     } else {
                         debug!("Unknown ticker {ticker} ignored from {}", self.url);
                     }
                 }
+
+                // the best source of the remote changed, or it stopped
+                // reporting the chain, so the state would be stuck otherwise
+                for (chain, source) in self.replace_reported(reported) {
+                    recorder.forget(source, chain).await;
+                }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mirror() -> ChainMonitor {
+        ChainMonitor::new(
+            reqwest::Client::new(),
+            "https://mirror.example.com".into(),
+            1,
+        )
+        .expect("mirror")
+    }
+
+    #[test]
+    fn sources_are_made_up_once_reported() {
+        let mirror = mirror();
+        assert!(mirror.sources.lock().expect("not poisoned").is_empty());
+
+        let source = mirror.local_source_id(Some("MempoolSpace"));
+        assert_eq!(source.short_name(), "MempoolSpace@1");
+        assert_eq!(mirror.local_source_id(Some("MempoolSpace")), source);
+        assert_eq!(mirror.local_source_id(None), SourceId::ChainMonitor);
+        assert_eq!(mirror.sources.lock().expect("not poisoned").len(), 1);
+    }
+
+    #[test]
+    fn sources_no_longer_reported_are_stale() {
+        let mirror = mirror();
+        let mempool_space = mirror.local_source_id(Some("MempoolSpace"));
+        let blockstream = mirror.local_source_id(Some("Blockstream"));
+
+        let reported = HashMap::from_iter([(Bitcoin, mempool_space), (Litecoin, mempool_space)]);
+        assert!(mirror.replace_reported(reported).is_empty());
+        let reported = HashMap::from_iter([(Bitcoin, blockstream)]);
+        let mut stale = mirror.replace_reported(reported);
+        stale.sort();
+        assert_eq!(stale, [(Bitcoin, mempool_space), (Litecoin, mempool_space)]);
+    }
+}
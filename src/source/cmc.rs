@@ -1,9 +1,9 @@
-use crate::{ChainState, ChainStateUpdate, ChainUpdateRecorder};
+use crate::{ChainHeight, ChainState};
 use anyhow::{bail, Result};
 use axum::async_trait;
 use serde::Deserialize;
 
-use super::{ChainId, ChainId::*, SourceId};
+use super::{pacing::SendPaced, ChainId, ChainId::*, ChainStateFetcher, PollingPolicy, SourceId};
 
 #[derive(Deserialize)]
 struct BlocksBody {
@@ -18,13 +18,14 @@ struct BlocksDataItem {
 
 pub(crate) async fn get_chain_state(
     client: &reqwest::Client,
+    base_url: &str,
     chain_api_symbol: &str,
 ) -> Result<ChainState> {
     let resp = client
         .get(format!(
-            "https://blockchain.coinmarketcap.com/api/blocks?symbol={chain_api_symbol}&start=1&limit=1&quote=true"
+            "{base_url}/api/blocks?symbol={chain_api_symbol}&start=1&limit=1&quote=true"
         ))
-        .send()
+        .send_paced()
         .await?
         .error_for_status()?
         .json::<BlocksBody>()
@@ -32,43 +33,27 @@ pub(crate) async fn get_chain_state(
 
     if let Some(item) = resp.data.get(0) {
         Ok(ChainState {
-            hash: item.hash.clone(),
-            height: item.height,
+            hash: item.hash.clone().into(),
+            height: ChainHeight::new(item.height),
+            ..Default::default()
         })
     } else {
         bail!("No blocks returned");
     }
 }
 
-async fn get_chain_update(
-    client: &reqwest::Client,
-    chain: ChainId,
-    chain_api_symbol: &str,
-) -> Option<ChainStateUpdate> {
-    match get_chain_state(client, chain_api_symbol).await {
-        Ok(state) => Some(ChainStateUpdate {
-            source: SourceId::CMC,
-            chain: chain.into(),
-            state,
-        }),
-        Err(e) => {
-            let chain_name: &str = chain.into();
-            tracing::warn!("Couldn't update CoinMarketCap {chain_name}: {e}");
-            None
-        }
-    }
-}
+const DEFAULT_BASE_URL: &str = "https://blockchain.coinmarketcap.com";
 
 pub struct CoinMarketCap {
     client: reqwest::Client,
+    base_url: String,
 }
 
 impl CoinMarketCap {
-    pub fn new() -> Result<Self> {
+    pub fn new(client: reqwest::Client, base_url: Option<String>) -> Result<Self> {
         Ok(Self {
-            client: reqwest::Client::builder()
-                .user_agent("curl/7.79.1")
-                .build()?,
+            client,
+            base_url: base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_owned()),
         })
     }
 
@@ -85,21 +70,21 @@ impl CoinMarketCap {
 }
 
 #[async_trait]
-impl super::StaticSource for CoinMarketCap {
+impl ChainStateFetcher for CoinMarketCap {
     const ID: SourceId = SourceId::CMC;
-    const SUPPORTED_CHAINS: &'static [ChainId] = &[Bitcoin, Ethereum, Litecoin, BinanceCoin];
+    const POLLING: PollingPolicy = PollingPolicy::Always;
+    const CONCURRENCY: usize = 4;
 
-    async fn check_updates(&self, recorder: &dyn ChainUpdateRecorder) {
-        for &chain_id in Self::SUPPORTED_CHAINS {
-            if let Some(update) = get_chain_update(
-                &self.client,
-                chain_id,
-                Self::coin_symbol_for_chain(chain_id),
-            )
-            .await
-            {
-                recorder.update(update).await;
-            }
-        }
+    fn supported_chains(&self) -> &[ChainId] {
+        &[Bitcoin, Ethereum, Litecoin, BinanceCoin]
+    }
+
+    async fn fetch(&self, chain: ChainId) -> Result<ChainState> {
+        get_chain_state(
+            &self.client,
+            &self.base_url,
+            Self::coin_symbol_for_chain(chain),
+        )
+        .await
     }
 }
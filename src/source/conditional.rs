@@ -0,0 +1,134 @@
+//! Conditional requests, so polling a chain that didn't change is cheap
+//!
+//! The validators (`ETag`, `Last-Modified`) of the responses to `GET`s of a
+//! successful fetch are sent back on the next fetch of the same URLs, and a
+//! `304 Not Modified` ends the fetch as [`SourceErrorKind::Unchanged`]. Only
+//! validators of fetches that succeeded are kept, so a fetch that failed after
+//! some of its requests is never cut short by them next time. They're kept
+//! apart for each kind of fetch, as fetching e.g. the difficulty from the same
+//! URL as the chain state right after it is still a change for the former.
+#![cfg_attr(not(feature = "all-sources"), allow(dead_code))]
+use std::{cell::RefCell, collections::HashMap, future::Future, sync::Mutex};
+
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use reqwest::{
+    header::{HeaderValue, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+    Method, RequestBuilder, Response, StatusCode,
+};
+
+use super::{SourceError, SourceErrorKind};
+
+#[derive(Clone, Default)]
+struct Validators {
+    etag: Option<HeaderValue>,
+    last_modified: Option<HeaderValue>,
+}
+
+/// Validators of the last successful fetches, by the key of their [`scope`]
+/// and URL
+static VALIDATORS: Lazy<Mutex<HashMap<(String, String), Validators>>> = Lazy::new(Default::default);
+
+/// Validators seen during a fetch, see [`scope`]
+struct Pending {
+    key: String,
+    validators: Vec<(String, Validators)>,
+}
+
+tokio::task_local! {
+    static PENDING: RefCell<Pending>;
+}
+
+/// Run `fetch`, making the requests in it conditional on the responses of the
+/// last successful fetch with the same `key`
+///
+/// Requests sent outside of it are never conditional.
+pub async fn scope<T>(
+    key: String,
+    fetch: impl Future<Output = Option<Result<T>>>,
+) -> Option<Result<T>> {
+    let pending = Pending {
+        key,
+        validators: vec![],
+    };
+    let (res, pending) = PENDING
+        .scope(RefCell::new(pending), async {
+            let res = fetch.await;
+            let pending = PENDING.with(|pending| {
+                let mut pending = pending.borrow_mut();
+                (pending.key.clone(), std::mem::take(&mut pending.validators))
+            });
+            (res, pending)
+        })
+        .await;
+
+    if matches!(res, Some(Ok(_))) {
+        let (key, validators) = pending;
+        VALIDATORS.lock().expect("not poisoned").extend(
+            validators
+                .into_iter()
+                .map(|(url, validators)| ((key.clone(), url), validators)),
+        );
+    }
+    res
+}
+
+/// Add the validators of the last response to `url`, if in a [`scope`]
+pub fn prepare(builder: RequestBuilder, method: &Method, url: &str) -> RequestBuilder {
+    if method != Method::GET {
+        return builder;
+    }
+    let key = match PENDING.try_with(|pending| pending.borrow().key.clone()) {
+        Ok(key) => key,
+        Err(_) => return builder,
+    };
+    let validators = match VALIDATORS
+        .lock()
+        .expect("not poisoned")
+        .get(&(key, url.to_owned()))
+    {
+        Some(validators) => validators.clone(),
+        None => return builder,
+    };
+
+    let builder = match validators.etag {
+        Some(etag) => builder.header(IF_NONE_MATCH, etag),
+        None => builder,
+    };
+    match validators.last_modified {
+        Some(last_modified) => builder.header(IF_MODIFIED_SINCE, last_modified),
+        None => builder,
+    }
+}
+
+/// Keep the validators of `response` to `url` for the end of the [`scope`],
+/// failing if it didn't change
+pub fn observe(method: &Method, url: &str, response: &Response) -> Result<()> {
+    if method != Method::GET {
+        return Ok(());
+    }
+    if response.status() == StatusCode::NOT_MODIFIED {
+        return Err(
+            SourceError::new(SourceErrorKind::Unchanged, format!("{url} not modified")).into(),
+        );
+    }
+    if !response.status().is_success() {
+        return Ok(());
+    }
+
+    let headers = response.headers();
+    let validators = Validators {
+        etag: headers.get(ETAG).cloned(),
+        last_modified: headers.get(LAST_MODIFIED).cloned(),
+    };
+    if validators.etag.is_some() || validators.last_modified.is_some() {
+        // not in a scope if it fails, and then there's nothing to keep
+        let _ = PENDING.try_with(|pending| {
+            pending
+                .borrow_mut()
+                .validators
+                .push((url.to_owned(), validators));
+        });
+    }
+    Ok(())
+}
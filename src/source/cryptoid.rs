@@ -0,0 +1,104 @@
+use super::{
+    error::without_url, pacing::SendPaced, ChainId, ChainId::*, ChainStateFetcher, SourceId,
+};
+use crate::{ChainHeight, ChainState};
+use anyhow::{format_err, Result};
+use axum::async_trait;
+
+const DEFAULT_BASE_URL: &str = "https://chainz.cryptoid.info";
+
+/// chainz.cryptoid.info, covering many smaller UTXO chains
+pub struct CryptoId {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+}
+
+impl CryptoId {
+    pub fn new(
+        client: reqwest::Client,
+        base_url: Option<String>,
+        api_key: Option<String>,
+    ) -> Result<Self> {
+        Ok(Self {
+            client,
+            base_url: base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_owned()),
+            api_key,
+        })
+    }
+
+    fn coin_for_chain(chain: ChainId) -> &'static str {
+        match chain {
+            Groestlcoin => "grs",
+            Dash => "dash",
+            Litecoin => "ltc",
+            _ => unreachable!(),
+        }
+    }
+
+    /// Run a query of the `api.dws` endpoint, which replies in plain text
+    async fn query(&self, chain: ChainId, query: &[(&str, &str)]) -> Result<String> {
+        let mut req = self
+            .client
+            .get(format!(
+                "{}/{}/api.dws",
+                self.base_url,
+                Self::coin_for_chain(chain)
+            ))
+            .query(query);
+        if let Some(api_key) = self.api_key.as_ref() {
+            req = req.query(&[("key", api_key)]);
+        }
+
+        // the URL might have the key in it
+        Ok(req
+            .send_paced()
+            .await
+            .map_err(without_url)?
+            .error_for_status()
+            .map_err(reqwest::Error::without_url)?
+            .text()
+            .await
+            .map_err(reqwest::Error::without_url)?)
+    }
+}
+
+#[async_trait]
+impl ChainStateFetcher for CryptoId {
+    const ID: SourceId = SourceId::CryptoId;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &[Groestlcoin, Dash, Litecoin]
+    }
+
+    fn url_for_chain(&self, chain: ChainId) -> Option<String> {
+        Some(format!(
+            "https://chainz.cryptoid.info/{}/block.dws?{{height}}.htm",
+            Self::coin_for_chain(chain)
+        ))
+    }
+
+    async fn fetch(&self, chain: ChainId) -> Result<ChainState> {
+        let height: u64 = self
+            .query(chain, &[("q", "getblockcount")])
+            .await?
+            .trim()
+            .parse()?;
+        let hash = self
+            .query(
+                chain,
+                &[("q", "getblockhash"), ("height", &height.to_string())],
+            )
+            .await?;
+        let hash = hash.trim().trim_matches('"');
+        if hash.is_empty() {
+            return Err(format_err!("no hash returned for block {height}"));
+        }
+
+        Ok(ChainState {
+            hash: hash.into(),
+            height: ChainHeight::new(height),
+            ..Default::default()
+        })
+    }
+}
@@ -0,0 +1,190 @@
+//! Caching DNS resolver of the sources, optionally over HTTPS
+//!
+//! Resolved addresses are kept for `--dns-cache-secs`, and kept being used
+//! when looking them up again fails, so a resolver throttling lookups doesn't
+//! fail all the sources on a host at once.
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, format_err, Result};
+use hyper::client::connect::dns::Name;
+use reqwest::{
+    dns::{Addrs, Resolve, Resolving},
+    Url,
+};
+use serde::Deserialize;
+use tracing::{debug, warn};
+
+/// How long to keep using stale addresses before looking them up again,
+/// after a failed lookup
+const STALE_RETRY: Duration = Duration::from_secs(30);
+
+/// Record types of the DNS JSON API
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+
+struct Entry {
+    addrs: Vec<IpAddr>,
+    expires: Instant,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DohResponse {
+    status: u32,
+    #[serde(default)]
+    answer: Vec<DohAnswer>,
+}
+
+#[derive(Deserialize)]
+struct DohAnswer {
+    #[serde(rename = "type")]
+    record_type: u16,
+    data: String,
+}
+
+/// Resolver using the DNS JSON API, e.g. of `https://cloudflare-dns.com/dns-query`
+struct Doh {
+    /// Resolving the host of `url` with the system resolver
+    client: reqwest::Client,
+    url: Url,
+}
+
+impl Doh {
+    async fn lookup_type(&self, name: &str, record_type: u16) -> Result<Vec<IpAddr>> {
+        let response = self
+            .client
+            .get(self.url.clone())
+            .query(&[("name", name), ("type", &record_type.to_string())])
+            .header(reqwest::header::ACCEPT, "application/dns-json")
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<DohResponse>()
+            .await?;
+        if response.status != 0 {
+            bail!("DNS error {} looking up {name}", response.status);
+        }
+
+        Ok(response
+            .answer
+            .iter()
+            .filter(|answer| answer.record_type == record_type)
+            .filter_map(|answer| answer.data.parse().ok())
+            .collect())
+    }
+
+    async fn lookup(&self, name: &str) -> Result<Vec<IpAddr>> {
+        let (v4, v6) = futures::join!(
+            self.lookup_type(name, TYPE_A),
+            self.lookup_type(name, TYPE_AAAA)
+        );
+        // a failure of just one of them is fine
+        let addrs: Vec<_> = match (v4, v6) {
+            (Err(e), Err(_)) => return Err(e),
+            (v4, v6) => v4
+                .unwrap_or_default()
+                .into_iter()
+                .chain(v6.unwrap_or_default())
+                .collect(),
+        };
+        if addrs.is_empty() {
+            bail!("no addresses of {name}");
+        }
+        Ok(addrs)
+    }
+}
+
+struct Inner {
+    cache: Mutex<HashMap<String, Entry>>,
+    cache_for: Duration,
+    doh: Option<Doh>,
+}
+
+impl Inner {
+    async fn lookup(&self, name: &str) -> Result<Vec<IpAddr>> {
+        match self.doh.as_ref() {
+            Some(doh) => doh.lookup(name).await,
+            None => Ok(tokio::net::lookup_host((name, 0))
+                .await?
+                .map(|addr| addr.ip())
+                .collect()),
+        }
+    }
+
+    async fn resolve(&self, name: &str) -> Result<Vec<IpAddr>> {
+        let now = Instant::now();
+        let cached = self
+            .cache
+            .lock()
+            .expect("not poisoned")
+            .get(name)
+            .map(|entry| (entry.addrs.clone(), entry.expires));
+        if let Some((addrs, expires)) = cached.as_ref() {
+            if now < *expires {
+                return Ok(addrs.clone());
+            }
+        }
+
+        let (addrs, expires) = match (self.lookup(name).await, cached) {
+            (Ok(addrs), _) => {
+                debug!("Resolved {name} to {addrs:?}");
+                (addrs, now + self.cache_for)
+            }
+            (Err(e), Some((addrs, _))) => {
+                warn!("Couldn't look up {name}, still using {addrs:?}: {e}");
+                (addrs, now + STALE_RETRY)
+            }
+            (Err(e), None) => return Err(e),
+        };
+        self.cache.lock().expect("not poisoned").insert(
+            name.to_owned(),
+            Entry {
+                addrs: addrs.clone(),
+                expires,
+            },
+        );
+        Ok(addrs)
+    }
+}
+
+#[derive(Clone)]
+pub struct CachingResolver(Arc<Inner>);
+
+impl CachingResolver {
+    /// Cache lookups for `cache_secs`, done over HTTPS with the DNS JSON API
+    /// at `doh_url`, if set
+    pub fn new(cache_secs: u64, doh_url: Option<&str>) -> Result<Self> {
+        let doh = doh_url
+            .map(|url| -> Result<_> {
+                Ok(Doh {
+                    client: reqwest::Client::builder().build()?,
+                    url: Url::parse(url)
+                        .map_err(|e| format_err!("invalid DNS over HTTPS URL {url}: {e}"))?,
+                })
+            })
+            .transpose()?;
+
+        Ok(Self(Arc::new(Inner {
+            cache: Default::default(),
+            cache_for: Duration::from_secs(cache_secs),
+            doh,
+        })))
+    }
+}
+
+impl Resolve for CachingResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let inner = self.0.clone();
+        Box::pin(async move {
+            let addrs = inner.resolve(name.as_str()).await?;
+            // the port is set by the connector
+            let addrs: Addrs = Box::new(addrs.into_iter().map(|ip| SocketAddr::new(ip, 0)));
+            Ok(addrs)
+        })
+    }
+}
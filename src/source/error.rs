@@ -0,0 +1,114 @@
+use std::fmt;
+
+use serde::Serialize;
+use strum::IntoStaticStr;
+
+/// Broad category of a source failure
+///
+/// Meant to tell apart problems that fix themselves (rate limiting,
+/// upstream outages) from ones that need attention (schema changes).
+#[derive(Serialize, IntoStaticStr, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+#[strum(serialize_all = "snake_case")]
+pub enum SourceErrorKind {
+    /// Upstream asked us to slow down
+    RateLimited,
+    /// Response could not be decoded at all
+    Decode,
+    /// Response was well-formed, but not of the expected shape
+    SchemaChange,
+    /// Upstream failed on its own (5xx and alike)
+    Upstream,
+    /// Upstream didn't respond in time
+    Timeout,
+    /// Anything else, e.g. connection failures
+    Other,
+    /// Not a failure: upstream responded `304 Not Modified` to a conditional
+    /// request, see [`super::conditional`]
+    Unchanged,
+}
+
+#[derive(Debug, Clone)]
+pub struct SourceError {
+    pub kind: SourceErrorKind,
+    pub message: String,
+}
+
+impl SourceError {
+    pub fn new(kind: SourceErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+        }
+    }
+
+    /// Data the source used to return is missing or malformed
+    pub fn schema_change(message: impl Into<String>) -> Self {
+        Self::new(SourceErrorKind::SchemaChange, message)
+    }
+
+    fn kind_of_json_error(e: &serde_json::Error) -> SourceErrorKind {
+        match e.classify() {
+            serde_json::error::Category::Data => SourceErrorKind::SchemaChange,
+            _ => SourceErrorKind::Decode,
+        }
+    }
+
+    fn kind_of_reqwest_error(e: &reqwest::Error) -> SourceErrorKind {
+        if e.is_timeout() {
+            return SourceErrorKind::Timeout;
+        }
+        if let Some(status) = e.status() {
+            if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                return SourceErrorKind::RateLimited;
+            }
+            if status.is_server_error() {
+                return SourceErrorKind::Upstream;
+            }
+        }
+        if e.is_decode() {
+            return std::error::Error::source(e)
+                .and_then(|source| source.downcast_ref::<serde_json::Error>())
+                .map_or(SourceErrorKind::Decode, Self::kind_of_json_error);
+        }
+        SourceErrorKind::Other
+    }
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind: &str = self.kind.into();
+        write!(f, "{kind}: {}", self.message)
+    }
+}
+
+impl std::error::Error for SourceError {}
+
+/// Drop the URL from `e` if it's a [`reqwest::Error`], for requests with
+/// secrets in their query, so they don't end up in the logs and `/status`
+#[cfg_attr(not(feature = "all-sources"), allow(dead_code))]
+pub fn without_url(e: anyhow::Error) -> anyhow::Error {
+    match e.downcast::<reqwest::Error>() {
+        Ok(e) => e.without_url().into(),
+        Err(e) => e,
+    }
+}
+
+/// Classify an error returned by the code of a source
+impl From<anyhow::Error> for SourceError {
+    fn from(e: anyhow::Error) -> Self {
+        if let Some(e) = e.downcast_ref::<SourceError>() {
+            return e.clone();
+        }
+
+        let kind = if let Some(reqwest_error) = e.downcast_ref::<reqwest::Error>() {
+            Self::kind_of_reqwest_error(reqwest_error)
+        } else if let Some(json_error) = e.downcast_ref::<serde_json::Error>() {
+            Self::kind_of_json_error(json_error)
+        } else {
+            SourceErrorKind::Other
+        };
+
+        Self::new(kind, e.to_string())
+    }
+}
@@ -0,0 +1,69 @@
+//! Common bits of Esplora-compatible APIs (mempool.space, Blockstream, ...)
+use super::pacing::SendPaced;
+use anyhow::{bail, Result};
+use serde::Deserialize;
+
+use crate::{ChainHeight, ChainState};
+
+#[derive(Deserialize)]
+pub(crate) struct Block {
+    pub id: String,
+    pub height: u64,
+    pub timestamp: u64,
+    /// Not reported on every network (e.g. on Liquid)
+    #[cfg_attr(not(feature = "source-mempoolspace"), allow(dead_code))]
+    pub difficulty: Option<f64>,
+}
+
+/// Most recent blocks, starting with the tip
+///
+/// `api_url` is the base of the API, e.g. `https://mempool.space/testnet/api`
+pub(crate) async fn get_blocks(client: &reqwest::Client, api_url: &str) -> Result<Vec<Block>> {
+    Ok(client
+        .get(format!("{api_url}/blocks/"))
+        .send_paced()
+        .await?
+        .error_for_status()?
+        .json::<Vec<Block>>()
+        .await?)
+}
+
+pub(crate) async fn get_chain_state(client: &reqwest::Client, api_url: &str) -> Result<ChainState> {
+    let blocks = get_blocks(client, api_url).await?;
+
+    let tip = match blocks.first() {
+        Some(tip) => tip,
+        None => bail!("No blocks returned"),
+    };
+
+    Ok(ChainState {
+        height: ChainHeight::new(tip.height),
+        hash: tip.id.clone().into(),
+        block_time: Some(tip.timestamp),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::fixtures;
+
+    #[tokio::test]
+    async fn reads_the_tip_of_the_blocks() {
+        let client = reqwest::Client::new();
+        let state = fixtures::replay(
+            fixtures::test_dir(),
+            get_chain_state(&client, "https://mempool.space/api"),
+        )
+        .await
+        .expect("state");
+
+        assert_eq!(state.height, ChainHeight::new(866_000));
+        assert_eq!(
+            state.hash,
+            "00000000000000000001a7c8d51ae1f1d46ec1e52bc1e2d7d3b59e3e0ea5aa2b".into()
+        );
+        assert_eq!(state.block_time, Some(1_729_157_000));
+    }
+}
@@ -0,0 +1,183 @@
+use super::{pacing::SendPaced, ChainId, ChainId::*, ChainStateFetcher, SourceError, SourceId};
+use crate::{util::parse_hex_u64, ChainHeight, ChainState};
+use anyhow::{format_err, Result};
+use axum::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<RpcError>,
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct Block {
+    hash: String,
+    number: String,
+    timestamp: String,
+}
+
+async fn get_block_by_number(
+    client: &reqwest::Client,
+    url: &str,
+    block_tag: &str,
+) -> Result<Block> {
+    let resp = client
+        .post(url)
+        .json(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_getBlockByNumber",
+            "params": [block_tag, false],
+        }))
+        .send_paced()
+        .await?
+        .error_for_status()?
+        .json::<RpcResponse<Block>>()
+        .await?;
+
+    if let Some(error) = resp.error {
+        return Err(format_err!("rpc error {}: {}", error.code, error.message));
+    }
+
+    Ok(resp
+        .result
+        .ok_or_else(|| SourceError::schema_change(format!("no `{block_tag}` block returned")))?)
+}
+
+async fn get_tagged_height(
+    client: &reqwest::Client,
+    url: &str,
+    block_tag: &str,
+) -> Option<ChainHeight> {
+    match get_block_by_number(client, url, block_tag).await {
+        Ok(block) => ChainHeight::from_hex(&block.number).ok(),
+        Err(e) => {
+            tracing::debug!("Couldn't get `{block_tag}` block from {url}: {e}");
+            None
+        }
+    }
+}
+
+pub(crate) async fn get_chain_state(
+    client: &reqwest::Client,
+    url: &str,
+    with_finality_tags: bool,
+) -> Result<ChainState> {
+    let latest = get_block_by_number(client, url, "latest").await?;
+
+    let (safe_height, finalized_height) = if with_finality_tags {
+        futures::join!(
+            get_tagged_height(client, url, "safe"),
+            get_tagged_height(client, url, "finalized"),
+        )
+    } else {
+        (None, None)
+    };
+
+    Ok(ChainState {
+        hash: latest.hash.into(),
+        height: ChainHeight::from_hex(&latest.number)?,
+        block_time: Some(parse_hex_u64(&latest.timestamp)?),
+        safe_height,
+        finalized_height,
+        ..Default::default()
+    })
+}
+
+/// Public JSON-RPC endpoints of EVM chains
+pub struct EvmRpc {
+    client: reqwest::Client,
+}
+
+impl EvmRpc {
+    pub fn new(client: reqwest::Client) -> Result<Self> {
+        Ok(Self { client })
+    }
+
+    fn rpc_url_for_chain(chain: ChainId) -> &'static str {
+        match chain {
+            Ethereum => "https://ethereum.publicnode.com",
+            EthereumGoerliTestnet => "https://ethereum-goerli.publicnode.com",
+            EthereumSepoliaTestnet => "https://ethereum-sepolia.publicnode.com",
+            EthereumHoleskyTestnet => "https://ethereum-holesky.publicnode.com",
+            Arbitrum => "https://arbitrum-one.publicnode.com",
+            Optimism => "https://optimism.publicnode.com",
+            Base => "https://base.publicnode.com",
+            BnbSmartChain => "https://bsc.publicnode.com",
+            Gnosis => "https://gnosis.publicnode.com",
+            Fantom => "https://fantom.publicnode.com",
+            Avalanche => "https://api.avax.network/ext/bc/C/rpc",
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Does the chain support `safe` and `finalized` block tags
+pub(crate) fn has_finality_tags(chain: ChainId) -> bool {
+    matches!(
+        chain,
+        Ethereum
+            | EthereumGoerliTestnet
+            | EthereumSepoliaTestnet
+            | EthereumHoleskyTestnet
+            | Arbitrum
+            | Optimism
+            | Base
+            | BnbSmartChain
+            | Gnosis
+    )
+}
+
+#[async_trait]
+impl ChainStateFetcher for EvmRpc {
+    const ID: SourceId = SourceId::EvmRpc;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &[
+            Ethereum,
+            EthereumGoerliTestnet,
+            EthereumSepoliaTestnet,
+            EthereumHoleskyTestnet,
+            Arbitrum,
+            Optimism,
+            Base,
+            BnbSmartChain,
+            Gnosis,
+            Fantom,
+            Avalanche,
+        ]
+    }
+
+    fn url_for_chain(&self, chain: ChainId) -> Option<String> {
+        Some(
+            match chain {
+                Arbitrum => "https://arbiscan.io/block/{height}",
+                Optimism => "https://optimistic.etherscan.io/block/{height}",
+                Base => "https://basescan.org/block/{height}",
+                BnbSmartChain => "https://bscscan.com/block/{height}",
+                Gnosis => "https://gnosis.blockscout.com/block/{height}",
+                Fantom => "https://ftmscan.com/block/{height}",
+                Avalanche => "https://snowtrace.io/block/{height}",
+                _ => return None,
+            }
+            .to_owned(),
+        )
+    }
+
+    async fn fetch(&self, chain: ChainId) -> Result<ChainState> {
+        get_chain_state(
+            &self.client,
+            Self::rpc_url_for_chain(chain),
+            has_finality_tags(chain),
+        )
+        .await
+    }
+}
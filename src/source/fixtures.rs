@@ -0,0 +1,206 @@
+//! Recording responses of sources to fixture files, and replaying them
+//!
+//! With `--record-fixtures <dir>`, every response to a request sent with
+//! [`super::pacing::SendPaced`] is saved to a file under `dir`, named after
+//! the request. Tests run the parsers of the sources against them in
+//! [`replay`], so a renamed field shows up as a failing test, without
+//! depending on the explorers being up.
+#![cfg_attr(not(all(test, feature = "test-util")), allow(dead_code))]
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{format_err, Context, Result};
+use once_cell::sync::OnceCell;
+use reqwest::{Request, Response};
+use serde::{Deserialize, Serialize};
+
+/// Query parameters left out of the file names, as they hold secrets
+const SECRET_PARAMS: &[&str] = &["key", "apikey", "api_key", "token"];
+
+/// Where to record responses to, see [`record_to`]
+static RECORD_DIR: OnceCell<PathBuf> = OnceCell::new();
+
+tokio::task_local! {
+    /// Where to replay responses from, see [`replay`]
+    static REPLAY_DIR: PathBuf;
+}
+
+#[derive(Serialize, Deserialize)]
+struct Fixture {
+    method: String,
+    url: String,
+    status: u16,
+    #[serde(default)]
+    headers: BTreeMap<String, String>,
+    body: String,
+}
+
+/// FNV-1a, to tell apart requests to the same URL by their body, e.g. JSON-RPC
+/// calls, the same way on every platform and Rust version
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// URL of `request`, without the secrets in its query
+fn redacted_url(request: &Request) -> reqwest::Url {
+    let mut url = request.url().clone();
+    let query: Vec<_> = url
+        .query_pairs()
+        .filter(|(name, _)| !SECRET_PARAMS.contains(&name.as_ref()))
+        .map(|(name, value)| (name.into_owned(), value.into_owned()))
+        .collect();
+    url.set_query(None);
+    if !query.is_empty() {
+        url.query_pairs_mut().extend_pairs(query);
+    }
+    url
+}
+
+/// File of the fixture of `request`, under `dir`
+fn path_of(dir: &Path, request: &Request) -> PathBuf {
+    let url = redacted_url(request);
+    let mut name = format!("{} {}", request.method(), url.path());
+    if let Some(query) = url.query() {
+        name = format!("{name}?{query}");
+    }
+    let mut name: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if let Some(body) = request.body().and_then(|body| body.as_bytes()) {
+        name = format!("{name}-{:016x}", fnv1a(body));
+    }
+
+    dir.join(url.host_str().unwrap_or("localhost"))
+        .join(format!("{name}.json"))
+}
+
+/// Record all the responses from now on to fixtures under `dir`
+pub fn record_to(dir: PathBuf) -> Result<()> {
+    RECORD_DIR
+        .set(dir)
+        .map_err(|_| format_err!("already recording fixtures"))
+}
+
+/// Save the `response` to `request`, if recording, and give it back
+pub async fn record(request: &Request, response: Response) -> Result<Response> {
+    let dir = match RECORD_DIR.get() {
+        Some(dir) => dir,
+        None => return Ok(response),
+    };
+
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = response.bytes().await?;
+    let fixture = Fixture {
+        method: request.method().to_string(),
+        url: redacted_url(request).to_string(),
+        status: status.as_u16(),
+        headers: headers
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| (reqwest::header::CONTENT_TYPE.to_string(), value.to_owned()))
+            .into_iter()
+            .collect(),
+        body: String::from_utf8_lossy(&body).into_owned(),
+    };
+    let path = path_of(dir, request);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, serde_json::to_string_pretty(&fixture)?)
+        .with_context(|| format!("can't record fixture {}", path.display()))?;
+
+    let mut rebuilt = hyper::Response::new(body.to_vec());
+    *rebuilt.status_mut() = status;
+    *rebuilt.headers_mut() = headers;
+    Ok(Response::from(rebuilt))
+}
+
+/// Response to `request` from the fixtures being replayed, if in [`replay`]
+pub fn replayed(request: &Request) -> Option<Result<Response>> {
+    let path = REPLAY_DIR.try_with(|dir| path_of(dir, request)).ok()?;
+    Some((|| {
+        let fixture: Fixture = serde_json::from_str(
+            &std::fs::read_to_string(&path)
+                .with_context(|| format!("no fixture {}", path.display()))?,
+        )?;
+        let mut response = hyper::Response::new(fixture.body.into_bytes());
+        *response.status_mut() = reqwest::StatusCode::from_u16(fixture.status)?;
+        for (name, value) in fixture.headers {
+            response.headers_mut().insert(
+                reqwest::header::HeaderName::from_bytes(name.as_bytes())?,
+                value.parse()?,
+            );
+        }
+        Ok(Response::from(response))
+    })())
+}
+
+/// Run `fut`, answering all the requests in it from the fixtures under `dir`
+/// instead of sending them
+pub async fn replay<T>(dir: impl Into<PathBuf>, fut: impl Future<Output = T>) -> T {
+    REPLAY_DIR.scope(dir.into(), fut).await
+}
+
+/// Fixtures of the tests, kept in the repository
+#[cfg(test)]
+pub fn test_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn secrets_are_left_out_of_the_names() {
+        let request = reqwest::Client::new()
+            .get("https://api.blockchair.com/bitcoin/stats?key=secret&limit=1")
+            .build()
+            .expect("request");
+
+        assert_eq!(
+            path_of(Path::new("fixtures"), &request),
+            Path::new("fixtures/api.blockchair.com/GET__bitcoin_stats_limit_1.json")
+        );
+        assert_eq!(
+            redacted_url(&request).as_str(),
+            "https://api.blockchair.com/bitcoin/stats?limit=1"
+        );
+    }
+
+    #[test]
+    fn bodies_tell_requests_apart() {
+        let client = reqwest::Client::new();
+        let request = |body: &'static str| {
+            client
+                .post("https://rpc.example.com/")
+                .body(body)
+                .build()
+                .expect("request")
+        };
+
+        let dir = Path::new("fixtures");
+        assert_ne!(
+            path_of(dir, &request(r#"{"method":"getblockcount"}"#)),
+            path_of(dir, &request(r#"{"method":"getbestblockhash"}"#))
+        );
+    }
+
+    #[tokio::test]
+    async fn missing_fixtures_fail_the_request() {
+        let request = reqwest::Client::new()
+            .get("https://mempool.space/api/blocks/tip/height")
+            .build()
+            .expect("request");
+
+        let response = replay(test_dir(), async { replayed(&request) }).await;
+        assert!(matches!(response, Some(Err(_))));
+    }
+}
@@ -0,0 +1,101 @@
+use super::{pacing::SendPaced, ChainId, ChainId::*, ChainStateFetcher, SourceError, SourceId};
+use crate::{ChainHeight, ChainState};
+use anyhow::{format_err, Result};
+use axum::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TipSet {
+    cids: Vec<Cid>,
+    blocks: Vec<BlockHeader>,
+    height: u64,
+}
+
+#[derive(Deserialize)]
+struct Cid {
+    #[serde(rename = "/")]
+    cid: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct BlockHeader {
+    timestamp: u64,
+}
+
+const DEFAULT_BASE_URL: &str = "https://api.node.glif.io";
+
+/// Public Lotus API hosted by Glif
+///
+/// Filecoin produces tipsets of several blocks at the same height; the CID
+/// of the first one stands for the hash of the whole tipset.
+pub struct Glif {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl Glif {
+    pub fn new(client: reqwest::Client, base_url: Option<String>) -> Result<Self> {
+        Ok(Self {
+            client,
+            base_url: base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_owned()),
+        })
+    }
+}
+
+#[async_trait]
+impl ChainStateFetcher for Glif {
+    const ID: SourceId = SourceId::Glif;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &[Filecoin]
+    }
+
+    fn url_for_chain(&self, _chain: ChainId) -> Option<String> {
+        Some("https://filfox.info/en/tipset/{height}".to_owned())
+    }
+
+    async fn fetch(&self, _chain: ChainId) -> Result<ChainState> {
+        let resp = self
+            .client
+            .post(format!("{}/rpc/v1", self.base_url))
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "Filecoin.ChainHead",
+                "params": [],
+            }))
+            .send_paced()
+            .await?
+            .error_for_status()?
+            .json::<RpcResponse<TipSet>>()
+            .await?;
+
+        if let Some(error) = resp.error {
+            return Err(format_err!("rpc error: {error}"));
+        }
+        let tipset = resp
+            .result
+            .ok_or_else(|| SourceError::schema_change("no tipset returned"))?;
+        let cid = tipset
+            .cids
+            .into_iter()
+            .next()
+            .ok_or_else(|| SourceError::schema_change("empty tipset"))?;
+
+        Ok(ChainState {
+            hash: cid.cid.into(),
+            height: ChainHeight::new(tipset.height),
+            block_time: tipset.blocks.first().map(|block| block.timestamp),
+            ..Default::default()
+        })
+    }
+}
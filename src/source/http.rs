@@ -0,0 +1,107 @@
+//! HTTP clients of the sources
+#![cfg_attr(not(feature = "all-sources"), allow(dead_code))]
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+
+use anyhow::{format_err, Result};
+use reqwest::header::{HeaderMap, HeaderValue};
+
+use super::{dns::CachingResolver, SourceId};
+use crate::opts::{Opts, PinnedAddress, SourceHeader, SourceUserAgent};
+
+/// Settings shared by the clients of all sources
+pub(crate) struct Clients {
+    resolver: Option<Arc<CachingResolver>>,
+    pinned_addresses: Vec<PinnedAddress>,
+    pool_idle_timeout: Duration,
+    pool_max_idle_per_host: Option<usize>,
+    timeout: Duration,
+    connect_timeout: Duration,
+    tcp_keepalive: Option<Duration>,
+    http2_prior_knowledge: Vec<SourceId>,
+    user_agent: String,
+    source_user_agents: Vec<SourceUserAgent>,
+    source_headers: Vec<SourceHeader>,
+}
+
+impl Clients {
+    pub fn new(opts: &Opts) -> Result<Self> {
+        let resolver = if opts.dns_cache_secs.is_some() || opts.dns_over_https.is_some() {
+            Some(Arc::new(CachingResolver::new(
+                opts.dns_cache_secs.unwrap_or(0),
+                opts.dns_over_https.as_deref(),
+            )?))
+        } else {
+            None
+        };
+
+        Ok(Self {
+            resolver,
+            pinned_addresses: opts.pinned_addresses.clone(),
+            pool_idle_timeout: Duration::from_secs(opts.http_pool_idle_timeout_secs),
+            pool_max_idle_per_host: opts.http_pool_max_idle_per_host,
+            timeout: Duration::from_secs(opts.http_timeout_secs),
+            connect_timeout: Duration::from_secs(opts.http_connect_timeout_secs),
+            tcp_keepalive: opts.http_tcp_keepalive_secs.map(Duration::from_secs),
+            http2_prior_knowledge: opts.http2_prior_knowledge.clone(),
+            user_agent: opts.user_agent.clone(),
+            source_user_agents: opts.source_user_agents.clone(),
+            source_headers: opts.source_headers.clone(),
+        })
+    }
+
+    /// Client of `source`
+    pub fn get(&self, source: SourceId) -> Result<reqwest::Client> {
+        let user_agent = self
+            .source_user_agents
+            .iter()
+            .rev()
+            .find(|user_agent| user_agent.source == source)
+            .map_or(&self.user_agent, |user_agent| &user_agent.user_agent);
+        let mut headers = HeaderMap::new();
+        for header in self
+            .source_headers
+            .iter()
+            .filter(|header| header.source == source)
+        {
+            let value =
+                crate::util::load_secret(&header.value, &format!("{} header", header.name))?;
+            headers.append(
+                header.name.clone(),
+                HeaderValue::from_str(&value)
+                    .map_err(|e| format_err!("invalid value of the {} header: {e}", header.name))?,
+            );
+        }
+
+        let mut builder = reqwest::Client::builder()
+            .user_agent(user_agent)
+            .default_headers(headers)
+            .pool_idle_timeout(self.pool_idle_timeout)
+            .timeout(self.timeout)
+            .connect_timeout(self.connect_timeout)
+            .tcp_keepalive(self.tcp_keepalive);
+        if let Some(max_idle) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+        if self.http2_prior_knowledge.contains(&source) {
+            builder = builder.http2_prior_knowledge();
+        }
+        if let Some(resolver) = self.resolver.as_ref() {
+            builder = builder.dns_resolver(resolver.clone());
+        }
+        for pinned in self
+            .pinned_addresses
+            .iter()
+            .filter(|pinned| pinned.source == source)
+        {
+            // the port is taken from the URL
+            let addrs: Vec<_> = pinned
+                .addrs
+                .iter()
+                .map(|ip| SocketAddr::new(*ip, 0))
+                .collect();
+            builder = builder.resolve_to_addrs(&pinned.host, &addrs);
+        }
+
+        Ok(builder.build()?)
+    }
+}
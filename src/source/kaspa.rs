@@ -0,0 +1,69 @@
+use super::{pacing::SendPaced, ChainId, ChainId::*, ChainStateFetcher, SourceError, SourceId};
+use crate::{ChainHeight, ChainState};
+use anyhow::Result;
+use axum::async_trait;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BlockDagInfo {
+    tip_hashes: Vec<String>,
+    /// In milliseconds
+    #[serde(deserialize_with = "crate::util::deserialize_number_from_string")]
+    past_median_time: u64,
+    #[serde(deserialize_with = "crate::util::deserialize_number_from_string")]
+    virtual_daa_score: u64,
+}
+
+const DEFAULT_BASE_URL: &str = "https://api.kaspa.org";
+
+/// Public Kaspa REST API
+///
+/// Kaspa is a blockDAG without a single tip, so the DAA score of the virtual
+/// block is used as the height, and one of the tips as the hash.
+pub struct KaspaApi {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl KaspaApi {
+    pub fn new(client: reqwest::Client, base_url: Option<String>) -> Result<Self> {
+        Ok(Self {
+            client,
+            base_url: base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_owned()),
+        })
+    }
+}
+
+#[async_trait]
+impl ChainStateFetcher for KaspaApi {
+    const ID: SourceId = SourceId::KaspaApi;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &[Kaspa]
+    }
+
+    async fn fetch(&self, _chain: ChainId) -> Result<ChainState> {
+        let info = self
+            .client
+            .get(format!("{}/info/blockdag", self.base_url))
+            .send_paced()
+            .await?
+            .error_for_status()?
+            .json::<BlockDagInfo>()
+            .await?;
+
+        let hash = info
+            .tip_hashes
+            .into_iter()
+            .next()
+            .ok_or_else(|| SourceError::schema_change("no tips returned"))?;
+
+        Ok(ChainState {
+            hash: hash.into(),
+            height: ChainHeight::new(info.virtual_daa_score),
+            block_time: Some(info.past_median_time / 1000),
+            ..Default::default()
+        })
+    }
+}
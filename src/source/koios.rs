@@ -0,0 +1,65 @@
+use super::{pacing::SendPaced, ChainId, ChainId::*, ChainStateFetcher, SourceError, SourceId};
+use crate::{ChainHeight, ChainState};
+use anyhow::Result;
+use axum::async_trait;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct Tip {
+    hash: String,
+    block_no: u64,
+    abs_slot: u64,
+    block_time: u64,
+}
+
+const DEFAULT_BASE_URL: &str = "https://api.koios.rest";
+
+/// Koios, the community-run Cardano API
+pub struct Koios {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl Koios {
+    pub fn new(client: reqwest::Client, base_url: Option<String>) -> Result<Self> {
+        Ok(Self {
+            client,
+            base_url: base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_owned()),
+        })
+    }
+}
+
+#[async_trait]
+impl ChainStateFetcher for Koios {
+    const ID: SourceId = SourceId::Koios;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &[Cardano]
+    }
+
+    fn url_for_chain(&self, _chain: ChainId) -> Option<String> {
+        Some("https://cardanoscan.io/block/{height}".to_owned())
+    }
+
+    async fn fetch(&self, _chain: ChainId) -> Result<ChainState> {
+        let tip = self
+            .client
+            .get(format!("{}/api/v1/tip", self.base_url))
+            .send_paced()
+            .await?
+            .error_for_status()?
+            .json::<Vec<Tip>>()
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| SourceError::schema_change("no tip returned"))?;
+
+        Ok(ChainState {
+            hash: tip.hash.into(),
+            height: ChainHeight::new(tip.block_no),
+            block_time: Some(tip.block_time),
+            secondary_height: Some(ChainHeight::new(tip.abs_slot)),
+            ..Default::default()
+        })
+    }
+}
@@ -1,69 +1,118 @@
-use super::{ChainId, ChainId::*, SourceId};
-use crate::{ChainState, ChainStateUpdate, ChainUpdateRecorder};
+use super::{
+    esplora, pacing::SendPaced, ChainId, ChainId::*, ChainStateFetcher, PollingPolicy, SourceId,
+};
+use crate::{ChainHeight, ChainState, DifficultyStats, MempoolStats};
 use anyhow::{bail, Result};
 use axum::async_trait;
-use rand::{seq::SliceRandom, thread_rng};
 use serde::Deserialize;
 
 #[derive(Deserialize)]
-struct Block {
-    id: String,
-    height: u64,
+struct Mempool {
+    count: u64,
+    vsize: u64,
 }
-pub(crate) async fn get_chain_state(
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecommendedFees {
+    minimum_fee: f64,
+}
+
+const DEFAULT_BASE_URL: &str = "https://mempool.space";
+
+pub(crate) async fn get_mempool_stats(
     client: &reqwest::Client,
-    chain_prefix: &str,
-) -> Result<ChainState> {
-    let resp = client
-        .get(format!("https://mempool.space/{chain_prefix}api/blocks/"))
-        .send()
+    api_url: &str,
+) -> Result<MempoolStats> {
+    let mempool = client
+        .get(format!("{api_url}/mempool"))
+        .send_paced()
         .await?
         .error_for_status()?
-        .json::<Vec<Block>>()
+        .json::<Mempool>()
         .await?;
 
-    if resp.is_empty() {
-        bail!("No blocks returned");
-    }
+    let fees = client
+        .get(format!("{api_url}/v1/fees/recommended"))
+        .send_paced()
+        .await?
+        .error_for_status()?
+        .json::<RecommendedFees>()
+        .await?;
 
-    Ok(ChainState {
-        height: resp[0].height,
-        hash: resp[0].id.clone(),
+    Ok(MempoolStats {
+        tx_count: mempool.count,
+        vsize: mempool.vsize,
+        min_fee_rate: Some(fees.minimum_fee),
     })
 }
 
-async fn get_updates(
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DifficultyAdjustment {
+    difficulty_change: f64,
+    remaining_blocks: u64,
+    next_retarget_height: u64,
+}
+
+pub(crate) async fn get_difficulty_stats(
     client: &reqwest::Client,
-    chain: ChainId,
-    chain_prefix: &str,
-) -> Option<ChainStateUpdate> {
-    match get_chain_state(client, chain_prefix).await {
-        Ok(state) => Some(ChainStateUpdate {
-            source: SourceId::MempoolSpace,
-            chain: chain.into(),
-            state,
-        }),
-        Err(e) => {
-            let chain_name: &str = chain.into();
-            tracing::warn!("Couldn't update MempoolSpace  {chain_name}: {e}");
-            None
-        }
-    }
+    api_url: &str,
+) -> Result<DifficultyStats> {
+    let blocks = esplora::get_blocks(client, api_url).await?;
+
+    let difficulty = match blocks.first().and_then(|block| block.difficulty) {
+        Some(difficulty) => difficulty,
+        None => bail!("No difficulty returned"),
+    };
+
+    let adjustment = client
+        .get(format!("{api_url}/v1/difficulty-adjustment"))
+        .send_paced()
+        .await?
+        .error_for_status()?
+        .json::<DifficultyAdjustment>()
+        .await?;
+
+    Ok(DifficultyStats {
+        difficulty,
+        next_adjustment_height: Some(ChainHeight::new(adjustment.next_retarget_height)),
+        remaining_blocks: Some(adjustment.remaining_blocks),
+        expected_change_pct: Some(adjustment.difficulty_change),
+    })
 }
 
 pub struct MempoolSpace {
     client: reqwest::Client,
+    /// Of both the API and the explorer
+    base_url: String,
+    track_mempool: bool,
+    track_difficulty: bool,
 }
 
 impl MempoolSpace {
-    pub fn new() -> Result<Self> {
+    pub fn new(
+        client: reqwest::Client,
+        base_url: Option<String>,
+        track_mempool: bool,
+        track_difficulty: bool,
+    ) -> Result<Self> {
         Ok(Self {
-            client: reqwest::Client::builder()
-                .user_agent("curl/7.79.1")
-                .build()?,
+            client,
+            base_url: base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_owned()),
+            track_mempool,
+            track_difficulty,
         })
     }
 
+    fn api_url(&self, chain: ChainId) -> String {
+        format!(
+            "{}/{}api",
+            self.base_url,
+            Self::get_api_prefix_for_chain(chain)
+        )
+    }
+
     fn get_api_prefix_for_chain(chain: ChainId) -> &'static str {
         match chain {
             Bitcoin => "",
@@ -75,26 +124,37 @@ impl MempoolSpace {
 }
 
 #[async_trait]
-impl super::StaticSource for MempoolSpace {
+impl ChainStateFetcher for MempoolSpace {
     const ID: SourceId = SourceId::MempoolSpace;
-    const SUPPORTED_CHAINS: &'static [ChainId] = &[Bitcoin, BitcoinTestnet, BitcoinSignet];
-
-    async fn check_updates(&self, recorder: &dyn ChainUpdateRecorder) {
-        // randomize the order to give all chains a chance, even in the presence
-        // of rate limiting
-        let mut supported_chains = Self::SUPPORTED_CHAINS.to_vec();
-        supported_chains.shuffle(&mut thread_rng());
-
-        for chain_id in supported_chains {
-            if let Some(update) = get_updates(
-                &self.client,
-                chain_id,
-                Self::get_api_prefix_for_chain(chain_id),
-            )
-            .await
-            {
-                recorder.update(update).await;
-            }
+    const POLLING: PollingPolicy = PollingPolicy::Always;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &[Bitcoin, BitcoinTestnet, BitcoinSignet]
+    }
+
+    fn url_for_chain(&self, chain: ChainId) -> Option<String> {
+        Some(format!(
+            "{}/{}block/{{hash}}",
+            self.base_url,
+            Self::get_api_prefix_for_chain(chain)
+        ))
+    }
+
+    async fn fetch(&self, chain: ChainId) -> Result<ChainState> {
+        esplora::get_chain_state(&self.client, &self.api_url(chain)).await
+    }
+
+    async fn fetch_mempool_stats(&self, chain: ChainId) -> Option<Result<MempoolStats>> {
+        if !self.track_mempool {
+            return None;
+        }
+        Some(get_mempool_stats(&self.client, &self.api_url(chain)).await)
+    }
+
+    async fn fetch_difficulty_stats(&self, chain: ChainId) -> Option<Result<DifficultyStats>> {
+        if !self.track_difficulty {
+            return None;
         }
+        Some(get_difficulty_stats(&self.client, &self.api_url(chain)).await)
     }
 }
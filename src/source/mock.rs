@@ -0,0 +1,228 @@
+//! A source driven by the test using it
+#![cfg_attr(not(test), allow(dead_code))]
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
+
+use super::{ChainId, SourceError, SourceId};
+use crate::{ChainState, ChainStateUpdate, ChainUpdateRecorder};
+use axum::async_trait;
+use tokio::sync::Mutex;
+
+/// Something for a [`MockSource`] to report in its next round
+pub enum MockEvent {
+    Update(ChainId, ChainState),
+    Error(Option<ChainId>, SourceError),
+}
+
+/// Handle to queue up what a [`MockSource`] reports, kept by the test
+#[derive(Clone, Default)]
+pub struct MockHandle(Arc<Mutex<VecDeque<MockEvent>>>);
+
+impl MockHandle {
+    pub async fn push(&self, event: MockEvent) {
+        self.0.lock().await.push_back(event);
+    }
+
+    pub async fn push_state(&self, chain: ChainId, state: ChainState) {
+        self.push(MockEvent::Update(chain, state)).await;
+    }
+
+    /// Events not reported yet
+    pub async fn pending(&self) -> usize {
+        self.0.lock().await.len()
+    }
+}
+
+/// Source reporting whatever was pushed to its [`MockHandle`], as `source`
+pub struct MockSource {
+    source: SourceId,
+    chains: HashSet<ChainId>,
+    events: MockHandle,
+}
+
+impl MockSource {
+    pub fn new(source: SourceId, chains: &[ChainId]) -> (Self, MockHandle) {
+        let events = MockHandle::default();
+        (
+            Self {
+                source,
+                chains: chains.iter().copied().collect(),
+                events: events.clone(),
+            },
+            events,
+        )
+    }
+}
+
+#[async_trait]
+impl super::Source for MockSource {
+    fn get_supported_chains(&self) -> HashSet<ChainId> {
+        self.chains.clone()
+    }
+
+    fn get_supported_sources(&self) -> HashSet<SourceId> {
+        HashSet::from_iter([self.source])
+    }
+
+    fn url_for_chain(&self, _source: SourceId, _chain: ChainId) -> Option<String> {
+        None
+    }
+
+    /// Rounds are paced by the test pushing events
+    fn update_interval(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    /// Report all the events queued up so far, in order
+    async fn check_updates(&self, recorder: &dyn ChainUpdateRecorder) {
+        let events: Vec<_> = self.events.0.lock().await.drain(..).collect();
+        for event in events {
+            match event {
+                MockEvent::Update(chain, state) => {
+                    recorder
+                        .update(ChainStateUpdate {
+                            source: self.source,
+                            chain,
+                            state,
+                        })
+                        .await
+                }
+                MockEvent::Error(chain, error) => {
+                    recorder.report_error(self.source, chain, error).await
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::{
+        clock::MockClock,
+        opts::Opts,
+        source::{Source, SourceErrorKind},
+        AppState, BlockHash, ChainHeight,
+    };
+
+    const NOW_TS: u64 = 1_700_000_000;
+
+    fn state(height: u64, block_time: u64) -> ChainState {
+        let height = ChainHeight::new(height);
+        ChainState {
+            hash: BlockHash::from_height(height),
+            height,
+            block_time: Some(block_time),
+            ..Default::default()
+        }
+    }
+
+    async fn app_state_with(sources: &[&MockSource]) -> AppState {
+        let mut app_state = AppState::new(&Opts::parse_from(["chain-monitor"]));
+        for source in sources {
+            app_state.add_source(source.source);
+            app_state.add_chains(source.chains.clone());
+        }
+        app_state
+    }
+
+    #[tokio::test]
+    async fn records_the_best_state_and_tip_age() {
+        let clock = MockClock::install(NOW_TS);
+        let (source, handle) = MockSource::new(SourceId::Blockstream, &[ChainId::Bitcoin]);
+        let app_state = app_state_with(&[&source]).await;
+
+        handle
+            .push_state(ChainId::Bitcoin, state(800_000, NOW_TS - 60))
+            .await;
+        source.check_updates(&app_state).await;
+        assert_eq!(handle.pending().await, 0);
+
+        let best_states = app_state.get_best_states(None).await;
+        let best = &best_states["btc"];
+        assert_eq!(best.source, SourceId::Blockstream);
+        assert_eq!(best.state.state.height, ChainHeight::new(800_000));
+
+        clock.advance(30);
+        assert_eq!(app_state.tip_age_secs(ChainId::Bitcoin).await, Some(90));
+    }
+
+    #[tokio::test]
+    async fn keeps_the_highest_state_of_all_sources() {
+        let _clock = MockClock::install(NOW_TS);
+        let (ahead, ahead_handle) = MockSource::new(SourceId::MempoolSpace, &[ChainId::Bitcoin]);
+        let (behind, behind_handle) = MockSource::new(SourceId::Blockstream, &[ChainId::Bitcoin]);
+        let app_state = app_state_with(&[&ahead, &behind]).await;
+
+        ahead_handle
+            .push_state(ChainId::Bitcoin, state(800_001, NOW_TS))
+            .await;
+        behind_handle
+            .push_state(ChainId::Bitcoin, state(800_000, NOW_TS - 600))
+            .await;
+        ahead.check_updates(&app_state).await;
+        behind.check_updates(&app_state).await;
+
+        let best_states = app_state.get_best_states(None).await;
+        assert_eq!(best_states["btc"].source, SourceId::MempoolSpace);
+        assert_eq!(
+            app_state
+                .how_far_behind(SourceId::Blockstream, ChainId::Bitcoin)
+                .await,
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn confirms_unchanged_states_without_republishing_them() {
+        let clock = MockClock::install(NOW_TS);
+        let (source, handle) = MockSource::new(SourceId::Blockstream, &[ChainId::Bitcoin]);
+        let app_state = app_state_with(&[&source]).await;
+        let shard = &app_state.chain_states[&ChainId::Bitcoin];
+
+        handle
+            .push_state(ChainId::Bitcoin, state(800_000, NOW_TS - 60))
+            .await;
+        source.check_updates(&app_state).await;
+        let published = shard.snapshot();
+
+        clock.advance(30);
+        handle
+            .push_state(ChainId::Bitcoin, state(800_000, NOW_TS - 60))
+            .await;
+        source.check_updates(&app_state).await;
+
+        assert!(Arc::ptr_eq(&published, &shard.snapshot()));
+        let best_states = app_state.get_best_states(None).await;
+        assert_eq!(best_states["btc"].state.last_checked_ts.get(), NOW_TS + 30);
+    }
+
+    #[tokio::test]
+    async fn records_errors_in_the_source_status() {
+        let _clock = MockClock::install(NOW_TS);
+        let (source, handle) = MockSource::new(SourceId::Blockstream, &[ChainId::Bitcoin]);
+        let app_state = app_state_with(&[&source]).await;
+
+        handle
+            .push(MockEvent::Error(
+                Some(ChainId::Bitcoin),
+                SourceError::schema_change("no height"),
+            ))
+            .await;
+        source.check_updates(&app_state).await;
+
+        let statuses = app_state.get_source_statuses().await;
+        let status = statuses.values().next().expect("status of the source");
+        assert_eq!(status.errors_total, 1);
+        assert_eq!(status.schema_changes_total, 1);
+        let last_error = status.last_error.as_ref().expect("last error");
+        assert_eq!(last_error.kind, SourceErrorKind::SchemaChange);
+        assert_eq!(last_error.chain, Some(ChainId::Bitcoin));
+        assert_eq!(last_error.ts, NOW_TS);
+    }
+}
@@ -0,0 +1,94 @@
+use super::{pacing::SendPaced, ChainId, ChainId::*, ChainStateFetcher, SourceError, SourceId};
+use crate::{ChainHeight, ChainState};
+use anyhow::{format_err, Result};
+use axum::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct Block {
+    header: BlockHeader,
+}
+
+#[derive(Deserialize)]
+struct BlockHeader {
+    height: u64,
+    hash: String,
+    /// In nanoseconds
+    timestamp: u64,
+}
+
+const DEFAULT_BASE_URL: &str = "https://rpc.mainnet.near.org";
+
+/// Public NEAR RPC endpoints
+pub struct NearRpc {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl NearRpc {
+    pub fn new(client: reqwest::Client, base_url: Option<String>) -> Result<Self> {
+        Ok(Self {
+            client,
+            base_url: base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_owned()),
+        })
+    }
+
+    /// Latest block of a given `finality` (`optimistic` or `final`)
+    async fn get_block(&self, finality: &str) -> Result<Block> {
+        let resp = self
+            .client
+            .post(&self.base_url)
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": "chain-monitor",
+                "method": "block",
+                "params": { "finality": finality },
+            }))
+            .send_paced()
+            .await?
+            .error_for_status()?
+            .json::<RpcResponse<Block>>()
+            .await?;
+
+        if let Some(error) = resp.error {
+            return Err(format_err!("rpc error: {error}"));
+        }
+
+        Ok(resp
+            .result
+            .ok_or_else(|| SourceError::schema_change(format!("no `{finality}` block returned")))?)
+    }
+}
+
+#[async_trait]
+impl ChainStateFetcher for NearRpc {
+    const ID: SourceId = SourceId::NearRpc;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &[Near]
+    }
+
+    fn url_for_chain(&self, _chain: ChainId) -> Option<String> {
+        Some("https://nearblocks.io/blocks/{hash}".to_owned())
+    }
+
+    async fn fetch(&self, _chain: ChainId) -> Result<ChainState> {
+        let (optimistic, finalized) =
+            futures::try_join!(self.get_block("optimistic"), self.get_block("final"))?;
+
+        Ok(ChainState {
+            hash: optimistic.header.hash.into(),
+            height: ChainHeight::new(optimistic.header.height),
+            block_time: Some(optimistic.header.timestamp / 1_000_000_000),
+            finalized_height: Some(ChainHeight::new(finalized.header.height)),
+            ..Default::default()
+        })
+    }
+}
@@ -0,0 +1,180 @@
+use std::{collections::HashSet, time::Duration};
+
+use super::{
+    evm,
+    websocket::{Protocol, SubscribeReply, Subscription},
+    ChainId, SourceId,
+};
+use crate::{util::parse_hex_u64, ChainHeight, ChainState, ChainStateUpdate, ChainUpdateRecorder};
+use anyhow::{bail, format_err, Result};
+use axum::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Deserialize)]
+struct SubscribeResponse {
+    result: Option<String>,
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct Notification {
+    params: NotificationParams,
+}
+
+#[derive(Deserialize)]
+struct NotificationParams {
+    result: Head,
+}
+
+#[derive(Deserialize)]
+struct Head {
+    hash: String,
+    number: String,
+    timestamp: String,
+}
+
+impl Head {
+    fn to_chain_state(&self) -> Result<ChainState> {
+        Ok(ChainState {
+            hash: self.hash.as_str().into(),
+            height: ChainHeight::from_hex(&self.number)?,
+            block_time: Some(parse_hex_u64(&self.timestamp)?),
+            ..Default::default()
+        })
+    }
+}
+
+/// A self-hosted EVM node
+struct Node {
+    http_url: String,
+    /// Subscription to `newHeads`
+    subscription: Subscription,
+}
+
+impl Node {
+    /// Parse `<ticker>=<ws-url>[,<http-url>]`
+    fn from_entry(entry: &str) -> Result<Self> {
+        let (ticker, urls) = entry
+            .split_once('=')
+            .ok_or_else(|| format_err!("expected `<ticker>=<ws-url>[,<http-url>]`: {entry}"))?;
+        let chain =
+            ChainId::from_ticker(ticker).ok_or_else(|| format_err!("unknown ticker: {ticker}"))?;
+        let (ws_url, http_url) = match urls.split_once(',') {
+            Some((ws_url, http_url)) => (ws_url.to_owned(), http_url.to_owned()),
+            None => (urls.to_owned(), urls.replacen("ws", "http", 1)),
+        };
+
+        Ok(Self {
+            http_url,
+            subscription: Subscription::new(SourceId::EvmNode, chain, ws_url),
+        })
+    }
+}
+
+/// Self-hosted EVM nodes (Geth, Erigon, ...), pushing new blocks over websocket
+///
+/// Unlike other sources, it keeps listening during `check_updates`,
+/// so new blocks are recorded as soon as the node sees them. While the
+/// websocket is down, the node is polled over HTTP instead, and the
+/// subscription is retried every round.
+pub struct NewHeads {
+    client: reqwest::Client,
+    nodes: Vec<Node>,
+}
+
+impl NewHeads {
+    pub fn new(client: reqwest::Client, nodes: &[String]) -> Result<Self> {
+        Ok(Self {
+            client,
+            nodes: nodes
+                .iter()
+                .map(|entry| Node::from_entry(entry))
+                .collect::<Result<_>>()?,
+        })
+    }
+
+    async fn poll(&self, node: &Node, recorder: &dyn ChainUpdateRecorder) {
+        match evm::get_chain_state(
+            &self.client,
+            &node.http_url,
+            evm::has_finality_tags(node.subscription.chain),
+        )
+        .await
+        {
+            Ok(state) => {
+                recorder
+                    .update(ChainStateUpdate {
+                        source: SourceId::EvmNode,
+                        chain: node.subscription.chain,
+                        state,
+                    })
+                    .await
+            }
+            Err(e) => {
+                recorder
+                    .report_error(SourceId::EvmNode, Some(node.subscription.chain), e.into())
+                    .await
+            }
+        }
+    }
+}
+
+impl Protocol for NewHeads {
+    const STREAM: &'static str = "new heads";
+
+    fn subscribe_request(&self) -> serde_json::Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "eth_subscribe",
+            "params": ["newHeads"],
+        })
+    }
+
+    fn parse_reply(&self, text: &str) -> Result<SubscribeReply> {
+        let resp: SubscribeResponse = serde_json::from_str(text)?;
+        if let Some(error) = resp.error {
+            bail!("subscription failed: {error}");
+        }
+        Ok(match resp.result {
+            Some(_) => SubscribeReply::Subscribed(None),
+            None => SubscribeReply::Other,
+        })
+    }
+
+    fn parse_message(&self, text: &str) -> Result<Option<ChainState>> {
+        let notification: Notification = serde_json::from_str(text)?;
+        notification.params.result.to_chain_state().map(Some)
+    }
+}
+
+#[async_trait]
+impl super::Source for NewHeads {
+    fn get_supported_chains(&self) -> HashSet<ChainId> {
+        self.nodes
+            .iter()
+            .map(|node| node.subscription.chain)
+            .collect()
+    }
+
+    fn get_supported_sources(&self) -> HashSet<SourceId> {
+        HashSet::from_iter([SourceId::EvmNode])
+    }
+
+    fn url_for_chain(&self, _source: SourceId, _chain: ChainId) -> Option<String> {
+        None
+    }
+
+    fn update_interval(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    async fn check_updates(&self, recorder: &dyn ChainUpdateRecorder) {
+        futures::future::join_all(self.nodes.iter().map(|node| {
+            node.subscription
+                .listen(self, recorder, self.poll(node, recorder))
+        }))
+        .await;
+    }
+}
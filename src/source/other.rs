@@ -1,18 +1,16 @@
 use super::{
+    pacing::SendPaced,
     ChainId::{self, *},
-    SourceId::{self, *},
+    ChainStateFetcher, SourceError, SourceId,
 };
-use crate::{ChainState, ChainStateUpdate, ChainUpdateRecorder};
-use anyhow::{format_err, Result};
+use crate::{BlockHash, ChainHeight, ChainState};
+use anyhow::Result;
 use axum::async_trait;
-use regex::Regex;
-use serde_json::Value;
-use tracing::log::warn;
+use serde_json::{json, Value};
 
 /// A catch-all of single-chain explorers and alikes
 pub struct Other {
     client: reqwest::Client,
-    rate_limiter: super::UpdateRateLimiter,
 }
 
 fn as_not_null(v: &Value) -> Option<&Value> {
@@ -23,21 +21,32 @@ fn as_not_null(v: &Value) -> Option<&Value> {
     }
 }
 
+/// Read a protobuf varint off the front of `buf`
+fn read_varint(buf: &mut &[u8]) -> Result<u64> {
+    let mut value = 0;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = buf
+            .split_first()
+            .ok_or_else(|| SourceError::schema_change("truncated varint"))?;
+        *buf = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(SourceError::schema_change("varint too long").into())
+}
+
 impl Other {
-    pub fn new() -> Result<Self> {
-        Ok(Self {
-            client: reqwest::Client::builder()
-                .user_agent("curl/7.79.1")
-                .build()?,
-            rate_limiter: super::UpdateRateLimiter::new(<Self as super::StaticSource>::ID),
-        })
+    pub fn new(client: reqwest::Client) -> Result<Self> {
+        Ok(Self { client })
     }
 
     pub async fn get_json(&self, url: &str) -> Result<Value> {
         Ok(self
             .client
             .get(url)
-            .send()
+            .send_paced()
             .await?
             .error_for_status()?
             .json::<Value>()
@@ -47,13 +56,14 @@ impl Other {
     pub async fn get_chain_state(&self, chain: ChainId) -> Result<ChainState> {
         Ok(match chain {
             ChainId::Algorand => self.get_algorand_chain_state().await?,
-            ChainId::Avalanche => self.get_avalanche_chain_state().await?,
+            ChainId::AvalanchePChain => self.get_avalanche_p_chain_state().await?,
             ChainId::BitcoinGold => self.get_btg_chain_state().await?,
             ChainId::Casper => self.get_casper_chain_state().await?,
-            ChainId::Celo => self.get_celo_chain_state().await?,
-            ChainId::EthereumClassic => self.get_etc_chain_state().await?,
+            ChainId::ECash => self.get_xec_chain_state().await?,
+            ChainId::Groestlcoin => self.get_grs_chain_state().await?,
             ChainId::HederaHashgraph => self.get_hedera_chain_state().await?,
-            ChainId::Stacks => self.get_stacks_chain_state().await?,
+            ChainId::InternetComputer => self.get_icp_chain_state().await?,
+            ChainId::Mina => self.get_mina_chain_state().await?,
             ChainId::Tezos => self.get_tezos_chain_state().await?,
             _ => unreachable!(),
         })
@@ -65,46 +75,51 @@ impl Other {
             .await?;
 
         let last_block = as_not_null(&value["blocks"][0])
-            .ok_or_else(|| format_err!("missing last block data"))?;
+            .ok_or_else(|| SourceError::schema_change("missing last block data"))?;
 
         Ok(ChainState {
             hash: last_block["hash"]
                 .as_str()
-                .ok_or_else(|| format_err!("missing hash"))?
-                .to_owned(),
-            height: last_block["round"]
-                .as_u64()
-                .ok_or_else(|| format_err!("missing height"))?,
+                .ok_or_else(|| SourceError::schema_change("missing hash"))?
+                .into(),
+            height: ChainHeight::new(
+                last_block["round"]
+                    .as_u64()
+                    .ok_or_else(|| SourceError::schema_change("missing height"))?,
+            ),
+            block_time: last_block["timestamp"].as_u64(),
+            ..Default::default()
         })
     }
 
-    pub async fn get_avalanche_chain_state(&self) -> Result<ChainState> {
-        let body = self
+    pub async fn get_avalanche_p_chain_state(&self) -> Result<ChainState> {
+        let value = self
             .client
-            .get("https://snowtrace.io/blocks")
-            .send()
+            .post("https://api.avax.network/ext/bc/P")
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "platform.getHeight",
+                "params": {},
+            }))
+            .send_paced()
             .await?
             .error_for_status()?
-            .bytes()
+            .json::<Value>()
             .await?;
 
-        let body = String::from_utf8_lossy(&body).to_owned();
-
-        let regex_hash = Regex::new(r"/block/(0x[a-f0-9]+)").expect("regex incorrect");
-        let hash = regex_hash
-            .captures_iter(&body)
-            .next()
-            .ok_or_else(|| format_err!("didn't find block hash"))?;
-
-        let regex_block_num = Regex::new(r"/block/([0-9]+)").expect("regex incorrect");
-        let block_number = regex_block_num
-            .captures_iter(&body)
-            .next()
-            .ok_or_else(|| format_err!("didn't find block number"))?;
+        let height = ChainHeight::new(
+            value["result"]["height"]
+                .as_str()
+                .ok_or_else(|| SourceError::schema_change("missing height"))?
+                .parse()?,
+        );
 
         Ok(ChainState {
-            hash: hash[0].to_owned(),
-            height: block_number[1].parse::<u64>()?,
+            // block ids are only available by decoding the whole block
+            hash: BlockHash::from_height(height),
+            height,
+            ..Default::default()
         })
     }
 
@@ -114,35 +129,98 @@ impl Other {
             .await?;
 
         let last_block = as_not_null(&value["blocks"][0])
-            .ok_or_else(|| format_err!("missing last block data"))?;
+            .ok_or_else(|| SourceError::schema_change("missing last block data"))?;
 
         Ok(ChainState {
             hash: last_block["hash"]
                 .as_str()
-                .ok_or_else(|| format_err!("missing hash"))?
-                .to_owned(),
-            height: last_block["height"]
-                .as_u64()
-                .ok_or_else(|| format_err!("missing height"))?,
+                .ok_or_else(|| SourceError::schema_change("missing hash"))?
+                .into(),
+            height: ChainHeight::new(
+                last_block["height"]
+                    .as_u64()
+                    .ok_or_else(|| SourceError::schema_change("missing height"))?,
+            ),
+            ..Default::default()
         })
     }
 
-    pub async fn get_stacks_chain_state(&self) -> Result<ChainState> {
-        let value = self.get_json(
-            "https://stacks-node-api.stacks.co/extended/v1/block?limit=1&offset=0&unanchored=true",
-        ).await?;
+    pub async fn get_grs_chain_state(&self) -> Result<ChainState> {
+        let value = self
+            .get_json("https://groestlsight.groestlcoin.org/api/blocks?limit=1")
+            .await?;
 
-        let last_block = as_not_null(&value["results"][0])
-            .ok_or_else(|| format_err!("missing last block data"))?;
+        let last_block = as_not_null(&value["blocks"][0])
+            .ok_or_else(|| SourceError::schema_change("missing last block data"))?;
 
         Ok(ChainState {
             hash: last_block["hash"]
                 .as_str()
-                .ok_or_else(|| format_err!("missing hash"))?
-                .to_owned(),
-            height: last_block["height"]
-                .as_u64()
-                .ok_or_else(|| format_err!("missing height"))?,
+                .ok_or_else(|| SourceError::schema_change("missing hash"))?
+                .into(),
+            height: ChainHeight::new(
+                last_block["height"]
+                    .as_u64()
+                    .ok_or_else(|| SourceError::schema_change("missing height"))?,
+            ),
+            block_time: last_block["time"].as_u64(),
+            ..Default::default()
+        })
+    }
+
+    /// Chronik, the indexer of the eCash node, replies in protobuf only
+    pub async fn get_xec_chain_state(&self) -> Result<ChainState> {
+        let body = self
+            .client
+            .get("https://chronik.e.cash/blockchain-info")
+            .send_paced()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+
+        // message BlockchainInfo { bytes tip_hash = 1; int32 tip_height = 2; }
+        let mut tip_hash = None;
+        let mut tip_height = None;
+        let mut buf = &body[..];
+        while !buf.is_empty() {
+            let key = read_varint(&mut buf)?;
+            match (key >> 3, key & 0x7) {
+                (field, 0) => {
+                    let value = read_varint(&mut buf)?;
+                    if field == 2 {
+                        tip_height = Some(value);
+                    }
+                }
+                (field, 2) => {
+                    let len = read_varint(&mut buf)? as usize;
+                    if buf.len() < len {
+                        return Err(SourceError::schema_change("truncated field").into());
+                    }
+                    if field == 1 {
+                        // little-endian, as serialized in blocks
+                        tip_hash = Some(
+                            buf[..len]
+                                .iter()
+                                .rev()
+                                .map(|b| format!("{b:02x}"))
+                                .collect::<String>(),
+                        );
+                    }
+                    buf = &buf[len..];
+                }
+                _ => return Err(SourceError::schema_change("unexpected wire type").into()),
+            }
+        }
+
+        Ok(ChainState {
+            hash: tip_hash
+                .ok_or_else(|| SourceError::schema_change("missing hash"))?
+                .into(),
+            height: ChainHeight::new(
+                tip_height.ok_or_else(|| SourceError::schema_change("missing height"))?,
+            ),
+            ..Default::default()
         })
     }
 
@@ -151,104 +229,114 @@ impl Other {
             "https://event-store-api-clarity-mainnet.make.services/blocks?page=1&limit=1&order_direction=DESC",
         ).await?;
 
-        let last_block =
-            as_not_null(&value["data"][0]).ok_or_else(|| format_err!("missing last block data"))?;
+        let last_block = as_not_null(&value["data"][0])
+            .ok_or_else(|| SourceError::schema_change("missing last block data"))?;
 
         Ok(ChainState {
             hash: last_block["blockHash"]
                 .as_str()
-                .ok_or_else(|| format_err!("missing hash"))?
-                .to_owned(),
-            height: last_block["height"]
-                .as_u64()
-                .ok_or_else(|| format_err!("missing height"))?,
+                .ok_or_else(|| SourceError::schema_change("missing hash"))?
+                .into(),
+            height: ChainHeight::new(
+                last_block["height"]
+                    .as_u64()
+                    .ok_or_else(|| SourceError::schema_change("missing height"))?,
+            ),
+            ..Default::default()
         })
     }
 
-    pub async fn get_etc_chain_state(&self) -> Result<ChainState> {
+    pub async fn get_hedera_chain_state(&self) -> Result<ChainState> {
         let value = self
-            .client
-            .get("https://blockscout.com/etc/mainnet/chain-blocks")
-            .header("x-requested-with", "XMLHttpRequest")
-            .send()
-            .await?
-            .error_for_status()?
-            .json::<Value>()
+            .get_json(
+                "https://mainnet-public.mirrornode.hedera.com/api/v1/blocks?limit=1&order=desc",
+            )
             .await?;
 
         let last_block = as_not_null(&value["blocks"][0])
-            .ok_or_else(|| format_err!("missing last block data"))?;
-
-        // LOL, WUT
-        let some_html_crap = last_block["chain_block_html"]
-            .as_str()
-            .ok_or_else(|| format_err!("missing hash data"))?;
-
-        let regex_hash = Regex::new(r"(0x[a-f0-9]+)").expect("regex incorrect");
-        let hash = regex_hash
-            .captures_iter(some_html_crap)
-            .next()
-            .ok_or_else(|| format_err!("didn't find block hash"))?;
+            .ok_or_else(|| SourceError::schema_change("missing last block data"))?;
 
         Ok(ChainState {
-            hash: hash[0].to_owned(),
-            height: last_block["block_number"]
-                .as_u64()
-                .ok_or_else(|| format_err!("missing height"))?,
+            hash: last_block["hash"]
+                .as_str()
+                .ok_or_else(|| SourceError::schema_change("missing hash"))?
+                .into(),
+            height: ChainHeight::new(
+                last_block["number"]
+                    .as_u64()
+                    .ok_or_else(|| SourceError::schema_change("missing height"))?,
+            ),
+            // `seconds.nanoseconds` of the first transaction in the block
+            block_time: last_block["timestamp"]["from"]
+                .as_str()
+                .and_then(|ts| ts.split('.').next())
+                .and_then(|secs| secs.parse().ok()),
+            ..Default::default()
         })
     }
 
-    pub async fn get_celo_chain_state(&self) -> Result<ChainState> {
+    pub async fn get_icp_chain_state(&self) -> Result<ChainState> {
         let value = self
-            .get_json("https://explorer.celo.org/blocks?type=JSON")
+            .get_json("https://ic-api.internetcomputer.org/api/v3/metrics/block-height")
             .await?;
 
-        // another html crap; oh well
-        let some_html_crap = as_not_null(&value["items"][0])
-            .ok_or_else(|| format_err!("missing last block data"))?
-            .as_str()
-            .ok_or_else(|| format_err!("invalid last block data"))?;
-
-        let regex_hash = Regex::new(r"(0x[a-f0-9]+)").expect("regex incorrect");
-        let hash = regex_hash
-            .captures_iter(some_html_crap)
-            .next()
-            .ok_or_else(|| format_err!("didn't find block hash"))?;
-
-        let regex_block_num =
-            Regex::new("data-block-number=\"([0-9]+)\"").expect("regex incorrect");
-        let block_number = regex_block_num
-            .captures_iter(some_html_crap)
-            .next()
-            .ok_or_else(|| format_err!("didn't find block number"))?;
+        // `[[<timestamp>, "<height>"]]`
+        let sample = as_not_null(&value["block_height"][0])
+            .ok_or_else(|| SourceError::schema_change("missing block height data"))?;
+
+        let height = ChainHeight::new(
+            sample[1]
+                .as_str()
+                .ok_or_else(|| SourceError::schema_change("missing height"))?
+                .parse()?,
+        );
 
         Ok(ChainState {
-            hash: hash[0].to_owned(),
-            height: block_number[1].parse::<u64>()?,
+            // the dashboard only counts blocks of all the subnets together
+            hash: BlockHash::from_height(height),
+            height,
+            block_time: sample[0].as_u64(),
+            ..Default::default()
         })
     }
 
-    pub async fn get_hedera_chain_state(&self) -> Result<ChainState> {
+    pub async fn get_mina_chain_state(&self) -> Result<ChainState> {
         let value = self
-            .get_json("https://mainnet-public.mirrornode.hedera.com/api/v1/transactions?limit=1")
+            .client
+            .post("https://api.minascan.io/node/mainnet/v1/graphql")
+            .json(&json!({
+                "query": "{ bestChain(maxLength: 1) { stateHash protocolState { consensusState { blockHeight } blockchainState { utcDate } } } }",
+            }))
+            .send_paced()
+            .await?
+            .error_for_status()?
+            .json::<Value>()
             .await?;
 
-        let last_tx = as_not_null(&value["transactions"][0])
-            .ok_or_else(|| format_err!("missing last block data"))?;
+        let last_block = as_not_null(&value["data"]["bestChain"][0])
+            .ok_or_else(|| SourceError::schema_change("missing last block data"))?;
+        let protocol_state = &last_block["protocolState"];
 
         Ok(ChainState {
-            hash: last_tx["transaction_hash"]
+            hash: last_block["stateHash"]
                 .as_str()
-                .ok_or_else(|| format_err!("missing hash"))?
-                .to_owned(),
-            height: ((last_tx["consensus_timestamp"]
+                .ok_or_else(|| SourceError::schema_change("missing hash"))?
+                .into(),
+            height: ChainHeight::new(
+                protocol_state["consensusState"]["blockHeight"]
+                    .as_str()
+                    .ok_or_else(|| SourceError::schema_change("missing height"))?
+                    .parse()?,
+            ),
+            // in milliseconds
+            block_time: protocol_state["blockchainState"]["utcDate"]
                 .as_str()
-                .ok_or_else(|| format_err!("missing height"))?
-                .parse::<f64>()?
-                - 1596139200f64)
-                / 5.) as u64,
+                .and_then(|ms| ms.parse::<u64>().ok())
+                .map(|ms| ms / 1000),
+            ..Default::default()
         })
     }
+
     pub async fn get_tezos_chain_state(&self) -> Result<ChainState> {
         let value = self
             .get_json("https://api.tzstats.com/explorer/tip")
@@ -257,50 +345,59 @@ impl Other {
         Ok(ChainState {
             hash: value["block_hash"]
                 .as_str()
-                .ok_or_else(|| format_err!("missing hash"))?
-                .to_owned(),
-            height: value["height"]
-                .as_u64()
-                .ok_or_else(|| format_err!("missing height"))?,
+                .ok_or_else(|| SourceError::schema_change("missing hash"))?
+                .into(),
+            height: ChainHeight::new(
+                value["height"]
+                    .as_u64()
+                    .ok_or_else(|| SourceError::schema_change("missing height"))?,
+            ),
+            ..Default::default()
         })
     }
 }
 
 #[async_trait]
-impl super::StaticSource for Other {
+impl ChainStateFetcher for Other {
     const ID: SourceId = SourceId::Other;
-    const SUPPORTED_CHAINS: &'static [ChainId] = &[
-        Algorand,
-        Avalanche,
-        BitcoinGold,
-        Casper,
-        Celo,
-        EthereumClassic,
-        HederaHashgraph,
-        Stacks,
-        Tezos,
-    ];
-
-    async fn check_updates(&self, recorder: &dyn ChainUpdateRecorder) {
-        for &chain in Self::SUPPORTED_CHAINS {
-            if self.rate_limiter.should_check(chain, recorder).await {
-                match self.get_chain_state(chain).await {
-                    Err(e) => warn!(
-                        "Could not get chain state from {} for {}: {e}",
-                        Self::ID.short_name(),
-                        chain.short_name()
-                    ),
-                    Ok(state) => {
-                        recorder
-                            .update(ChainStateUpdate {
-                                source: Other,
-                                chain: chain,
-                                state,
-                            })
-                            .await;
-                    }
-                }
+    // every chain is a different endpoint
+    const CONCURRENCY: usize = 4;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &[
+            Algorand,
+            AvalanchePChain,
+            BitcoinGold,
+            Casper,
+            ECash,
+            Groestlcoin,
+            HederaHashgraph,
+            InternetComputer,
+            Mina,
+            Tezos,
+        ]
+    }
+
+    fn url_for_chain(&self, chain: ChainId) -> Option<String> {
+        Some(
+            match chain {
+                Algorand => "https://algoexplorer.io/block/{height}",
+                AvalanchePChain => "https://subnets.avax.network/p-chain/block/{height}",
+                BitcoinGold => "https://explorer.bitcoingold.org/insight/block/{hash}",
+                Casper => "https://cspr.live/block/{hash}",
+                ECash => "https://explorer.e.cash/block-height/{height}",
+                Groestlcoin => "https://groestlsight.groestlcoin.org/block/{hash}",
+                HederaHashgraph => "https://hashscan.io/mainnet/block/{height}",
+                InternetComputer => "https://dashboard.internetcomputer.org",
+                Mina => "https://minascan.io/mainnet/block/{hash}",
+                Tezos => "https://tzstats.com/{height}",
+                _ => return None,
             }
-        }
+            .to_owned(),
+        )
+    }
+
+    async fn fetch(&self, chain: ChainId) -> Result<ChainState> {
+        self.get_chain_state(chain).await
     }
 }
@@ -0,0 +1,233 @@
+//! Pacing of the requests to each host, learning its rate limits
+//!
+//! Hosts aren't paced until they push back, with a `429 Too Many Requests` or
+//! rate limit headers. From then on requests go through a token bucket,
+//! refilled slower after every `429` and faster again while requests succeed,
+//! so many chains polled from the same host share its limits.
+#![cfg_attr(not(feature = "all-sources"), allow(dead_code))]
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Result;
+use axum::async_trait;
+use metrics::gauge;
+use once_cell::sync::Lazy;
+use reqwest::{
+    header::{HeaderMap, RETRY_AFTER},
+    RequestBuilder, Response, StatusCode,
+};
+use tracing::{debug, info};
+
+use super::{conditional, SourceError, SourceErrorKind};
+
+/// Requests per second to start from after the first `429`
+const INITIAL_RATE: f64 = 1.0;
+/// Requests per second to never slow down below
+const MIN_RATE: f64 = 1.0 / 60.0;
+/// Added to the requests per second after every successful request
+const RATE_INCREASE: f64 = 0.01;
+/// Requests that can be sent at once after being idle
+const BURST: f64 = 3.0;
+/// Fail right away instead of waiting longer than this for the host
+const MAX_WAIT: Duration = Duration::from_secs(30);
+
+const REMAINING_HEADERS: &[&str] = &[
+    "x-ratelimit-remaining",
+    "x-rate-limit-remaining",
+    "ratelimit-remaining",
+];
+const RESET_HEADERS: &[&str] = &["x-ratelimit-reset", "x-rate-limit-reset", "ratelimit-reset"];
+
+struct Bucket {
+    /// Requests per second
+    rate: f64,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl Bucket {
+    fn new(rate: f64, now: Instant) -> Self {
+        Self {
+            rate: rate.max(MIN_RATE),
+            tokens: 0.0,
+            refilled: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed_secs = now.saturating_duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed_secs * self.rate).min(BURST);
+        self.refilled = now;
+    }
+}
+
+#[derive(Default)]
+struct Host {
+    /// `None` while the host didn't limit us
+    bucket: Option<Bucket>,
+    /// Set by `Retry-After` or running out of remaining requests
+    blocked_until: Option<Instant>,
+}
+
+impl Host {
+    /// Reserve a request, returning how long to wait before sending it, or
+    /// if that's too long, how long it'd be without reserving it
+    fn reserve(&mut self, now: Instant) -> Result<Duration, Duration> {
+        let mut wait = self
+            .blocked_until
+            .map(|until| until.saturating_duration_since(now))
+            .unwrap_or_default();
+
+        if let Some(bucket) = self.bucket.as_mut() {
+            bucket.refill(now);
+            // going negative reserves tokens yet to be refilled
+            bucket.tokens -= 1.0;
+            if bucket.tokens < 0.0 {
+                wait = wait.max(Duration::from_secs_f64(-bucket.tokens / bucket.rate));
+            }
+            if MAX_WAIT < wait {
+                bucket.tokens += 1.0;
+            }
+        }
+
+        if MAX_WAIT < wait {
+            Err(wait)
+        } else {
+            Ok(wait)
+        }
+    }
+
+    /// Learn from the `response` to a request to `host` sent at `now`
+    fn observe(&mut self, host: &str, response: &Response, now: Instant) {
+        let headers = response.headers();
+
+        if let Some(retry_after_secs) =
+            header_u64(headers, &[RETRY_AFTER.as_str()]).filter(|_| is_limited(response))
+        {
+            self.blocked_until = Some(now + Duration::from_secs(retry_after_secs));
+        }
+
+        let remaining = header_u64(headers, REMAINING_HEADERS);
+        let reset_secs = header_u64(headers, RESET_HEADERS).map(reset_to_secs);
+        if let (Some(remaining), Some(reset_secs)) = (remaining, reset_secs) {
+            if remaining == 0 {
+                self.blocked_until = Some(now + Duration::from_secs(reset_secs));
+            }
+            // spread what's remaining over the rest of the window
+            let rate = remaining as f64 / reset_secs.max(1) as f64;
+            match self.bucket.as_mut() {
+                Some(bucket) => bucket.rate = rate.max(MIN_RATE),
+                None => self.bucket = Some(Bucket::new(rate, now)),
+            }
+        } else if is_limited(response) {
+            let rate = self
+                .bucket
+                .as_ref()
+                .map_or(INITIAL_RATE, |bucket| bucket.rate / 2.0);
+            info!("{host} is rate limiting, slowing down to {rate:.2} requests/s");
+            self.bucket = Some(Bucket::new(rate, now));
+        } else if response.status().is_success() {
+            if let Some(bucket) = self.bucket.as_mut() {
+                bucket.rate += RATE_INCREASE;
+            }
+        }
+
+        if let Some(bucket) = self.bucket.as_ref() {
+            gauge!("chain_monitor_host_request_rate", bucket.rate, "host" => host.to_owned());
+        }
+    }
+}
+
+fn is_limited(response: &Response) -> bool {
+    response.status() == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// Value of the first of `names` that's a number
+fn header_u64(headers: &HeaderMap, names: &[&str]) -> Option<u64> {
+    names.iter().find_map(|name| {
+        headers
+            .get(*name)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok())
+    })
+}
+
+/// Seconds until a rate limit window resets, given either as that, or as a
+/// UNIX timestamp
+fn reset_to_secs(reset: u64) -> u64 {
+    const MIN_TS: u64 = 1_000_000_000;
+
+    if reset < MIN_TS {
+        return reset;
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default();
+    reset.saturating_sub(now)
+}
+
+static HOSTS: Lazy<Mutex<HashMap<String, Host>>> = Lazy::new(Default::default);
+
+#[async_trait]
+pub trait SendPaced {
+    /// [`RequestBuilder::send`], paced according to what was learnt about the
+    /// rate limits of the host, and made conditional on the previous responses
+    /// in a [`conditional::scope`]
+    ///
+    /// With `test-util`, responses can be recorded to and replayed from
+    /// fixtures, see [`super::fixtures`].
+    async fn send_paced(self) -> Result<Response>;
+}
+
+#[async_trait]
+impl SendPaced for RequestBuilder {
+    async fn send_paced(self) -> Result<Response> {
+        let request = match self.try_clone().and_then(|builder| builder.build().ok()) {
+            Some(request) => request,
+            // e.g. a streamed body, or an invalid request `send` will fail on
+            None => return Ok(self.send().await?),
+        };
+        #[cfg(any(test, feature = "test-util"))]
+        if let Some(response) = super::fixtures::replayed(&request) {
+            return response;
+        }
+        let (method, url) = (request.method(), request.url().as_str());
+        let host = match request.url().host_str() {
+            Some(host) => host,
+            None => return Ok(self.send().await?),
+        };
+
+        let wait = HOSTS
+            .lock()
+            .expect("not poisoned")
+            .entry(host.to_owned())
+            .or_default()
+            .reserve(Instant::now())
+            .map_err(|wait| {
+                SourceError::new(
+                    SourceErrorKind::RateLimited,
+                    format!("pacing requests to {host}, next one in {}s", wait.as_secs()),
+                )
+            })?;
+        if !wait.is_zero() {
+            debug!("Waiting {}ms for {host}", wait.as_millis());
+            tokio::time::sleep(wait).await;
+        }
+
+        let response = conditional::prepare(self, method, url).send().await?;
+        HOSTS
+            .lock()
+            .expect("not poisoned")
+            .entry(host.to_owned())
+            .or_default()
+            .observe(host, &response, Instant::now());
+        conditional::observe(method, url, &response)?;
+        #[cfg(any(test, feature = "test-util"))]
+        let response = super::fixtures::record(&request, response).await?;
+        Ok(response)
+    }
+}
@@ -0,0 +1,409 @@
+//! Central polling of [`ChainStateFetcher`]s
+#![cfg_attr(not(feature = "all-sources"), allow(dead_code))]
+use std::{
+    cmp,
+    collections::{HashMap, HashSet},
+    future::Future,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use axum::async_trait;
+use futures::stream::{self, StreamExt};
+use metrics::histogram;
+use rand::{seq::SliceRandom, thread_rng};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+use super::{conditional, ChainId, Source, SourceError, SourceErrorKind, SourceId};
+use crate::{
+    opts::Tuning, ChainState, ChainStateUpdate, ChainUpdateRecorder, DifficultyStats,
+    DifficultyStatsUpdate, MempoolStats, MempoolStatsUpdate,
+};
+
+/// How many times to retry a failed fetch that might succeed on a retry
+const RETRIES: usize = 1;
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+/// Lower bound of the time between periodic checks of a chain
+const MIN_RECHECK_SECS: u64 = 45;
+
+/// When should a [`Poller`] fetch a chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PollingPolicy {
+    /// Every round
+    Always,
+    /// When behind other sources, or not checked for a while,
+    /// see [`recheck_threshold_secs`]
+    RateLimited,
+    /// Only when behind other sources, for sources with tight rate limits
+    WhenBehind,
+}
+
+/// A source that fetches the state of one chain at a time
+///
+/// Everything else (scheduling, rate limiting, retries, metrics, recording
+/// and error reporting) is handled uniformly by the [`Poller`].
+#[async_trait]
+pub trait ChainStateFetcher: Sync + Send {
+    const ID: SourceId;
+    const POLLING: PollingPolicy = PollingPolicy::RateLimited;
+    /// How many chains to fetch at the same time
+    const CONCURRENCY: usize = 1;
+
+    fn supported_chains(&self) -> &[ChainId];
+
+    /// See [`Source::url_for_chain`]
+    fn url_for_chain(&self, _chain: ChainId) -> Option<String> {
+        None
+    }
+
+    async fn fetch(&self, chain: ChainId) -> Result<ChainState>;
+
+    /// Mempool statistics, if tracked for `chain`
+    async fn fetch_mempool_stats(&self, _chain: ChainId) -> Option<Result<MempoolStats>> {
+        None
+    }
+
+    /// Difficulty statistics, if tracked for `chain`
+    async fn fetch_difficulty_stats(&self, _chain: ChainId) -> Option<Result<DifficultyStats>> {
+        None
+    }
+}
+
+/// Drives a [`ChainStateFetcher`], turning it into a [`Source`]
+pub struct Poller<F> {
+    fetcher: F,
+    rate_limiter: UpdateRateLimiter,
+}
+
+impl<F: ChainStateFetcher> Poller<F> {
+    pub fn new(fetcher: F) -> Self {
+        Self {
+            fetcher,
+            rate_limiter: UpdateRateLimiter::new(F::ID, F::POLLING),
+        }
+    }
+
+    /// Call `fetch`, retrying errors that might be transient
+    ///
+    /// `None` means there was nothing to fetch.
+    async fn fetch_with_retries<T, Fut>(
+        &self,
+        chain: ChainId,
+        what: &'static str,
+        fetch: impl Fn() -> Fut,
+    ) -> Option<Result<T, SourceError>>
+    where
+        Fut: Future<Output = Option<Result<T>>>,
+    {
+        let mut retries_left = RETRIES;
+        loop {
+            let start = Instant::now();
+            let res = conditional::scope(format!("{:?}/{chain:?}/{what}", F::ID), fetch())
+                .await?
+                .map_err(SourceError::from);
+            histogram!(
+                "chain_monitor_source_fetch_duration_seconds",
+                start.elapsed().as_secs_f64(),
+                "source" => F::ID.short_name().to_lowercase(),
+                "chain" => chain.short_name().to_lowercase(),
+                "what" => what
+            );
+
+            match res {
+                Err(e)
+                    if 0 < retries_left
+                        && matches!(
+                            e.kind,
+                            SourceErrorKind::Timeout | SourceErrorKind::Upstream
+                        ) =>
+                {
+                    debug!("{:?} {:?} {what} fetch failed, retrying: {e}", F::ID, chain);
+                    retries_left -= 1;
+                    tokio::time::sleep(RETRY_DELAY).await;
+                }
+                res => return Some(res),
+            }
+        }
+    }
+
+    async fn poll_chain(&self, chain: ChainId, recorder: &dyn ChainUpdateRecorder) {
+        match self
+            .fetch_with_retries(chain, "state", || async {
+                Some(self.fetcher.fetch(chain).await)
+            })
+            .await
+        {
+            Some(Ok(state)) => {
+                recorder
+                    .update(ChainStateUpdate {
+                        source: F::ID,
+                        chain,
+                        state,
+                    })
+                    .await
+            }
+            Some(Err(e)) if e.kind == SourceErrorKind::Unchanged => {
+                recorder.unchanged(F::ID, chain).await
+            }
+            Some(Err(e)) => recorder.report_error(F::ID, Some(chain), e).await,
+            None => {}
+        }
+
+        match self
+            .fetch_with_retries(chain, "mempool", || self.fetcher.fetch_mempool_stats(chain))
+            .await
+        {
+            Some(Ok(stats)) => {
+                recorder
+                    .update_mempool(MempoolStatsUpdate {
+                        source: F::ID,
+                        chain,
+                        stats,
+                    })
+                    .await
+            }
+            Some(Err(e)) if e.kind == SourceErrorKind::Unchanged => {}
+            Some(Err(e)) => recorder.report_error(F::ID, Some(chain), e).await,
+            None => {}
+        }
+
+        match self
+            .fetch_with_retries(chain, "difficulty", || {
+                self.fetcher.fetch_difficulty_stats(chain)
+            })
+            .await
+        {
+            Some(Ok(stats)) => {
+                recorder
+                    .update_difficulty(DifficultyStatsUpdate {
+                        source: F::ID,
+                        chain,
+                        stats,
+                    })
+                    .await
+            }
+            Some(Err(e)) if e.kind == SourceErrorKind::Unchanged => {}
+            Some(Err(e)) => recorder.report_error(F::ID, Some(chain), e).await,
+            None => {}
+        }
+    }
+}
+
+#[async_trait]
+impl<F: ChainStateFetcher> Source for Poller<F> {
+    fn get_supported_chains(&self) -> HashSet<ChainId> {
+        self.fetcher.supported_chains().iter().copied().collect()
+    }
+
+    fn get_supported_sources(&self) -> HashSet<SourceId> {
+        HashSet::from_iter([F::ID])
+    }
+
+    fn url_for_chain(&self, source: SourceId, chain: ChainId) -> Option<String> {
+        if source == F::ID && self.fetcher.supported_chains().contains(&chain) {
+            self.fetcher.url_for_chain(chain)
+        } else {
+            None
+        }
+    }
+
+    async fn check_updates(&self, recorder: &dyn ChainUpdateRecorder) {
+        // randomize the order to give all chains a chance, even in the presence
+        // of rate limiting
+        let mut supported_chains = self.fetcher.supported_chains().to_vec();
+        supported_chains.shuffle(&mut thread_rng());
+
+        stream::iter(supported_chains)
+            .for_each_concurrent(F::CONCURRENCY, |chain| async move {
+                if !recorder.is_paused(chain).await
+                    && self.rate_limiter.should_check(chain, recorder).await
+                {
+                    self.poll_chain(chain, recorder).await;
+                }
+            })
+            .await;
+    }
+}
+
+/// How long to wait between periodic checks of `chain`
+///
+/// Right after a block was found, the next one is unlikely to come soon,
+/// so checks can be relaxed. Once a block is overdue, check more aggressively.
+/// Unless set for the chain with `--chain-tuning`.
+fn recheck_threshold_secs(chain: ChainId, tuning: Tuning, tip_age_secs: Option<u64>) -> u64 {
+    if let Some(recheck_secs) = tuning.recheck_secs {
+        return recheck_secs;
+    }
+    let block_time_secs = u64::from(chain.block_time_secs());
+
+    let threshold_secs = match tip_age_secs {
+        Some(age) if age < block_time_secs / 2 => block_time_secs,
+        Some(age) if block_time_secs <= age => block_time_secs / 4,
+        _ => block_time_secs / 2,
+    };
+
+    cmp::max(threshold_secs, MIN_RECHECK_SECS)
+}
+
+struct UpdateRateLimiter {
+    source: SourceId,
+    last_checked: Mutex<HashMap<ChainId, u64>>,
+    /// Latest forced refresh already done, see [`ChainUpdateRecorder::refresh_requested`]
+    last_refresh: Mutex<HashMap<ChainId, u64>>,
+    policy: PollingPolicy,
+}
+
+impl UpdateRateLimiter {
+    fn new(source: SourceId, policy: PollingPolicy) -> Self {
+        Self {
+            source,
+            last_checked: Mutex::new(HashMap::default()),
+            last_refresh: Mutex::new(HashMap::default()),
+            policy,
+        }
+    }
+
+    async fn should_check(
+        &self,
+        chain: ChainId,
+        update_recorder: &dyn ChainUpdateRecorder,
+    ) -> bool {
+        let now = crate::get_now_ts();
+        let mut last_checked = self.last_checked.lock().await;
+
+        let refresh = update_recorder.refresh_requested(self.source, chain).await;
+        let last_refresh = self.last_refresh.lock().await.insert(chain, refresh);
+        if last_refresh.unwrap_or(0) < refresh {
+            debug!("{:?} {:?} refresh requested; updating", self.source, chain);
+            last_checked.insert(chain, now);
+            return true;
+        }
+
+        if self.policy == PollingPolicy::Always {
+            return true;
+        }
+
+        let since_last_check_secs = now.saturating_sub(*last_checked.entry(chain).or_insert(0));
+        let tuning = update_recorder.tuning(chain);
+        let recheck_threashold_secs =
+            recheck_threshold_secs(chain, tuning, update_recorder.tip_age_secs(chain).await);
+        let how_far_behind = update_recorder.how_far_behind(self.source, chain).await;
+
+        let is_behind = if how_far_behind > tuning.acceptable_lag_blocks.unwrap_or(0) {
+            debug!(
+                "{:?} {:?} is {} behind; updating",
+                self.source, chain, how_far_behind
+            );
+            true
+        } else {
+            false
+        };
+
+        let is_stale = if (since_last_check_secs > recheck_threashold_secs)
+            && self.policy == PollingPolicy::RateLimited
+        {
+            debug!(
+                "{:?} {:?} is {}s since last updated; updating",
+                self.source, chain, since_last_check_secs
+            );
+            true
+        } else {
+            false
+        };
+
+        if is_behind || is_stale {
+            last_checked.insert(chain, now);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::{
+        clock::MockClock, opts::Opts, source::mock::MockSource, AppState, BlockHash, ChainHeight,
+    };
+
+    const NOW_TS: u64 = 1_700_000_000;
+
+    fn app_state() -> AppState {
+        let mut app_state = AppState::new(&Opts::parse_from(["chain-monitor"]));
+        app_state.add_chain(ChainId::Bitcoin);
+        app_state
+    }
+
+    fn state(height: u64) -> ChainState {
+        let height = ChainHeight::new(height);
+        ChainState {
+            hash: BlockHash::from_height(height),
+            height,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn rechecks_once_the_threshold_passed() {
+        let clock = MockClock::install(NOW_TS);
+        let app_state = app_state();
+        let limiter = UpdateRateLimiter::new(SourceId::Blockstream, PollingPolicy::RateLimited);
+
+        assert!(limiter.should_check(ChainId::Bitcoin, &app_state).await);
+        assert!(!limiter.should_check(ChainId::Bitcoin, &app_state).await);
+
+        // half the block time, without a tip to tell how old it is
+        clock.advance(300);
+        assert!(!limiter.should_check(ChainId::Bitcoin, &app_state).await);
+        clock.advance(1);
+        assert!(limiter.should_check(ChainId::Bitcoin, &app_state).await);
+    }
+
+    #[tokio::test]
+    async fn clock_going_back_is_not_a_reason_to_recheck() {
+        let clock = MockClock::install(NOW_TS);
+        let app_state = app_state();
+        let limiter = UpdateRateLimiter::new(SourceId::Blockstream, PollingPolicy::RateLimited);
+
+        assert!(limiter.should_check(ChainId::Bitcoin, &app_state).await);
+        clock.set(NOW_TS - 60);
+        assert!(!limiter.should_check(ChainId::Bitcoin, &app_state).await);
+    }
+
+    #[tokio::test]
+    async fn checks_a_source_behind_right_away() {
+        let _clock = MockClock::install(NOW_TS);
+        let app_state = app_state();
+        app_state.add_source(SourceId::Blockstream);
+        app_state.add_source(SourceId::MempoolSpace);
+        let (ahead, ahead_handle) = MockSource::new(SourceId::MempoolSpace, &[ChainId::Bitcoin]);
+        let (behind, behind_handle) = MockSource::new(SourceId::Blockstream, &[ChainId::Bitcoin]);
+        let limiter = UpdateRateLimiter::new(SourceId::Blockstream, PollingPolicy::RateLimited);
+
+        behind_handle.push_state(ChainId::Bitcoin, state(100)).await;
+        behind.check_updates(&app_state).await;
+        assert!(limiter.should_check(ChainId::Bitcoin, &app_state).await);
+        assert!(!limiter.should_check(ChainId::Bitcoin, &app_state).await);
+
+        ahead_handle.push_state(ChainId::Bitcoin, state(101)).await;
+        ahead.check_updates(&app_state).await;
+        assert!(limiter.should_check(ChainId::Bitcoin, &app_state).await);
+
+        behind_handle.push_state(ChainId::Bitcoin, state(101)).await;
+        behind.check_updates(&app_state).await;
+        assert!(!limiter.should_check(ChainId::Bitcoin, &app_state).await);
+    }
+
+    #[tokio::test]
+    async fn polls_when_behind_only_when_behind() {
+        let _clock = MockClock::install(NOW_TS);
+        let app_state = app_state();
+        let limiter = UpdateRateLimiter::new(SourceId::Blockstream, PollingPolicy::WhenBehind);
+
+        assert!(!limiter.should_check(ChainId::Bitcoin, &app_state).await);
+    }
+}
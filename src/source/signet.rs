@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+
+use super::{esplora, ChainId, ChainStateFetcher, SourceId};
+use crate::ChainState;
+use anyhow::{format_err, Result};
+use axum::async_trait;
+
+/// Esplora instances of user-defined signets (Mutinynet and alike)
+pub struct CustomSignets {
+    client: reqwest::Client,
+    chains: Vec<ChainId>,
+    api_urls: HashMap<ChainId, String>,
+}
+
+impl CustomSignets {
+    /// Create from `<ticker>[:<name>[:<block-time-secs>]]=<esplora-api-url>`
+    /// entries, registered with [`super::register_custom_signets`]
+    pub fn new(client: reqwest::Client, signets: &[String]) -> Result<Self> {
+        let api_urls: HashMap<_, _> = signets
+            .iter()
+            .map(|entry| {
+                let (id, url) = entry.split_once('=').ok_or_else(|| {
+                    format_err!(
+                        "expected `<ticker>[:<name>[:<block-time-secs>]]=<esplora-api-url>`: {entry}"
+                    )
+                })?;
+                let ticker = id.split(':').next().unwrap_or_default();
+                let chain = ChainId::from_ticker(ticker)
+                    .filter(|chain| matches!(chain, ChainId::CustomSignet(_)))
+                    .ok_or_else(|| format_err!("custom signet not registered: {ticker}"))?;
+
+                Ok((chain, url.trim_end_matches('/').to_owned()))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            client,
+            chains: api_urls.keys().copied().collect(),
+            api_urls,
+        })
+    }
+}
+
+#[async_trait]
+impl ChainStateFetcher for CustomSignets {
+    const ID: SourceId = SourceId::CustomEsplora;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &self.chains
+    }
+
+    async fn fetch(&self, chain: ChainId) -> Result<ChainState> {
+        esplora::get_chain_state(&self.client, &self.api_urls[&chain]).await
+    }
+}
@@ -0,0 +1,232 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    time::Duration,
+};
+
+use super::{ChainId, SimulatedSourceId, SourceError, SourceErrorKind, SourceId};
+use crate::{BlockHash, ChainHeight, ChainState, ChainStateUpdate, ChainUpdateRecorder};
+use axum::async_trait;
+use rand::{thread_rng, Rng};
+use tokio::sync::Mutex;
+use tracing::debug;
+
+const CHAINS: &[ChainId] = &[
+    ChainId::Bitcoin,
+    ChainId::Litecoin,
+    ChainId::Doge,
+    ChainId::Ethereum,
+];
+
+/// How many recent blocks to keep, for lagging sources and reorgs
+const KEEP_BLOCKS: usize = 16;
+
+/// Chance of a chain stalling for a while, per round
+const STALL_PROBABILITY: f64 = 0.005;
+/// Chance of the tip getting replaced by a competing block, per round
+const REORG_PROBABILITY: f64 = 0.01;
+
+/// How a simulated source sees the simulated chains
+struct Behavior {
+    id: SourceId,
+    /// Blocks are only seen this long after being produced
+    delay_secs: u64,
+    /// Chance of a failed update
+    error_probability: f64,
+}
+
+const BEHAVIORS: &[Behavior] = &[
+    Behavior {
+        id: SourceId::Simulated(SimulatedSourceId::new("SimFast", "Simulated (fast)")),
+        delay_secs: 0,
+        error_probability: 0.0,
+    },
+    Behavior {
+        id: SourceId::Simulated(SimulatedSourceId::new("SimLagging", "Simulated (lagging)")),
+        delay_secs: 90,
+        error_probability: 0.0,
+    },
+    Behavior {
+        id: SourceId::Simulated(SimulatedSourceId::new("SimFlaky", "Simulated (flaky)")),
+        delay_secs: 10,
+        error_probability: 0.2,
+    },
+];
+
+struct Block {
+    height: ChainHeight,
+    hash: BlockHash,
+    time: u64,
+}
+
+/// The "real" state of a simulated chain
+struct SimChain {
+    chain: ChainId,
+    /// Most recent last
+    blocks: VecDeque<Block>,
+    next_block_ts: u64,
+}
+
+impl SimChain {
+    fn new(chain: ChainId, now: u64) -> Self {
+        let mut sim = Self {
+            chain,
+            blocks: VecDeque::new(),
+            next_block_ts: now,
+        };
+        // start somewhere plausible, with the tip a while ago
+        let start_height = thread_rng().gen_range(100_000..1_000_000);
+        for i in 0..KEEP_BLOCKS as u64 {
+            let time = now - (KEEP_BLOCKS as u64 - i) * u64::from(chain.block_time_secs());
+            sim.push(ChainHeight::new(start_height + i), time);
+        }
+        sim.schedule_next_block(now);
+        sim
+    }
+
+    fn random_hash(&self) -> BlockHash {
+        let bytes: [u8; 32] = thread_rng().gen();
+        let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+        if self.chain == ChainId::Ethereum {
+            format!("0x{hex}").as_str().into()
+        } else {
+            hex.as_str().into()
+        }
+    }
+
+    fn push(&mut self, height: ChainHeight, time: u64) {
+        let hash = self.random_hash();
+        self.blocks.push_back(Block { height, hash, time });
+        while KEEP_BLOCKS < self.blocks.len() {
+            self.blocks.pop_front();
+        }
+    }
+
+    /// Block times are exponentially distributed around the nominal one
+    fn schedule_next_block(&mut self, now: u64) {
+        let block_time = f64::from(self.chain.block_time_secs());
+        let uniform: f64 = thread_rng().gen_range(f64::EPSILON..1.0);
+        self.next_block_ts = now + (-uniform.ln() * block_time) as u64;
+    }
+
+    fn advance(&mut self, now: u64) {
+        let block_time = u64::from(self.chain.block_time_secs());
+        let mut rng = thread_rng();
+
+        if rng.gen_bool(STALL_PROBABILITY) {
+            debug!("Simulating a stall of {:?}", self.chain);
+            self.next_block_ts += 20 * block_time;
+        }
+
+        if rng.gen_bool(REORG_PROBABILITY) {
+            debug!("Simulating a reorg of {:?}", self.chain);
+            let hash = self.random_hash();
+            if let Some(tip) = self.blocks.back_mut() {
+                tip.hash = hash;
+            }
+        }
+
+        while self.next_block_ts <= now {
+            let height = self
+                .blocks
+                .back()
+                .map(|block| block.height + 1)
+                .unwrap_or_default();
+            let time = self.next_block_ts;
+            self.push(height, time);
+            self.schedule_next_block(time);
+        }
+    }
+
+    /// Latest block visible to a source seeing blocks `delay_secs` late
+    fn seen_tip(&self, now: u64, delay_secs: u64) -> Option<&Block> {
+        self.blocks
+            .iter()
+            .rev()
+            .find(|block| block.time + delay_secs <= now)
+            .or_else(|| self.blocks.front())
+    }
+}
+
+/// Fake sources producing plausible block progressions, for `--simulate`
+///
+/// Chains occasionally stall and reorg, one of the sources lags behind,
+/// and another fails every now and then.
+pub struct Simulated {
+    chains: Mutex<HashMap<ChainId, SimChain>>,
+}
+
+impl Simulated {
+    pub fn new() -> Self {
+        let now = crate::get_now_ts();
+        Self {
+            chains: Mutex::new(
+                CHAINS
+                    .iter()
+                    .map(|&chain| (chain, SimChain::new(chain, now)))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+#[async_trait]
+impl super::Source for Simulated {
+    fn get_supported_chains(&self) -> HashSet<ChainId> {
+        CHAINS.iter().copied().collect()
+    }
+
+    fn get_supported_sources(&self) -> HashSet<SourceId> {
+        BEHAVIORS.iter().map(|behavior| behavior.id).collect()
+    }
+
+    fn url_for_chain(&self, _source: SourceId, _chain: ChainId) -> Option<String> {
+        None
+    }
+
+    fn update_interval(&self) -> Duration {
+        Duration::from_secs(5)
+    }
+
+    async fn check_updates(&self, recorder: &dyn ChainUpdateRecorder) {
+        let now = crate::get_now_ts();
+        let mut updates = vec![];
+        let mut errors = vec![];
+
+        {
+            let mut chains = self.chains.lock().await;
+            let mut rng = thread_rng();
+            for sim in chains.values_mut() {
+                sim.advance(now);
+                for behavior in BEHAVIORS {
+                    if rng.gen_bool(behavior.error_probability) {
+                        errors.push((behavior.id, sim.chain));
+                    } else if let Some(block) = sim.seen_tip(now, behavior.delay_secs) {
+                        updates.push(ChainStateUpdate {
+                            source: behavior.id,
+                            chain: sim.chain,
+                            state: ChainState {
+                                hash: block.hash.clone(),
+                                height: block.height,
+                                block_time: Some(block.time),
+                                ..Default::default()
+                            },
+                        });
+                    }
+                }
+            }
+        }
+
+        for update in updates {
+            recorder.update(update).await;
+        }
+        for (source, chain) in errors {
+            recorder
+                .report_error(
+                    source,
+                    Some(chain),
+                    SourceError::new(SourceErrorKind::Upstream, "simulated failure"),
+                )
+                .await;
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use super::{pacing::SendPaced, ChainId, ChainId::*, ChainStateFetcher, SourceError, SourceId};
+use crate::{opts::SolanaCommitment, ChainHeight, ChainState};
+use anyhow::{format_err, Result};
+use axum::async_trait;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct Context {
+    slot: u64,
+}
+
+#[derive(Deserialize)]
+struct WithContext<T> {
+    context: Context,
+    value: T,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LatestBlockhash {
+    blockhash: String,
+    last_valid_block_height: u64,
+}
+
+/// For how many blocks a blockhash can be used in transactions, which
+/// `lastValidBlockHeight` is ahead of the block the hash is of
+const MAX_PROCESSING_AGE: u64 = 150;
+
+/// Public Solana RPC endpoints
+///
+/// The authoritative view of the cluster, to cross-check explorers with.
+pub struct SolanaRpc {
+    client: reqwest::Client,
+    commitment: SolanaCommitment,
+}
+
+impl SolanaRpc {
+    pub fn new(client: reqwest::Client, commitment: SolanaCommitment) -> Result<Self> {
+        Ok(Self { client, commitment })
+    }
+
+    fn rpc_url(chain: ChainId) -> &'static str {
+        match chain {
+            Solana => "https://api.mainnet-beta.solana.com",
+            SolanaTestnet => "https://api.testnet.solana.com",
+            SolanaDevnet => "https://api.devnet.solana.com",
+            _ => unreachable!(),
+        }
+    }
+
+    async fn call<T: DeserializeOwned>(&self, chain: ChainId, method: &str) -> Result<T> {
+        let commitment: &'static str = self.commitment.into();
+        let resp = self
+            .client
+            .post(Self::rpc_url(chain))
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": [{ "commitment": commitment }],
+            }))
+            .send_paced()
+            .await?
+            .error_for_status()?
+            .json::<RpcResponse<T>>()
+            .await?;
+
+        if let Some(error) = resp.error {
+            return Err(format_err!("rpc error: {error}"));
+        }
+
+        Ok(resp.result.ok_or_else(|| {
+            SourceError::schema_change(format!("no result returned for `{method}`"))
+        })?)
+    }
+}
+
+#[async_trait]
+impl ChainStateFetcher for SolanaRpc {
+    const ID: SourceId = SourceId::SolanaRpc;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &[Solana, SolanaTestnet, SolanaDevnet]
+    }
+
+    fn url_for_chain(&self, chain: ChainId) -> Option<String> {
+        Some(match chain {
+            Solana => "https://explorer.solana.com/block/{height}".to_owned(),
+            SolanaTestnet => {
+                "https://explorer.solana.com/block/{height}?cluster=testnet".to_owned()
+            }
+            SolanaDevnet => "https://explorer.solana.com/block/{height}?cluster=devnet".to_owned(),
+            _ => return None,
+        })
+    }
+
+    /// Everything comes from a single `getLatestBlockhash`, as separate calls
+    /// for the slot and block height could land on different blocks
+    async fn fetch(&self, chain: ChainId) -> Result<ChainState> {
+        let latest_blockhash = self
+            .call::<WithContext<LatestBlockhash>>(chain, "getLatestBlockhash")
+            .await?;
+        let block_height = latest_blockhash
+            .value
+            .last_valid_block_height
+            .checked_sub(MAX_PROCESSING_AGE)
+            .ok_or_else(|| {
+                SourceError::schema_change(format!(
+                    "`lastValidBlockHeight` {} below {MAX_PROCESSING_AGE}",
+                    latest_blockhash.value.last_valid_block_height
+                ))
+            })?;
+
+        Ok(ChainState {
+            hash: latest_blockhash.value.blockhash.into(),
+            height: ChainHeight::new(latest_blockhash.context.slot),
+            secondary_height: Some(ChainHeight::new(block_height)),
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::fixtures;
+
+    #[tokio::test]
+    async fn reads_the_slot_and_block_height_of_the_same_block() {
+        let source =
+            SolanaRpc::new(reqwest::Client::new(), SolanaCommitment::Finalized).expect("source");
+        let state = fixtures::replay(fixtures::test_dir(), source.fetch(Solana))
+            .await
+            .expect("state");
+
+        assert_eq!(state.height, ChainHeight::new(297_440_512));
+        assert_eq!(state.secondary_height, Some(ChainHeight::new(275_812_361)));
+        assert_eq!(
+            state.hash,
+            "6dGzYgMdyJ3N6S4LhAXvyJ5wDZnfmAY3oBq7WDnCyFtK".into()
+        );
+    }
+}
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+use super::{pacing::SendPaced, ChainId, ChainId::*, ChainStateFetcher, SourceError, SourceId};
+use crate::{ChainHeight, ChainState};
+use anyhow::{format_err, Result};
+use axum::async_trait;
+use serde::Deserialize;
+
+/// APIs run by Hiro, used unless overridden with `--stacks-api`
+const DEFAULT_URLS: &[(ChainId, &str)] = &[
+    (Stacks, "https://api.hiro.so"),
+    (StacksTestnet, "https://api.testnet.hiro.so"),
+];
+
+#[derive(Deserialize)]
+struct Blocks {
+    results: Vec<Block>,
+}
+
+#[derive(Deserialize)]
+struct Block {
+    hash: String,
+    height: u64,
+    block_time: u64,
+    burn_block_height: u64,
+}
+
+/// Stacks Blockchain API, either Hiro's or a self-hosted one
+pub struct StacksApi {
+    client: reqwest::Client,
+    chains: Vec<ChainId>,
+    urls: HashMap<ChainId, String>,
+}
+
+impl StacksApi {
+    /// Create from `<ticker>=<url>` entries, on top of [`DEFAULT_URLS`]
+    pub fn new(client: reqwest::Client, overrides: &[String]) -> Result<Self> {
+        let mut urls: HashMap<_, _> = DEFAULT_URLS
+            .iter()
+            .map(|&(chain, url)| (chain, url.to_owned()))
+            .collect();
+
+        for entry in overrides {
+            let (ticker, url) = entry
+                .split_once('=')
+                .ok_or_else(|| format_err!("expected `<ticker>=<url>`: {entry}"))?;
+            let chain = ChainId::from_ticker(ticker)
+                .ok_or_else(|| format_err!("unknown ticker: {ticker}"))?;
+            if !matches!(chain, Stacks | StacksTestnet) {
+                return Err(format_err!("not a Stacks network: {ticker}"));
+            }
+            urls.insert(chain, url.trim_end_matches('/').to_owned());
+        }
+
+        Ok(Self {
+            client,
+            chains: urls.keys().copied().collect(),
+            urls,
+        })
+    }
+}
+
+#[async_trait]
+impl ChainStateFetcher for StacksApi {
+    const ID: SourceId = SourceId::StacksApi;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &self.chains
+    }
+
+    fn url_for_chain(&self, chain: ChainId) -> Option<String> {
+        match chain {
+            Stacks => Some("https://explorer.hiro.so/block/{hash}".to_owned()),
+            StacksTestnet => Some("https://explorer.hiro.so/block/{hash}?chain=testnet".to_owned()),
+            _ => None,
+        }
+    }
+
+    async fn fetch(&self, chain: ChainId) -> Result<ChainState> {
+        let block = self
+            .client
+            .get(format!("{}/extended/v2/blocks?limit=1", self.urls[&chain]))
+            .send_paced()
+            .await?
+            .error_for_status()?
+            .json::<Blocks>()
+            .await?
+            .results
+            .into_iter()
+            .next()
+            .ok_or_else(|| SourceError::schema_change("no blocks returned"))?;
+
+        Ok(ChainState {
+            hash: block.hash.into(),
+            height: ChainHeight::new(block.height),
+            block_time: Some(block.block_time),
+            secondary_height: Some(ChainHeight::new(block.burn_block_height)),
+            ..Default::default()
+        })
+    }
+}
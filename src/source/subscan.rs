@@ -0,0 +1,112 @@
+use super::{pacing::SendPaced, ChainId, ChainId::*, ChainStateFetcher, SourceError, SourceId};
+use crate::{BlockHash, ChainHeight, ChainState};
+use anyhow::{format_err, Result};
+use axum::async_trait;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct Response<T> {
+    code: i64,
+    message: String,
+    data: Option<T>,
+}
+
+#[derive(Deserialize)]
+struct Metadata {
+    #[serde(
+        rename = "blockNum",
+        deserialize_with = "crate::util::deserialize_number_from_string"
+    )]
+    block_num: u64,
+    #[serde(
+        rename = "finalized_blockNum",
+        default,
+        deserialize_with = "crate::util::deserialize_opt_number_from_string"
+    )]
+    finalized_block_num: Option<u64>,
+    #[serde(
+        rename = "blockTime",
+        default,
+        deserialize_with = "crate::util::deserialize_opt_number_from_string"
+    )]
+    block_time: Option<u64>,
+}
+
+/// Subscan, the explorer of Substrate-based chains
+///
+/// Its metadata endpoint is the same on every network, but doesn't
+/// include the hash of the latest block.
+pub struct Subscan {
+    client: reqwest::Client,
+    /// In place of the API host of each network
+    base_url: Option<String>,
+    api_key: String,
+}
+
+impl Subscan {
+    pub fn new(client: reqwest::Client, base_url: Option<String>, api_key: String) -> Result<Self> {
+        Ok(Self {
+            client,
+            base_url,
+            api_key,
+        })
+    }
+
+    fn network_for_chain(chain: ChainId) -> &'static str {
+        match chain {
+            Polkadot => "polkadot",
+            Kusama => "kusama",
+            Moonbeam => "moonbeam",
+            Astar => "astar",
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[async_trait]
+impl ChainStateFetcher for Subscan {
+    const ID: SourceId = SourceId::Subscan;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &[Polkadot, Kusama, Moonbeam, Astar]
+    }
+
+    fn url_for_chain(&self, chain: ChainId) -> Option<String> {
+        Some(format!(
+            "https://{}.subscan.io/block/{{height}}",
+            Self::network_for_chain(chain)
+        ))
+    }
+
+    async fn fetch(&self, chain: ChainId) -> Result<ChainState> {
+        let base_url = self.base_url.clone().unwrap_or_else(|| {
+            format!("https://{}.api.subscan.io", Self::network_for_chain(chain))
+        });
+        let resp = self
+            .client
+            .post(format!("{base_url}/api/scan/metadata"))
+            .header("X-API-Key", &self.api_key)
+            .json(&serde_json::json!({}))
+            .send_paced()
+            .await?
+            .error_for_status()?
+            .json::<Response<Metadata>>()
+            .await?;
+
+        if resp.code != 0 {
+            return Err(format_err!("error {}: {}", resp.code, resp.message));
+        }
+        let metadata = resp
+            .data
+            .ok_or_else(|| SourceError::schema_change("no metadata returned"))?;
+
+        let height = ChainHeight::new(metadata.block_num);
+        Ok(ChainState {
+            hash: BlockHash::from_height(height),
+            height,
+            block_time: metadata.block_time,
+            finalized_height: metadata.finalized_block_num.map(ChainHeight::new),
+            ..Default::default()
+        })
+    }
+}
@@ -0,0 +1,108 @@
+use super::{pacing::SendPaced, ChainId, ChainId::*, ChainStateFetcher, SourceError, SourceId};
+use crate::{ChainHeight, ChainState};
+use anyhow::{format_err, Result};
+use axum::async_trait;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct Header {
+    /// Hex-encoded
+    number: String,
+}
+
+/// Public RPC nodes of Substrate-based relay chains
+pub struct Substrate {
+    client: reqwest::Client,
+}
+
+impl Substrate {
+    pub fn new(client: reqwest::Client) -> Result<Self> {
+        Ok(Self { client })
+    }
+
+    fn rpc_url(chain: ChainId) -> &'static str {
+        match chain {
+            Polkadot => "https://rpc.polkadot.io",
+            Kusama => "https://kusama-rpc.polkadot.io",
+            _ => unreachable!(),
+        }
+    }
+
+    async fn call<T: DeserializeOwned>(
+        &self,
+        chain: ChainId,
+        method: &str,
+        params: Value,
+    ) -> Result<T> {
+        let resp = self
+            .client
+            .post(Self::rpc_url(chain))
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": params,
+            }))
+            .send_paced()
+            .await?
+            .error_for_status()?
+            .json::<RpcResponse<T>>()
+            .await?;
+
+        if let Some(error) = resp.error {
+            return Err(format_err!("rpc error: {error}"));
+        }
+
+        Ok(resp.result.ok_or_else(|| {
+            SourceError::schema_change(format!("no result returned for `{method}`"))
+        })?)
+    }
+
+    async fn get_height(&self, chain: ChainId, hash: &str) -> Result<ChainHeight> {
+        let header: Header = self.call(chain, "chain_getHeader", json!([hash])).await?;
+        ChainHeight::from_hex(&header.number)
+    }
+}
+
+#[async_trait]
+impl ChainStateFetcher for Substrate {
+    const ID: SourceId = SourceId::Substrate;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &[Polkadot, Kusama]
+    }
+
+    fn url_for_chain(&self, chain: ChainId) -> Option<String> {
+        match chain {
+            Polkadot => Some("https://polkadot.subscan.io/block/{height}".to_owned()),
+            Kusama => Some("https://kusama.subscan.io/block/{height}".to_owned()),
+            _ => None,
+        }
+    }
+
+    async fn fetch(&self, chain: ChainId) -> Result<ChainState> {
+        // headers don't include their own hash, so get the hashes first
+        let (best_hash, finalized_hash): (String, String) = futures::try_join!(
+            self.call(chain, "chain_getBlockHash", json!([])),
+            self.call(chain, "chain_getFinalizedHead", json!([])),
+        )?;
+        let (height, finalized_height) = futures::try_join!(
+            self.get_height(chain, &best_hash),
+            self.get_height(chain, &finalized_hash),
+        )?;
+
+        Ok(ChainState {
+            hash: best_hash.into(),
+            height,
+            finalized_height: Some(finalized_height),
+            ..Default::default()
+        })
+    }
+}
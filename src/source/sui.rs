@@ -0,0 +1,95 @@
+use super::{pacing::SendPaced, ChainId, ChainId::*, ChainStateFetcher, SourceError, SourceId};
+use crate::{ChainHeight, ChainState};
+use anyhow::{format_err, Result};
+use axum::async_trait;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Checkpoint {
+    digest: String,
+    #[serde(deserialize_with = "crate::util::deserialize_number_from_string")]
+    sequence_number: u64,
+    #[serde(deserialize_with = "crate::util::deserialize_number_from_string")]
+    timestamp_ms: u64,
+}
+
+const DEFAULT_BASE_URL: &str = "https://fullnode.mainnet.sui.io";
+
+/// Public Sui JSON-RPC endpoint
+///
+/// The height is the sequence number of the latest checkpoint.
+pub struct SuiRpc {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl SuiRpc {
+    pub fn new(client: reqwest::Client, base_url: Option<String>) -> Result<Self> {
+        Ok(Self {
+            client,
+            base_url: base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_owned()),
+        })
+    }
+
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        let resp = self
+            .client
+            .post(&self.base_url)
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": params,
+            }))
+            .send_paced()
+            .await?
+            .error_for_status()?
+            .json::<RpcResponse<T>>()
+            .await?;
+
+        if let Some(error) = resp.error {
+            return Err(format_err!("rpc error: {error}"));
+        }
+
+        Ok(resp.result.ok_or_else(|| {
+            SourceError::schema_change(format!("no result returned for `{method}`"))
+        })?)
+    }
+}
+
+#[async_trait]
+impl ChainStateFetcher for SuiRpc {
+    const ID: SourceId = SourceId::SuiRpc;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &[Sui]
+    }
+
+    fn url_for_chain(&self, _chain: ChainId) -> Option<String> {
+        Some("https://suiscan.xyz/mainnet/checkpoint/{height}".to_owned())
+    }
+
+    async fn fetch(&self, _chain: ChainId) -> Result<ChainState> {
+        let sequence_number: String = self
+            .call("sui_getLatestCheckpointSequenceNumber", json!([]))
+            .await?;
+        let checkpoint: Checkpoint = self
+            .call("sui_getCheckpoint", json!([sequence_number]))
+            .await?;
+
+        Ok(ChainState {
+            hash: checkpoint.digest.into(),
+            height: ChainHeight::new(checkpoint.sequence_number),
+            block_time: Some(checkpoint.timestamp_ms / 1000),
+            ..Default::default()
+        })
+    }
+}
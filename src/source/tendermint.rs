@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use super::{pacing::SendPaced, ChainId, ChainId::*, ChainStateFetcher, SourceError, SourceId};
+use crate::{ChainHeight, ChainState};
+use anyhow::{format_err, Result};
+use axum::async_trait;
+use serde::Deserialize;
+
+/// Public RPC endpoints used unless overridden with `--tendermint-rpc`
+const DEFAULT_RPC_URLS: &[(ChainId, &str)] = &[
+    (CosmosHub, "https://cosmos-rpc.publicnode.com"),
+    (Osmosis, "https://osmosis-rpc.publicnode.com"),
+    (Celestia, "https://celestia-rpc.publicnode.com"),
+];
+
+#[derive(Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct Status {
+    sync_info: SyncInfo,
+}
+
+#[derive(Deserialize)]
+struct SyncInfo {
+    latest_block_hash: String,
+    #[serde(deserialize_with = "crate::util::deserialize_number_from_string")]
+    latest_block_height: u64,
+    catching_up: bool,
+}
+
+/// Generic Tendermint/CometBFT RPC source, for Cosmos SDK chains
+pub struct Tendermint {
+    client: reqwest::Client,
+    chains: Vec<ChainId>,
+    urls: HashMap<ChainId, String>,
+}
+
+impl Tendermint {
+    /// Create from `<ticker>=<rpc-url>` entries, on top of [`DEFAULT_RPC_URLS`]
+    pub fn new(client: reqwest::Client, overrides: &[String]) -> Result<Self> {
+        let mut urls: HashMap<_, _> = DEFAULT_RPC_URLS
+            .iter()
+            .map(|&(chain, url)| (chain, url.to_owned()))
+            .collect();
+
+        for entry in overrides {
+            let (ticker, url) = entry
+                .split_once('=')
+                .ok_or_else(|| format_err!("expected `<ticker>=<rpc-url>`: {entry}"))?;
+            let chain = ChainId::from_ticker(ticker)
+                .ok_or_else(|| format_err!("unknown ticker: {ticker}"))?;
+            urls.insert(chain, url.trim_end_matches('/').to_owned());
+        }
+
+        Ok(Self {
+            client,
+            chains: urls.keys().copied().collect(),
+            urls,
+        })
+    }
+}
+
+#[async_trait]
+impl ChainStateFetcher for Tendermint {
+    const ID: SourceId = SourceId::Tendermint;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &self.chains
+    }
+
+    fn url_for_chain(&self, chain: ChainId) -> Option<String> {
+        let network = match chain {
+            CosmosHub => "cosmos",
+            Osmosis => "osmosis",
+            Celestia => "celestia",
+            _ => return None,
+        };
+        Some(format!(
+            "https://www.mintscan.io/{network}/block/{{height}}"
+        ))
+    }
+
+    async fn fetch(&self, chain: ChainId) -> Result<ChainState> {
+        let resp = self
+            .client
+            .get(format!("{}/status", self.urls[&chain]))
+            .send_paced()
+            .await?
+            .error_for_status()?
+            .json::<RpcResponse<Status>>()
+            .await?;
+
+        if let Some(error) = resp.error {
+            return Err(format_err!("rpc error: {error}"));
+        }
+        let sync_info = resp
+            .result
+            .ok_or_else(|| SourceError::schema_change("no status returned"))?
+            .sync_info;
+        if sync_info.catching_up {
+            return Err(format_err!("node is still catching up"));
+        }
+
+        Ok(ChainState {
+            hash: sync_info.latest_block_hash.to_lowercase().into(),
+            height: ChainHeight::new(sync_info.latest_block_height),
+            ..Default::default()
+        })
+    }
+}
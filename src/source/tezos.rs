@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use super::{pacing::SendPaced, ChainId, ChainId::*, ChainStateFetcher, SourceId};
+use crate::{ChainHeight, ChainState};
+use anyhow::{format_err, Result};
+use axum::async_trait;
+use serde::Deserialize;
+use tracing::debug;
+
+/// Public nodes, used unless overridden with `--tezos-node`
+const DEFAULT_NODES: &[(ChainId, &str)] = &[
+    (Tezos, "https://rpc.tzkt.io/mainnet"),
+    (Tezos, "https://mainnet.smartpy.io"),
+    (TezosTestnet, "https://rpc.tzkt.io/ghostnet"),
+    (TezosTestnet, "https://ghostnet.smartpy.io"),
+];
+
+#[derive(Deserialize)]
+struct BlockHeader {
+    hash: String,
+    level: u64,
+}
+
+/// RPC of Tezos nodes, without any indexer in between
+pub struct TezosNode {
+    client: reqwest::Client,
+    chains: Vec<ChainId>,
+    nodes: HashMap<ChainId, Vec<String>>,
+}
+
+impl TezosNode {
+    /// Create from `<ticker>=<rpc-url>` entries, replacing [`DEFAULT_NODES`]
+    /// of the chains they are given for
+    pub fn new(client: reqwest::Client, overrides: &[String]) -> Result<Self> {
+        let mut custom_nodes: HashMap<ChainId, Vec<String>> = HashMap::new();
+        for entry in overrides {
+            let (ticker, url) = entry
+                .split_once('=')
+                .ok_or_else(|| format_err!("expected `<ticker>=<rpc-url>`: {entry}"))?;
+            let chain = ChainId::from_ticker(ticker)
+                .ok_or_else(|| format_err!("unknown ticker: {ticker}"))?;
+            if !matches!(chain, Tezos | TezosTestnet) {
+                return Err(format_err!("not a Tezos network: {ticker}"));
+            }
+            custom_nodes
+                .entry(chain)
+                .or_default()
+                .push(url.trim_end_matches('/').to_owned());
+        }
+
+        let mut nodes = custom_nodes.clone();
+        for &(chain, url) in DEFAULT_NODES {
+            if !custom_nodes.contains_key(&chain) {
+                nodes.entry(chain).or_default().push(url.to_owned());
+            }
+        }
+
+        Ok(Self {
+            client,
+            chains: nodes.keys().copied().collect(),
+            nodes,
+        })
+    }
+
+    async fn get_head_header(&self, url: &str) -> Result<BlockHeader> {
+        Ok(self
+            .client
+            .get(format!("{url}/chains/main/blocks/head/header"))
+            .send_paced()
+            .await?
+            .error_for_status()?
+            .json::<BlockHeader>()
+            .await?)
+    }
+}
+
+#[async_trait]
+impl ChainStateFetcher for TezosNode {
+    const ID: SourceId = SourceId::TezosNode;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &self.chains
+    }
+
+    fn url_for_chain(&self, chain: ChainId) -> Option<String> {
+        match chain {
+            Tezos => Some("https://tzkt.io/{height}".to_owned()),
+            TezosTestnet => Some("https://ghostnet.tzkt.io/{height}".to_owned()),
+            _ => None,
+        }
+    }
+
+    async fn fetch(&self, chain: ChainId) -> Result<ChainState> {
+        let mut last_error = None;
+
+        for url in &self.nodes[&chain] {
+            match self.get_head_header(url).await {
+                Ok(header) => {
+                    return Ok(ChainState {
+                        hash: header.hash.into(),
+                        height: ChainHeight::new(header.level),
+                        ..Default::default()
+                    })
+                }
+                Err(e) => {
+                    debug!("Tezos node {url} failed: {e}");
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| format_err!("no nodes configured")))
+    }
+}
@@ -0,0 +1,90 @@
+use super::{
+    pacing::SendPaced, ChainId, ChainId::*, ChainStateFetcher, SourceError, SourceErrorKind,
+    SourceId,
+};
+use crate::{ChainHeight, ChainState};
+use anyhow::Result;
+use axum::async_trait;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct Response<T> {
+    ok: bool,
+    result: Option<T>,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct MasterchainInfo {
+    last: BlockId,
+}
+
+#[derive(Deserialize)]
+struct BlockId {
+    seqno: u64,
+    root_hash: String,
+}
+
+const DEFAULT_BASE_URL: &str = "https://toncenter.com";
+
+/// toncenter, a public TON HTTP API
+///
+/// The height is the seqno of the last masterchain block.
+pub struct TonCenter {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: Option<String>,
+}
+
+impl TonCenter {
+    pub fn new(
+        client: reqwest::Client,
+        base_url: Option<String>,
+        api_key: Option<String>,
+    ) -> Result<Self> {
+        Ok(Self {
+            client,
+            base_url: base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_owned()),
+            api_key,
+        })
+    }
+}
+
+#[async_trait]
+impl ChainStateFetcher for TonCenter {
+    const ID: SourceId = SourceId::TonCenter;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &[Ton]
+    }
+
+    async fn fetch(&self, _chain: ChainId) -> Result<ChainState> {
+        let mut req = self
+            .client
+            .get(format!("{}/api/v2/getMasterchainInfo", self.base_url));
+        if let Some(api_key) = self.api_key.as_ref() {
+            req = req.header("X-API-Key", api_key);
+        }
+
+        let resp = req
+            .send_paced()
+            .await?
+            .error_for_status()?
+            .json::<Response<MasterchainInfo>>()
+            .await?;
+
+        let info = match (resp.ok, resp.result, resp.error) {
+            (true, Some(info), _) => info,
+            (_, _, Some(error)) => {
+                return Err(SourceError::new(SourceErrorKind::Upstream, error).into())
+            }
+            _ => return Err(SourceError::schema_change("missing masterchain info").into()),
+        };
+
+        Ok(ChainState {
+            hash: info.last.root_hash.into(),
+            height: ChainHeight::new(info.last.seqno),
+            ..Default::default()
+        })
+    }
+}
@@ -0,0 +1,96 @@
+use super::{pacing::SendPaced, ChainId, ChainId::*, ChainStateFetcher, SourceId};
+use crate::{ChainHeight, ChainState};
+use anyhow::Result;
+use axum::async_trait;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct NowBlock {
+    #[serde(rename = "blockID")]
+    block_id: String,
+    block_header: BlockHeader,
+}
+
+#[derive(Deserialize)]
+struct BlockHeader {
+    raw_data: BlockHeaderRawData,
+}
+
+#[derive(Deserialize)]
+struct BlockHeaderRawData {
+    number: u64,
+    /// In milliseconds
+    timestamp: u64,
+}
+
+/// TronGrid, the public API of Tron full nodes
+pub struct TronGrid {
+    client: reqwest::Client,
+    /// In place of the host of each network
+    base_url: Option<String>,
+    api_key: Option<String>,
+}
+
+impl TronGrid {
+    pub fn new(
+        client: reqwest::Client,
+        base_url: Option<String>,
+        api_key: Option<String>,
+    ) -> Result<Self> {
+        Ok(Self {
+            client,
+            base_url,
+            api_key,
+        })
+    }
+
+    fn host_for_chain(chain: ChainId) -> &'static str {
+        match chain {
+            Tron => "api.trongrid.io",
+            TronShastaTestnet => "api.shasta.trongrid.io",
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[async_trait]
+impl ChainStateFetcher for TronGrid {
+    const ID: SourceId = SourceId::TronGrid;
+
+    fn supported_chains(&self) -> &[ChainId] {
+        &[Tron, TronShastaTestnet]
+    }
+
+    fn url_for_chain(&self, chain: ChainId) -> Option<String> {
+        match chain {
+            Tron => Some("https://tronscan.org/#/block/{height}".to_owned()),
+            TronShastaTestnet => Some("https://shasta.tronscan.org/#/block/{height}".to_owned()),
+            _ => None,
+        }
+    }
+
+    async fn fetch(&self, chain: ChainId) -> Result<ChainState> {
+        let base_url = self
+            .base_url
+            .clone()
+            .unwrap_or_else(|| format!("https://{}", Self::host_for_chain(chain)));
+        let mut req = self.client.post(format!("{base_url}/wallet/getnowblock"));
+        if let Some(api_key) = self.api_key.as_ref() {
+            req = req.header("TRON-PRO-API-KEY", api_key);
+        }
+
+        let block = req
+            .send_paced()
+            .await?
+            .error_for_status()?
+            .json::<NowBlock>()
+            .await?;
+
+        Ok(ChainState {
+            hash: block.block_id.into(),
+            height: ChainHeight::new(block.block_header.raw_data.number),
+            block_time: Some(block.block_header.raw_data.timestamp / 1000),
+            ..Default::default()
+        })
+    }
+}
@@ -0,0 +1,191 @@
+//! Subscriptions over websocket, kept open across rounds of updates
+//!
+//! Sources pushing new blocks (self-hosted EVM nodes, XRPL servers) keep
+//! listening during `check_updates`, so every block is recorded as soon as
+//! it's pushed. What differs between them is the [`Protocol`].
+use std::{future::Future, time::Duration};
+
+use super::{ChainId, SourceError, SourceErrorKind, SourceId};
+use crate::{ChainState, ChainStateUpdate, ChainUpdateRecorder};
+use anyhow::{bail, Result};
+use futures::{SinkExt, StreamExt};
+use tokio::{
+    net::TcpStream,
+    sync::Mutex,
+    time::{sleep_until, timeout, timeout_at, Instant},
+};
+use tokio_tungstenite::{tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use tracing::{debug, info};
+
+/// How long to listen for pushed blocks in a single `check_updates`
+///
+/// Must stay well below the timeout of a round of updates, see [`crate::supervisor`].
+const LISTEN_WINDOW: Duration = Duration::from_secs(20);
+
+/// How long to wait for the server to accept the subscription
+const SUBSCRIBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Reply of the server to a subscription request
+pub(crate) enum SubscribeReply {
+    /// Some other message, to keep waiting for the reply after
+    #[cfg_attr(not(feature = "source-newheads"), allow(dead_code))]
+    Other,
+    /// Subscribed, with the current state if the reply has it
+    Subscribed(Option<ChainState>),
+}
+
+/// How to subscribe to new blocks, and read them from the messages pushed
+pub(crate) trait Protocol: Sync {
+    /// What's subscribed to, for the logs, e.g. `new heads`
+    const STREAM: &'static str;
+
+    /// Message subscribing to new blocks
+    fn subscribe_request(&self) -> serde_json::Value;
+
+    /// Read a message received while subscribing; failing the subscription
+    /// if it was refused
+    fn parse_reply(&self, text: &str) -> Result<SubscribeReply>;
+
+    /// Read a message of the subscription, `None` for those without a block
+    fn parse_message(&self, text: &str) -> Result<Option<ChainState>>;
+}
+
+/// A subscription of `source` to the blocks of `chain` at `url`
+pub(crate) struct Subscription {
+    source: SourceId,
+    pub chain: ChainId,
+    url: String,
+    /// The stream, if currently connected
+    conn: Mutex<Option<WsStream>>,
+}
+
+impl Subscription {
+    pub fn new(source: SourceId, chain: ChainId, url: String) -> Self {
+        Self {
+            source,
+            chain,
+            url,
+            conn: Mutex::new(None),
+        }
+    }
+
+    async fn subscribe<P: Protocol>(&self, protocol: &P) -> Result<(WsStream, Option<ChainState>)> {
+        let (mut stream, _) = tokio_tungstenite::connect_async(&self.url).await?;
+
+        stream
+            .send(Message::Text(protocol.subscribe_request().to_string()))
+            .await?;
+
+        while let Some(msg) = stream.next().await {
+            if let Message::Text(text) = msg? {
+                if let SubscribeReply::Subscribed(state) = protocol.parse_reply(&text)? {
+                    return Ok((stream, state));
+                }
+            }
+        }
+
+        bail!("connection closed before subscribing")
+    }
+
+    async fn record(&self, state: Result<ChainState>, recorder: &dyn ChainUpdateRecorder) {
+        match state {
+            Ok(state) => {
+                recorder
+                    .update(ChainStateUpdate {
+                        source: self.source,
+                        chain: self.chain,
+                        state,
+                    })
+                    .await
+            }
+            Err(e) => {
+                recorder
+                    .report_error(self.source, Some(self.chain), e.into())
+                    .await
+            }
+        }
+    }
+
+    /// Record the blocks pushed until the end of [`LISTEN_WINDOW`]
+    ///
+    /// Without a working subscription, runs `fallback` (e.g. polling once)
+    /// and waits the window out, before trying to subscribe again.
+    pub async fn listen<P: Protocol>(
+        &self,
+        protocol: &P,
+        recorder: &dyn ChainUpdateRecorder,
+        fallback: impl Future<Output = ()>,
+    ) {
+        let deadline = Instant::now() + LISTEN_WINDOW;
+        let mut conn = self.conn.lock().await;
+
+        if conn.is_none() {
+            match timeout(SUBSCRIBE_TIMEOUT, self.subscribe(protocol))
+                .await
+                .unwrap_or_else(|_| {
+                    Err(SourceError::new(SourceErrorKind::Timeout, "timed out subscribing").into())
+                }) {
+                Ok((stream, state)) => {
+                    info!(
+                        "Subscribed to {} of {:?} at {}",
+                        P::STREAM,
+                        self.chain,
+                        self.url
+                    );
+                    if let Some(state) = state {
+                        self.record(Ok(state), recorder).await;
+                    }
+                    *conn = Some(stream);
+                }
+                Err(e) => {
+                    recorder
+                        .report_error(self.source, Some(self.chain), e.into())
+                        .await;
+                    fallback.await;
+                    sleep_until(deadline).await;
+                    return;
+                }
+            }
+        }
+
+        let stream = conn.as_mut().expect("just connected");
+
+        let error = loop {
+            let msg = match timeout_at(deadline, stream.next()).await {
+                Ok(msg) => msg,
+                Err(_) => return,
+            };
+
+            match msg {
+                Some(Ok(Message::Text(text))) => match protocol.parse_message(&text) {
+                    Ok(Some(state)) => self.record(Ok(state), recorder).await,
+                    Ok(None) => {}
+                    Err(e) => self.record(Err(e), recorder).await,
+                },
+                Some(Ok(Message::Close(_))) | None => {
+                    break SourceError::new(
+                        SourceErrorKind::Upstream,
+                        "websocket closed by the server",
+                    )
+                }
+                // pings are answered by tungstenite itself
+                Some(Ok(_)) => {}
+                Some(Err(e)) => break anyhow::Error::from(e).into(),
+            }
+        };
+
+        debug!(
+            "Lost {} subscription of {:?}: {error}",
+            P::STREAM,
+            self.chain
+        );
+        *conn = None;
+        recorder
+            .report_error(self.source, Some(self.chain), error)
+            .await;
+        fallback.await;
+        sleep_until(deadline).await;
+    }
+}
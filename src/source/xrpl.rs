@@ -0,0 +1,145 @@
+use std::{collections::HashSet, time::Duration};
+
+use super::{
+    websocket::{Protocol, SubscribeReply, Subscription},
+    ChainId,
+    ChainId::*,
+    SourceError, SourceId,
+};
+use crate::{ChainHeight, ChainState, ChainUpdateRecorder};
+use anyhow::{bail, Result};
+use axum::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+
+/// Seconds between the Unix epoch and the Ripple epoch (2000-01-01)
+const RIPPLE_EPOCH_OFFSET_SECS: u64 = 946_684_800;
+
+#[derive(Deserialize)]
+struct SubscribeResponse {
+    status: String,
+    result: Option<LedgerClosed>,
+    error: Option<String>,
+}
+
+/// Both the result of subscribing, and the `ledgerClosed` stream messages
+#[derive(Deserialize)]
+struct LedgerClosed {
+    ledger_index: u64,
+    ledger_hash: String,
+    /// In seconds since the Ripple epoch
+    ledger_time: u64,
+}
+
+impl LedgerClosed {
+    fn to_chain_state(&self) -> ChainState {
+        ChainState {
+            hash: self.ledger_hash.as_str().into(),
+            height: ChainHeight::new(self.ledger_index),
+            block_time: Some(self.ledger_time + RIPPLE_EPOCH_OFFSET_SECS),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct StreamMessage {
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+/// Public XRP Ledger servers, pushing closed ledgers over websocket
+///
+/// Like the self-hosted EVM nodes (`--evm-node`), it keeps listening during
+/// `check_updates`, so every closed ledger is recorded right away.
+pub struct XrplCluster {
+    /// Subscriptions to the `ledger` stream
+    servers: Vec<Subscription>,
+}
+
+impl XrplCluster {
+    pub fn new() -> Self {
+        Self {
+            servers: vec![
+                Subscription::new(
+                    SourceId::XrplCluster,
+                    Ripple,
+                    "wss://s1.ripple.com".to_owned(),
+                ),
+                Subscription::new(
+                    SourceId::XrplCluster,
+                    RippleTestnet,
+                    "wss://s.altnet.rippletest.net:51233".to_owned(),
+                ),
+            ],
+        }
+    }
+}
+
+impl Protocol for XrplCluster {
+    const STREAM: &'static str = "ledgers";
+
+    fn subscribe_request(&self) -> serde_json::Value {
+        json!({
+            "command": "subscribe",
+            "streams": ["ledger"],
+        })
+    }
+
+    /// The reply has the latest closed ledger
+    fn parse_reply(&self, text: &str) -> Result<SubscribeReply> {
+        let resp: SubscribeResponse = serde_json::from_str(text)?;
+        if resp.status != "success" {
+            bail!(
+                "subscription failed: {}",
+                resp.error.as_deref().unwrap_or(&resp.status)
+            );
+        }
+        let ledger = resp
+            .result
+            .ok_or_else(|| SourceError::schema_change("no ledger in the subscription response"))?;
+        Ok(SubscribeReply::Subscribed(Some(ledger.to_chain_state())))
+    }
+
+    fn parse_message(&self, text: &str) -> Result<Option<ChainState>> {
+        // other streams aren't subscribed to
+        if serde_json::from_str::<StreamMessage>(text)?.kind != "ledgerClosed" {
+            return Ok(None);
+        }
+        Ok(Some(
+            serde_json::from_str::<LedgerClosed>(text)?.to_chain_state(),
+        ))
+    }
+}
+
+#[async_trait]
+impl super::Source for XrplCluster {
+    fn get_supported_chains(&self) -> HashSet<ChainId> {
+        self.servers.iter().map(|server| server.chain).collect()
+    }
+
+    fn get_supported_sources(&self) -> HashSet<SourceId> {
+        HashSet::from_iter([SourceId::XrplCluster])
+    }
+
+    fn url_for_chain(&self, _source: SourceId, chain: ChainId) -> Option<String> {
+        match chain {
+            Ripple => Some("https://livenet.xrpl.org/ledgers/{height}".to_owned()),
+            RippleTestnet => Some("https://testnet.xrpl.org/ledgers/{height}".to_owned()),
+            _ => None,
+        }
+    }
+
+    fn update_interval(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    async fn check_updates(&self, recorder: &dyn ChainUpdateRecorder) {
+        futures::future::join_all(
+            self.servers
+                .iter()
+                .map(|server| server.listen(self, recorder, async {})),
+        )
+        .await;
+    }
+}
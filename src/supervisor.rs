@@ -0,0 +1,69 @@
+//! Running sources isolated from each other
+use std::{any::Any, cmp, panic::AssertUnwindSafe, sync::Arc, time::Duration};
+
+use futures::FutureExt;
+use tokio::time::timeout;
+
+use crate::{source::Source, AppState};
+
+/// How long a single round of updates can take
+const UPDATE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Delay before restarting a source after its first panic
+const MIN_RESTART_BACKOFF: Duration = Duration::from_secs(15);
+/// Upper bound of the delay before restarting a source that keeps panicking
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(600);
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        (*s).to_owned()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic".to_owned()
+    }
+}
+
+/// Keep updating from `source` forever
+///
+/// A panic in the source is caught and reported, and the source marked
+/// unhealthy and restarted after an exponentially growing delay, without
+/// affecting any other source.
+pub async fn supervise(source: Box<dyn Source>, app_state: Arc<AppState>) {
+    let source_ids = source.get_supported_sources();
+    let mut backoff = MIN_RESTART_BACKOFF;
+    let mut healthy = true;
+
+    loop {
+        match AssertUnwindSafe(timeout(UPDATE_TIMEOUT, source.check_updates(&*app_state)))
+            .catch_unwind()
+            .await
+        {
+            Ok(res) => {
+                if let Err(e) = res {
+                    tracing::warn!("Timeout waiting for updates from {source_ids:?}: {e}");
+                }
+                if !healthy {
+                    app_state.mark_sources_healthy(&source_ids).await;
+                    healthy = true;
+                }
+                backoff = MIN_RESTART_BACKOFF;
+                // forced refreshes shouldn't wait for the next round
+                tokio::select! {
+                    _ = tokio::time::sleep(source.update_interval()) => {}
+                    _ = app_state.refresh_notify.notified() => {}
+                }
+            }
+            Err(payload) => {
+                let message = panic_message(&*payload);
+                tracing::error!(
+                    "Source {source_ids:?} panicked: {message}; restarting in {}s",
+                    backoff.as_secs()
+                );
+                app_state.record_source_panic(&source_ids, message).await;
+                healthy = false;
+                tokio::time::sleep(backoff).await;
+                backoff = cmp::min(backoff * 2, MAX_RESTART_BACKOFF);
+            }
+        }
+    }
+}
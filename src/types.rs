@@ -0,0 +1,113 @@
+//! Basic types describing the state of a chain
+use std::{
+    fmt,
+    ops::{Add, Sub},
+    str::FromStr,
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{source::ChainId, util};
+
+/// Height of a chain, see [`ChainId::height_kind`]
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(transparent)]
+pub struct ChainHeight(u64);
+
+impl ChainHeight {
+    pub const fn new(height: u64) -> Self {
+        Self(height)
+    }
+
+    /// Parse a `0x`-prefixed hex quantity, as used by Ethereum JSON-RPC
+    pub fn from_hex(s: &str) -> Result<Self> {
+        util::parse_hex_u64(s).map(Self)
+    }
+
+    pub const fn as_u64(self) -> u64 {
+        self.0
+    }
+
+    pub fn as_f64(self) -> f64 {
+        self.0 as f64
+    }
+}
+
+impl fmt::Display for ChainHeight {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for ChainHeight {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(Self)
+    }
+}
+
+/// Advance by a number of blocks
+impl Add<u64> for ChainHeight {
+    type Output = ChainHeight;
+
+    fn add(self, blocks: u64) -> ChainHeight {
+        Self(self.0 + blocks)
+    }
+}
+
+/// Number of blocks between two heights
+impl Sub for ChainHeight {
+    type Output = u64;
+
+    fn sub(self, other: ChainHeight) -> u64 {
+        self.0 - other.0
+    }
+}
+
+/// Hash (or other identifier) of a block, as reported by a source
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct BlockHash(String);
+
+impl BlockHash {
+    /// Stand-in for sources that don't report block hashes at all
+    pub fn from_height(height: ChainHeight) -> Self {
+        Self(height.to_string())
+    }
+
+    /// Is it a stand-in created by [`BlockHash::from_height`]
+    pub fn is_height(&self, height: ChainHeight) -> bool {
+        self.0 == height.to_string()
+    }
+
+    /// Check the format expected on `chain`, see [`ChainId::hash_format`]
+    pub fn validate(&self, chain: ChainId) -> Result<()> {
+        chain.hash_format().validate(&self.0)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for BlockHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<String> for BlockHash {
+    fn from(hash: String) -> Self {
+        Self(hash)
+    }
+}
+
+impl From<&str> for BlockHash {
+    fn from(hash: &str) -> Self {
+        Self(hash.to_owned())
+    }
+}
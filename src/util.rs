@@ -1,22 +1,82 @@
+// helpers of sources, which might be compiled out
+#![cfg_attr(not(feature = "all-sources"), allow(dead_code))]
+
 use std::{fmt::Display, str::FromStr};
 
 use serde::{Deserialize, Deserializer};
 
+/// A number, possibly given as a string
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StringOrInt<T> {
+    String(String),
+    Number(T),
+}
+
+impl<T> StringOrInt<T>
+where
+    T: FromStr,
+    <T as FromStr>::Err: Display,
+{
+    fn parse<E: serde::de::Error>(self) -> Result<T, E> {
+        match self {
+            StringOrInt::String(s) => s.parse::<T>().map_err(E::custom),
+            StringOrInt::Number(i) => Ok(i),
+        }
+    }
+}
+
 pub fn deserialize_number_from_string<'de, T, D>(deserializer: D) -> Result<T, D::Error>
 where
     D: Deserializer<'de>,
     T: FromStr + serde::Deserialize<'de>,
     <T as FromStr>::Err: Display,
 {
-    #[derive(Deserialize)]
-    #[serde(untagged)]
-    enum StringOrInt<T> {
-        String(String),
-        Number(T),
-    }
+    StringOrInt::<T>::deserialize(deserializer)?.parse()
+}
+
+pub fn deserialize_opt_number_from_string<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr + serde::Deserialize<'de>,
+    <T as FromStr>::Err: Display,
+{
+    Option::<StringOrInt<T>>::deserialize(deserializer)?
+        .map(StringOrInt::parse)
+        .transpose()
+}
+
+/// Take a secret given directly, from an environment variable (`env:<var>`)
+/// or from a file (`file:<path>`)
+pub fn load_secret(secret: &str, what: &str) -> anyhow::Result<String> {
+    let secret = if let Some(var) = secret.strip_prefix("env:") {
+        std::env::var(var)
+            .map_err(|e| anyhow::format_err!("can't read {what} from `{var}`: {e}"))?
+    } else if let Some(path) = secret.strip_prefix("file:") {
+        std::fs::read_to_string(path)
+            .map_err(|e| anyhow::format_err!("can't read {what} from {path}: {e}"))?
+    } else {
+        secret.to_owned()
+    };
+
+    Ok(secret.trim().to_owned())
+}
+
+/// Parse a `0x`-prefixed hex quantity, as used by Ethereum JSON-RPC
+pub fn parse_hex_u64(s: &str) -> anyhow::Result<u64> {
+    Ok(u64::from_str_radix(s.trim_start_matches("0x"), 16)?)
+}
 
-    match StringOrInt::<T>::deserialize(deserializer)? {
-        StringOrInt::String(s) => s.parse::<T>().map_err(serde::de::Error::custom),
-        StringOrInt::Number(i) => Ok(i),
+/// Parse hex-encoded cumulative chain work, as reported by bitcoind
+///
+/// Values too big to fit are saturated, which is way beyond any real chain.
+pub fn parse_chain_work(s: &str) -> Option<u128> {
+    let s = s.trim_start_matches("0x").trim_start_matches('0');
+    if s.is_empty() {
+        Some(0)
+    } else if 32 < s.len() {
+        Some(u128::MAX)
+    } else {
+        u128::from_str_radix(s, 16).ok()
     }
 }
@@ -0,0 +1,156 @@
+//! Webhooks registered at runtime, notified of alert changes
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::{bail, format_err, Result};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use crate::{
+    alert::{AlertEvent, AlertEventKind},
+    source::ChainId,
+};
+
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Subscription as requested through the API
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct NewWebhook {
+    url: String,
+    /// Tickers of the chains to notify about, all if empty
+    #[serde(default)]
+    chains: Vec<String>,
+    /// Events to notify about, all if empty
+    #[serde(default)]
+    events: Vec<AlertEventKind>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Webhook {
+    id: u64,
+    url: String,
+    chains: Vec<String>,
+    events: Vec<AlertEventKind>,
+}
+
+impl Webhook {
+    fn matches(&self, event: &AlertEvent) -> bool {
+        (self.chains.is_empty()
+            || self
+                .chains
+                .iter()
+                .any(|ticker| ticker == event.alert.chain().ticker()))
+            && (self.events.is_empty() || self.events.contains(&event.event))
+    }
+}
+
+/// Registered webhooks, saved to `path` on every change if set
+#[derive(Default)]
+pub struct Webhooks {
+    path: Option<PathBuf>,
+    webhooks: Mutex<Vec<Webhook>>,
+    client: reqwest::Client,
+}
+
+impl Webhooks {
+    /// Load the webhooks saved at `path`, if it exists
+    pub fn load(path: Option<PathBuf>) -> Result<Self> {
+        let webhooks = match path.as_ref() {
+            Some(path) if path.exists() => {
+                let webhooks: Vec<Webhook> = serde_json::from_slice(&std::fs::read(path)?)
+                    .map_err(|e| format_err!("can't load webhooks from {}: {e}", path.display()))?;
+                info!("Loaded {} webhooks from {}", webhooks.len(), path.display());
+                webhooks
+            }
+            _ => vec![],
+        };
+
+        Ok(Self {
+            path,
+            webhooks: Mutex::new(webhooks),
+            client: reqwest::Client::builder()
+                .timeout(DELIVERY_TIMEOUT)
+                .build()?,
+        })
+    }
+
+    fn save(&self, webhooks: &[Webhook]) -> Result<()> {
+        if let Some(path) = self.path.as_ref() {
+            // write the whole file at once, so a crash can't leave it half-written
+            let tmp_path = path.with_extension("tmp");
+            std::fs::write(&tmp_path, serde_json::to_vec_pretty(webhooks)?)?;
+            std::fs::rename(tmp_path, path)?;
+        }
+        Ok(())
+    }
+
+    pub async fn list(&self) -> Vec<Webhook> {
+        self.webhooks.lock().await.clone()
+    }
+
+    pub async fn add(&self, new: NewWebhook) -> Result<Webhook> {
+        if !new.url.starts_with("http://") && !new.url.starts_with("https://") {
+            bail!("not an http(s) url: {}", new.url);
+        }
+        if let Some(ticker) = new
+            .chains
+            .iter()
+            .find(|ticker| ChainId::from_ticker(ticker).is_none())
+        {
+            bail!("unknown ticker: {ticker}");
+        }
+
+        let mut webhooks = self.webhooks.lock().await;
+        let webhook = Webhook {
+            id: webhooks
+                .iter()
+                .map(|webhook| webhook.id + 1)
+                .max()
+                .unwrap_or(1),
+            url: new.url,
+            chains: new.chains,
+            events: new.events,
+        };
+        webhooks.push(webhook.clone());
+        self.save(&webhooks)?;
+        info!("Registered webhook {} to {}", webhook.id, webhook.url);
+
+        Ok(webhook)
+    }
+
+    /// Returns `false` if there's no such webhook
+    pub async fn remove(&self, id: u64) -> Result<bool> {
+        let mut webhooks = self.webhooks.lock().await;
+        let len = webhooks.len();
+        webhooks.retain(|webhook| webhook.id != id);
+        if webhooks.len() == len {
+            return Ok(false);
+        }
+        self.save(&webhooks)?;
+        info!("Removed webhook {id}");
+
+        Ok(true)
+    }
+
+    /// Send `events` to the webhooks subscribed to them, in the background
+    pub async fn notify(&self, events: &[AlertEvent]) {
+        for webhook in self.webhooks.lock().await.iter() {
+            for event in events.iter().filter(|event| webhook.matches(event)) {
+                let request = self.client.post(&webhook.url).json(event);
+                let (id, url) = (webhook.id, webhook.url.clone());
+                tokio::spawn(async move {
+                    match request
+                        .send()
+                        .await
+                        .and_then(|resp| resp.error_for_status())
+                    {
+                        Ok(_) => debug!("Delivered alert to webhook {id}"),
+                        Err(e) => warn!("Couldn't deliver alert to webhook {id} at {url}: {e}"),
+                    }
+                });
+            }
+        }
+    }
+}